@@ -0,0 +1,987 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+//
+
+//! A fixed-capacity ring buffer which stores its elements inline.
+
+use core::cmp::Ordering;
+use core::fmt;
+use core::iter::FusedIterator;
+use core::mem::{ManuallyDrop, MaybeUninit};
+use core::ops::{Range, RangeBounds};
+use core::ptr;
+use core::slice;
+
+use super::{binary_search_slices, copy_wrapped, partition_point_slices, slice_range, split_range};
+use super::{wrap_offset, Iter, IterMut, TryPushError};
+
+/// A double-ended queue with a fixed capacity of `N` elements, stored inline
+/// in an array instead of a heap allocation.
+///
+/// `ArrayRingBuf` has the same deque, slice and iterator API as `RingBuf`,
+/// except that it can never grow: `push_back`, `push_front`, `insert`,
+/// `append` and `resize` panic when there is no room, and `try_push_back` and
+/// `try_push_front` hand the value back instead.
+///
+/// `new` is a `const fn`, so an `ArrayRingBuf` can be placed in a `static`.
+///
+/// The methods of `RingBuf` which manage its heap buffer are left out:
+/// `with_capacity`, `reserve` and `shrink_to` and their variants, the growth
+/// and auto shrink policies, and the conversions to and from `Vec`. So are
+/// `set_len` and the spare capacity accessors, which expose the layout of the
+/// buffer. `drain` moves the drained elements out before it returns, rather
+/// than as the iterator advances.
+///
+/// # Example
+///
+/// ```rust
+/// # use ringbuf::ArrayRingBuf;
+/// use std::sync::Mutex;
+///
+/// static EVENTS: Mutex<ArrayRingBuf<u32, 4>> = Mutex::new(ArrayRingBuf::new());
+///
+/// let mut events = EVENTS.lock().unwrap();
+/// events.push_back(1);
+/// events.push_front(0);
+/// assert_eq!(events.as_slices(), (&[0][..], &[1][..]));
+/// ```
+pub struct ArrayRingBuf<T, const N: usize> {
+
+    /// The index of the first element in the buffer.
+    lo: usize,
+
+    /// The number of elements in the buffer.
+    len: usize,
+
+    buf: [MaybeUninit<T>; N],
+}
+
+impl<T, const N: usize> ArrayRingBuf<T, N> {
+
+    /// Creates an empty `ArrayRingBuf`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use ringbuf::ArrayRingBuf;
+    /// const RINGBUF: ArrayRingBuf<i32, 8> = ArrayRingBuf::new();
+    /// assert!(RINGBUF.is_empty());
+    /// ```
+    #[inline]
+    pub const fn new() -> ArrayRingBuf<T, N> {
+        ArrayRingBuf { lo: 0, len: 0, buf: [const { MaybeUninit::uninit() }; N] }
+    }
+
+    /// Retrieves an element in the `ArrayRingBuf` by index.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use ringbuf::ArrayRingBuf;
+    /// let ringbuf = ArrayRingBuf::from([3, 4, 5]);
+    /// assert_eq!(ringbuf.get(1), Some(&4));
+    /// assert_eq!(ringbuf.get(3), None);
+    /// ```
+    #[inline]
+    pub fn get(&self, index: usize) -> Option<&T> {
+        if index < self.len {
+            unsafe { Some(&*self.ptr().add(self.get_offset(index))) }
+        } else {
+            None
+        }
+    }
+
+    /// Retrieves an element in the `ArrayRingBuf` mutably by index.
+    #[inline]
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        if index < self.len {
+            unsafe { Some(&mut *self.mut_ptr().add(self.get_offset(index))) }
+        } else {
+            None
+        }
+    }
+
+    /// Swaps elements at indices `i` and `j`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if either index is out of bounds.
+    pub fn swap(&mut self, i: usize, j: usize) {
+        assert!(i < self.len && j < self.len, "index out of bounds");
+        unsafe {
+            let ptr = self.mut_ptr();
+            ptr::swap(ptr.add(self.get_offset(i)), ptr.add(self.get_offset(j)));
+        }
+    }
+
+    /// Returns the number of elements the `ArrayRingBuf` can hold.
+    #[inline]
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    /// Returns the number of elements in the `ArrayRingBuf`.
+    #[inline]
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the `ArrayRingBuf` contains no elements.
+    #[inline]
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns `true` if the `ArrayRingBuf` is full.
+    #[inline]
+    pub const fn is_full(&self) -> bool {
+        self.len == N
+    }
+
+    /// Returns a front-to-back iterator.
+    pub fn iter(&self) -> Iter<'_, T> {
+        let (slice1, slice2) = self.as_slices();
+        Iter { iter1: slice1.iter(), iter2: slice2.iter() }
+    }
+
+    /// Returns a front-to-back iterator which returns mutable references.
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        let (slice1, slice2) = self.as_mut_slices();
+        IterMut { iter1: slice1.iter_mut(), iter2: slice2.iter_mut() }
+    }
+
+    /// Returns an iterator over references to the elements in the given range
+    /// of the `ArrayRingBuf`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the starting point is greater than the end point or if the
+    /// end point is greater than the length of the `ArrayRingBuf`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use ringbuf::ArrayRingBuf;
+    /// let ringbuf = ArrayRingBuf::from([1, 2, 3, 4]);
+    /// assert!(ringbuf.range(1..3).eq(&[2, 3]));
+    /// ```
+    pub fn range<R>(&self, range: R) -> Iter<'_, T> where R: RangeBounds<usize> {
+        let (slice1, slice2) = self.as_slices();
+        let (range1, range2) = split_range(slice_range(range, self.len), slice1.len());
+        Iter { iter1: slice1[range1].iter(), iter2: slice2[range2].iter() }
+    }
+
+    /// Returns an iterator over mutable references to the elements in the
+    /// given range of the `ArrayRingBuf`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the starting point is greater than the end point or if the
+    /// end point is greater than the length of the `ArrayRingBuf`.
+    pub fn range_mut<R>(&mut self, range: R) -> IterMut<'_, T> where R: RangeBounds<usize> {
+        let range = slice_range(range, self.len);
+        let (slice1, slice2) = self.as_mut_slices();
+        let (range1, range2) = split_range(range, slice1.len());
+        IterMut { iter1: slice1[range1].iter_mut(), iter2: slice2[range2].iter_mut() }
+    }
+
+    /// Removes the elements in the given range from the `ArrayRingBuf` and
+    /// returns them as an iterator.
+    ///
+    /// Unlike `RingBuf::drain`, the elements are moved into the iterator and
+    /// the gap is closed before this returns, so the iterator does not borrow
+    /// the `ArrayRingBuf`. Whichever of the elements before or after the range
+    /// is shorter is moved.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the starting point is greater than the end point or if the
+    /// end point is greater than the length of the `ArrayRingBuf`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use ringbuf::ArrayRingBuf;
+    /// let mut ringbuf = ArrayRingBuf::from([1, 2, 3, 4, 5]);
+    /// assert!(ringbuf.drain(1..3).eq([2, 3]));
+    /// assert!(ringbuf.iter().eq(&[1, 4, 5]));
+    /// ```
+    pub fn drain<R>(&mut self, range: R) -> IntoIter<T, N> where R: RangeBounds<usize> {
+        let Range { start, end } = slice_range(range, self.len);
+        let count = end - start;
+        let mut drained = ArrayRingBuf::new();
+        unsafe {
+            // The second slice starts at offset 0.
+            let (offset, len1, _) = self.slice_ranges();
+            let (range1, range2) = split_range(start..end, len1);
+            let ptr = self.ptr();
+            ptr::copy_nonoverlapping(ptr.add(offset + range1.start),
+                                     drained.mut_ptr(),
+                                     range1.len());
+            ptr::copy_nonoverlapping(ptr.add(range2.start),
+                                     drained.mut_ptr().add(range1.len()),
+                                     range2.len());
+            drained.len = count;
+
+            if start < self.len - end {
+                // Close the gap by moving the front of the ring forward.
+                self.copy_elements(0, count, start);
+                self.lo = self.get_offset(count);
+            } else {
+                // Close the gap by moving the back of the ring back.
+                let tail = self.len - end;
+                self.copy_elements(end, start, tail);
+            }
+        }
+        self.len -= count;
+        drained.into_iter()
+    }
+
+    /// Returns a pair of slices which contain the contents of the
+    /// `ArrayRingBuf`, in order.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use ringbuf::ArrayRingBuf;
+    /// let mut ringbuf: ArrayRingBuf<i32, 4> = ArrayRingBuf::new();
+    /// ringbuf.push_back(2);
+    /// ringbuf.push_front(1);
+    /// assert_eq!(ringbuf.as_slices(), (&[1][..], &[2][..]));
+    /// ```
+    #[inline]
+    pub fn as_slices(&self) -> (&[T], &[T]) {
+        let (offset, len1, len2) = self.slice_ranges();
+        unsafe {
+            let ptr = self.ptr();
+            (slice::from_raw_parts(ptr.add(offset), len1), slice::from_raw_parts(ptr, len2))
+        }
+    }
+
+    /// Returns a pair of mutable slices which contain the contents of the
+    /// `ArrayRingBuf`, in order.
+    #[inline]
+    pub fn as_mut_slices(&mut self) -> (&mut [T], &mut [T]) {
+        let (offset, len1, len2) = self.slice_ranges();
+        unsafe {
+            let ptr = self.mut_ptr();
+            (slice::from_raw_parts_mut(ptr.add(offset), len1),
+             slice::from_raw_parts_mut(ptr, len2))
+        }
+    }
+
+    /// Rearranges the elements so that they do not wrap around the end of
+    /// the buffer, and returns them as a single mutable slice.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use ringbuf::ArrayRingBuf;
+    /// let mut ringbuf: ArrayRingBuf<i32, 4> = ArrayRingBuf::new();
+    /// ringbuf.push_back(2);
+    /// ringbuf.push_front(1);
+    /// assert_eq!(ringbuf.make_contiguous(), &[1, 2]);
+    /// ```
+    pub fn make_contiguous(&mut self) -> &mut [T] {
+        if self.lo > N - self.len {
+            self.buf.rotate_left(self.lo);
+            self.lo = 0;
+        }
+        self.as_mut_slices().0
+    }
+
+    /// Rotates the `ArrayRingBuf` `n` places to the left, so that the element
+    /// at index `n` becomes the first element.
+    ///
+    /// This runs in place in `O(min(n, len - n))` time.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n` is greater than the length of the `ArrayRingBuf`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use ringbuf::ArrayRingBuf;
+    /// let mut ringbuf: ArrayRingBuf<i32, 8> = (1..6).collect();
+    /// ringbuf.rotate_left(2);
+    /// assert!(ringbuf.iter().eq(&[3, 4, 5, 1, 2]));
+    /// ```
+    pub fn rotate_left(&mut self, n: usize) {
+        assert!(n <= self.len, "rotation out of bounds");
+        let k = self.len - n;
+        unsafe {
+            if n <= k {
+                self.rotate_left_inner(n);
+            } else {
+                self.rotate_right_inner(k);
+            }
+        }
+    }
+
+    /// Rotates the `ArrayRingBuf` `n` places to the right, so that the first
+    /// element moves to index `n`.
+    ///
+    /// This runs in place in `O(min(n, len - n))` time.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n` is greater than the length of the `ArrayRingBuf`.
+    pub fn rotate_right(&mut self, n: usize) {
+        assert!(n <= self.len, "rotation out of bounds");
+        let k = self.len - n;
+        unsafe {
+            if n <= k {
+                self.rotate_right_inner(n);
+            } else {
+                self.rotate_left_inner(k);
+            }
+        }
+    }
+
+    /// Binary searches this sorted `ArrayRingBuf` for a given element.
+    ///
+    /// If the value is found then `Ok` is returned, containing the index of
+    /// the matching element. If the value is not found then `Err` is
+    /// returned, containing the index where a matching element could be
+    /// inserted while maintaining sorted order.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use ringbuf::ArrayRingBuf;
+    /// let ringbuf = ArrayRingBuf::from([1, 3, 5, 7]);
+    /// assert_eq!(ringbuf.binary_search(&5), Ok(2));
+    /// assert_eq!(ringbuf.binary_search(&4), Err(2));
+    /// ```
+    pub fn binary_search(&self, x: &T) -> Result<usize, usize> where T: Ord {
+        self.binary_search_by(|elem| elem.cmp(x))
+    }
+
+    /// Binary searches this sorted `ArrayRingBuf` with a comparator function.
+    ///
+    /// The comparator function should return the ordering of its argument
+    /// relative to the desired target.
+    pub fn binary_search_by<'a, F>(&'a self, f: F) -> Result<usize, usize>
+            where F: FnMut(&'a T) -> Ordering {
+        let (slice1, slice2) = self.as_slices();
+        binary_search_slices(slice1, slice2, f)
+    }
+
+    /// Binary searches this sorted `ArrayRingBuf` with a key extraction
+    /// function.
+    pub fn binary_search_by_key<'a, B, F>(&'a self, b: &B, mut f: F) -> Result<usize, usize>
+            where F: FnMut(&'a T) -> B, B: Ord {
+        self.binary_search_by(|elem| f(elem).cmp(b))
+    }
+
+    /// Returns the index of the partition point of an `ArrayRingBuf`
+    /// partitioned according to the given predicate.
+    ///
+    /// The `ArrayRingBuf` is assumed to be partitioned such that all elements
+    /// for which the predicate returns `true` precede all elements for which
+    /// it returns `false`.
+    pub fn partition_point<P>(&self, pred: P) -> usize where P: FnMut(&T) -> bool {
+        let (slice1, slice2) = self.as_slices();
+        partition_point_slices(slice1, slice2, pred)
+    }
+
+    /// Returns `true` if the `ArrayRingBuf` contains an element equal to the
+    /// given value.
+    pub fn contains(&self, x: &T) -> bool where T: PartialEq {
+        let (slice1, slice2) = self.as_slices();
+        slice1.contains(x) || slice2.contains(x)
+    }
+
+    /// Provides a reference to the front element, or `None` if the
+    /// `ArrayRingBuf` is empty.
+    #[inline]
+    pub fn front(&self) -> Option<&T> {
+        self.get(0)
+    }
+
+    /// Provides a mutable reference to the front element, or `None` if the
+    /// `ArrayRingBuf` is empty.
+    #[inline]
+    pub fn front_mut(&mut self) -> Option<&mut T> {
+        self.get_mut(0)
+    }
+
+    /// Provides a reference to the back element, or `None` if the
+    /// `ArrayRingBuf` is empty.
+    #[inline]
+    pub fn back(&self) -> Option<&T> {
+        self.len.checked_sub(1).and_then(|index| self.get(index))
+    }
+
+    /// Provides a mutable reference to the back element, or `None` if the
+    /// `ArrayRingBuf` is empty.
+    #[inline]
+    pub fn back_mut(&mut self) -> Option<&mut T> {
+        self.len.checked_sub(1).and_then(move |index| self.get_mut(index))
+    }
+
+    /// Prepends an element to the `ArrayRingBuf`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `ArrayRingBuf` is full.
+    pub fn push_front(&mut self, value: T) {
+        if self.try_push_front(value).is_err() {
+            panic!("ArrayRingBuf is full");
+        }
+    }
+
+    /// Prepends an element to the `ArrayRingBuf`, or returns it in an error
+    /// if the `ArrayRingBuf` is full.
+    pub fn try_push_front(&mut self, value: T) -> Result<(), TryPushError<T>> {
+        if self.is_full() { return Err(TryPushError::Full(value)) }
+        self.lo = if self.lo == 0 { N - 1 } else { self.lo - 1 };
+        self.len += 1;
+        self.buf[self.lo].write(value);
+        Ok(())
+    }
+
+    /// Removes the first element and returns it, or `None` if the
+    /// `ArrayRingBuf` is empty.
+    pub fn pop_front(&mut self) -> Option<T> {
+        if self.is_empty() { return None }
+        let value = unsafe { self.buf[self.lo].assume_init_read() };
+        self.lo = self.get_offset(1);
+        self.len -= 1;
+        Some(value)
+    }
+
+    /// Appends an element to the back of the `ArrayRingBuf`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `ArrayRingBuf` is full.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use ringbuf::ArrayRingBuf;
+    /// let mut ringbuf: ArrayRingBuf<i32, 2> = ArrayRingBuf::new();
+    /// ringbuf.push_back(1);
+    /// ringbuf.push_back(3);
+    /// assert_eq!(ringbuf.back(), Some(&3));
+    /// assert!(ringbuf.try_push_back(5).is_err());
+    /// ```
+    pub fn push_back(&mut self, value: T) {
+        if self.try_push_back(value).is_err() {
+            panic!("ArrayRingBuf is full");
+        }
+    }
+
+    /// Appends an element to the back of the `ArrayRingBuf`, or returns it
+    /// in an error if the `ArrayRingBuf` is full.
+    pub fn try_push_back(&mut self, value: T) -> Result<(), TryPushError<T>> {
+        if self.is_full() { return Err(TryPushError::Full(value)) }
+        let offset = self.get_offset(self.len);
+        self.buf[offset].write(value);
+        self.len += 1;
+        Ok(())
+    }
+
+    /// Removes the last element and returns it, or `None` if the
+    /// `ArrayRingBuf` is empty.
+    pub fn pop_back(&mut self) -> Option<T> {
+        if self.is_empty() { return None }
+        self.len -= 1;
+        let offset = self.get_offset(self.len);
+        unsafe { Some(self.buf[offset].assume_init_read()) }
+    }
+
+    /// Removes the element at `index` and returns it, replacing it with the
+    /// first element. Returns `None` if `index` is out of bounds.
+    ///
+    /// This does not preserve ordering, but is O(1).
+    pub fn swap_remove_front(&mut self, index: usize) -> Option<T> {
+        if index >= self.len { return None }
+        if index != 0 { self.swap(index, 0); }
+        self.pop_front()
+    }
+
+    /// Removes the element at `index` and returns it, replacing it with the
+    /// last element. Returns `None` if `index` is out of bounds.
+    ///
+    /// This does not preserve ordering, but is O(1).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use ringbuf::ArrayRingBuf;
+    /// let mut ringbuf = ArrayRingBuf::from([1, 2, 3]);
+    /// assert_eq!(ringbuf.swap_remove_back(0), Some(1));
+    /// assert!(ringbuf.iter().eq(&[3, 2]));
+    /// ```
+    pub fn swap_remove_back(&mut self, index: usize) -> Option<T> {
+        if index >= self.len { return None }
+        let last = self.len - 1;
+        if index != last { self.swap(index, last); }
+        self.pop_back()
+    }
+
+    /// Inserts an element at `index` within the `ArrayRingBuf`, shifting
+    /// whichever side of `index` is shorter.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is greater than the length, or if the
+    /// `ArrayRingBuf` is full.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use ringbuf::ArrayRingBuf;
+    /// let mut ringbuf: ArrayRingBuf<i32, 4> = [1, 3, 4].into_iter().collect();
+    /// ringbuf.insert(1, 2);
+    /// assert!(ringbuf.iter().eq(&[1, 2, 3, 4]));
+    /// ```
+    pub fn insert(&mut self, index: usize, value: T) {
+        assert!(index <= self.len, "index out of bounds");
+        assert!(!self.is_full(), "ArrayRingBuf is full");
+
+        unsafe {
+            if index < self.len - index {
+                // Move the front of the ring back by one slot.
+                self.lo = if self.lo == 0 { N - 1 } else { self.lo - 1 };
+                self.copy_elements(1, 0, index);
+            } else {
+                // Move the back of the ring forward by one slot.
+                let count = self.len - index;
+                self.copy_elements(index, index + 1, count);
+            }
+            let offset = self.get_offset(index);
+            ptr::write(self.mut_ptr().add(offset), value);
+        }
+        self.len += 1;
+    }
+
+    /// Removes and returns the element at `index`, shifting whichever side
+    /// of `index` is shorter. Returns `None` if `index` is out of bounds.
+    pub fn remove(&mut self, index: usize) -> Option<T> {
+        if index >= self.len { return None }
+
+        unsafe {
+            let offset = self.get_offset(index);
+            let value = ptr::read(self.ptr().add(offset));
+            if index < self.len - index - 1 {
+                // Close the gap by moving the front of the ring forward.
+                self.copy_elements(0, 1, index);
+                self.lo = self.get_offset(1);
+            } else {
+                // Close the gap by moving the back of the ring back.
+                let count = self.len - index - 1;
+                self.copy_elements(index + 1, index, count);
+            }
+            self.len -= 1;
+            Some(value)
+        }
+    }
+
+    /// Splits the `ArrayRingBuf` into two at the given index. Returns a new
+    /// `ArrayRingBuf` containing the elements from `at` onwards, and leaves
+    /// the elements before `at` in `self`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `at` is greater than the length.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use ringbuf::ArrayRingBuf;
+    /// let mut ringbuf = ArrayRingBuf::from([1, 2, 3]);
+    /// let back = ringbuf.split_off(1);
+    /// assert!(ringbuf.iter().eq(&[1]));
+    /// assert!(back.iter().eq(&[2, 3]));
+    /// ```
+    pub fn split_off(&mut self, at: usize) -> ArrayRingBuf<T, N> {
+        self.drain(at..).ringbuf
+    }
+
+    /// Moves all the elements of `other` to the back of `self`, leaving
+    /// `other` empty.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the elements do not fit in `self`, in which case neither
+    /// `ArrayRingBuf` is changed.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use ringbuf::ArrayRingBuf;
+    /// let mut ringbuf: ArrayRingBuf<i32, 4> = [1, 2].into_iter().collect();
+    /// let mut other: ArrayRingBuf<i32, 2> = ArrayRingBuf::from([3, 4]);
+    /// ringbuf.append(&mut other);
+    /// assert!(ringbuf.iter().eq(&[1, 2, 3, 4]));
+    /// assert!(other.is_empty());
+    /// ```
+    pub fn append<const M: usize>(&mut self, other: &mut ArrayRingBuf<T, M>) {
+        assert!(other.len <= N - self.len, "ArrayRingBuf is full");
+        while let Some(value) = other.pop_front() {
+            self.push_back(value);
+        }
+    }
+
+    /// Shortens the `ArrayRingBuf`, dropping excess elements from the back.
+    pub fn truncate(&mut self, len: usize) {
+        while self.len > len {
+            self.pop_back();
+        }
+    }
+
+    /// Clears the `ArrayRingBuf`, removing all values.
+    pub fn clear(&mut self) {
+        self.truncate(0);
+    }
+
+    /// Retains only the elements specified by the predicate.
+    pub fn retain<F>(&mut self, mut f: F) where F: FnMut(&T) -> bool {
+        self.retain_mut(|elem| f(elem))
+    }
+
+    /// Retains only the elements specified by the predicate, passing a
+    /// mutable reference to it.
+    pub fn retain_mut<F>(&mut self, mut f: F) where F: FnMut(&mut T) -> bool {
+        let len = self.len;
+        let mut deleted = 0;
+        for i in 0..len {
+            if !f(&mut self[i]) {
+                deleted += 1;
+            } else if deleted > 0 {
+                self.swap(i - deleted, i);
+            }
+        }
+        if deleted > 0 {
+            self.truncate(len - deleted);
+        }
+    }
+
+    /// Modifies the `ArrayRingBuf` in-place so that `len()` is equal to
+    /// `new_len`, either by removing excess elements from the back or by
+    /// appending elements generated by calling `generator` to the back.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `new_len` is greater than the capacity.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use ringbuf::ArrayRingBuf;
+    /// let mut ringbuf: ArrayRingBuf<i32, 4> = [1, 2].into_iter().collect();
+    /// let mut next = 3;
+    /// ringbuf.resize_with(4, || { next += 1; next - 1 });
+    /// assert!(ringbuf.iter().eq(&[1, 2, 3, 4]));
+    /// ```
+    pub fn resize_with<F>(&mut self, new_len: usize, mut generator: F) where F: FnMut() -> T {
+        assert!(new_len <= N, "ArrayRingBuf is full");
+        while self.len < new_len {
+            self.push_back(generator());
+        }
+        self.truncate(new_len);
+    }
+
+    /// Modifies the `ArrayRingBuf` in-place so that `len()` is equal to
+    /// `new_len`, either by removing excess elements from the back or by
+    /// appending clones of `value` to the back.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `new_len` is greater than the capacity.
+    pub fn resize(&mut self, new_len: usize, value: T) where T: Clone {
+        self.resize_with(new_len, || value.clone());
+    }
+}
+
+impl<T, const N: usize> ArrayRingBuf<T, N> {
+
+    #[inline]
+    fn ptr(&self) -> *const T {
+        self.buf.as_ptr() as *const T
+    }
+
+    #[inline]
+    fn mut_ptr(&mut self) -> *mut T {
+        self.buf.as_mut_ptr() as *mut T
+    }
+
+    /// Returns the offset and length of the first slice, and the length of
+    /// the second slice, which starts at offset 0.
+    #[inline]
+    fn slice_ranges(&self) -> (usize, usize, usize) {
+        if self.lo > N - self.len {
+            (self.lo, N - self.lo, self.len - (N - self.lo))
+        } else {
+            (self.lo, self.len, 0)
+        }
+    }
+
+    /// Return the offset of the given index in the buffer.
+    #[inline]
+    fn get_offset(&self, index: usize) -> usize {
+        wrap_offset(self.lo, N, index)
+    }
+
+    /// Move `count` elements starting at index `src` to index `dst`, as
+    /// `RingBuf::copy_elements` does.
+    unsafe fn copy_elements(&mut self, src: usize, dst: usize, count: usize) {
+        copy_wrapped(self.mut_ptr(), N, self.lo, src, dst, count);
+    }
+
+    /// Rotate the ring `n` places to the left by moving the first `n`
+    /// elements to the back, as `RingBuf::rotate_left_inner` does.
+    unsafe fn rotate_left_inner(&mut self, n: usize) {
+        self.lo = self.get_offset(n);
+        self.copy_elements(N - n, self.len - n, n);
+    }
+
+    /// Rotate the ring `n` places to the right by moving the last `n`
+    /// elements to the front, as `RingBuf::rotate_right_inner` does.
+    unsafe fn rotate_right_inner(&mut self, n: usize) {
+        self.copy_elements(self.len - n, N - n, n);
+        self.lo = self.get_offset(N - n);
+    }
+}
+
+deque_impls!(ArrayRingBuf<T, const N: usize>);
+
+impl<T: Clone, const N: usize> Clone for ArrayRingBuf<T, N> {
+    fn clone(&self) -> ArrayRingBuf<T, N> {
+        self.iter().cloned().collect()
+    }
+}
+
+impl<T, const N: usize> Drop for ArrayRingBuf<T, N> {
+    fn drop(&mut self) {
+        unsafe {
+            let (slice1, slice2) = self.as_mut_slices();
+            let (slice1, slice2) = (slice1 as *mut [T], slice2 as *mut [T]);
+            ptr::drop_in_place(slice1);
+            ptr::drop_in_place(slice2);
+        }
+    }
+}
+
+/// Creates a full `ArrayRingBuf` from an array.
+impl<T, const N: usize> From<[T; N]> for ArrayRingBuf<T, N> {
+    fn from(array: [T; N]) -> ArrayRingBuf<T, N> {
+        let array = ManuallyDrop::new(array);
+        let mut ringbuf = ArrayRingBuf::new();
+        unsafe {
+            ptr::copy_nonoverlapping(array.as_ptr(), ringbuf.mut_ptr(), N);
+        }
+        ringbuf.len = N;
+        ringbuf
+    }
+}
+
+/// Appends the elements of the iterator.
+///
+/// # Panics
+///
+/// Panics if the `ArrayRingBuf` becomes full, so collecting more than `N`
+/// elements into an `ArrayRingBuf` panics too.
+impl<T, const N: usize> Extend<T> for ArrayRingBuf<T, N> {
+    fn extend<I: IntoIterator<Item=T>>(&mut self, iterable: I) {
+        for value in iterable {
+            self.push_back(value);
+        }
+    }
+}
+
+impl<T, const N: usize> IntoIterator for ArrayRingBuf<T, N> {
+    type Item = T;
+    type IntoIter = IntoIter<T, N>;
+
+    fn into_iter(self) -> IntoIter<T, N> {
+        IntoIter { ringbuf: self }
+    }
+}
+
+/// An iterator that moves out of an `ArrayRingBuf`.
+#[derive(Clone)]
+pub struct IntoIter<T, const N: usize> {
+    ringbuf: ArrayRingBuf<T, N>,
+}
+
+impl<T, const N: usize> Iterator for IntoIter<T, N> {
+    type Item = T;
+
+    #[inline]
+    fn next(&mut self) -> Option<T> {
+        self.ringbuf.pop_front()
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.ringbuf.len, Some(self.ringbuf.len))
+    }
+}
+
+impl<T, const N: usize> DoubleEndedIterator for IntoIter<T, N> {
+    #[inline]
+    fn next_back(&mut self) -> Option<T> {
+        self.ringbuf.pop_back()
+    }
+}
+
+impl<T, const N: usize> ExactSizeIterator for IntoIter<T, N> {}
+
+impl<T, const N: usize> FusedIterator for IntoIter<T, N> {}
+
+impl<T: fmt::Debug, const N: usize> fmt::Debug for IntoIter<T, N> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("IntoIter").field(&self.ringbuf).finish()
+    }
+}
+
+#[cfg(test)]
+mod checks {
+    use std::collections::VecDeque;
+    use std::rc::Rc;
+
+    use super::ArrayRingBuf;
+    use crate::model::{self, Model};
+
+    impl Model for ArrayRingBuf<i32, 5> {
+        fn apply(&mut self, model: &mut VecDeque<i32>, op: u8, value: i32, index: usize) -> bool {
+            let n = value.unsigned_abs() as usize;
+            match op % 12 {
+                0 => if model.len() < 5 {
+                    self.insert(index, value);
+                    model.insert(index, value);
+                },
+                1 => return self.remove(index) == model.remove(index),
+                2 => {
+                    self.retain(|&x| x % 3 != 0);
+                    model.retain(|&x| x % 3 != 0);
+                }
+                3 => return self.make_contiguous() == model.make_contiguous(),
+                4 => {
+                    self.rotate_left(index);
+                    model.rotate_left(index);
+                }
+                5 => {
+                    self.rotate_right(index);
+                    model.rotate_right(index);
+                }
+                6 => return self.swap_remove_front(index) == model.swap_remove_front(index),
+                7 => return self.swap_remove_back(index) == model.swap_remove_back(index),
+                8 => {
+                    let end = index + n % (model.len() - index + 1);
+                    return self.drain(index..end).eq(model.drain(index..end));
+                }
+                9 => {
+                    let mut back = self.split_off(index);
+                    let equal = self.iter().eq(model.range(..index))
+                        && back.iter().eq(model.range(index..));
+                    self.append(&mut back);
+                    return equal && back.is_empty();
+                }
+                10 => {
+                    self.resize(n % 6, value);
+                    model.resize(n % 6, value);
+                }
+                _ => {
+                    for x in self.range_mut(index..) { *x = x.wrapping_add(1); }
+                    for x in model.range_mut(index..) { *x = x.wrapping_add(1); }
+                    return self.range(index..).eq(model.range(index..));
+                }
+            }
+            true
+        }
+
+        fn check(&self, model: &VecDeque<i32>) -> bool {
+            let (slice1, slice2) = self.as_slices();
+            slice1.iter().chain(slice2).eq(model.iter())
+        }
+    }
+
+    #[test]
+    fn check_model() {
+        model::check_model::<ArrayRingBuf<i32, 5>>();
+    }
+
+    #[test]
+    fn test_drop_items() {
+        model::check_drop_items::<ArrayRingBuf<Rc<()>, 4>>(4);
+    }
+
+    #[test]
+    #[should_panic(expected = "ArrayRingBuf is full")]
+    fn test_push_full() {
+        let mut ringbuf = ArrayRingBuf::from([1, 2]);
+        ringbuf.push_back(3);
+    }
+
+    #[test]
+    fn test_insert_remove_wrapped() {
+        let mut ringbuf: ArrayRingBuf<i32, 8> = ArrayRingBuf::new();
+        ringbuf.extend([3, 4, 5, 6]);
+        ringbuf.push_front(2);
+        ringbuf.push_front(1);
+        ringbuf.insert(1, 10);
+        ringbuf.insert(6, 20);
+        assert!(ringbuf.iter().eq(&[1, 10, 2, 3, 4, 5, 20, 6]));
+        assert_eq!(ringbuf.remove(2), Some(2));
+        assert_eq!(ringbuf.remove(5), Some(20));
+        assert!(ringbuf.iter().eq(&[1, 10, 3, 4, 5, 6]));
+    }
+
+    #[test]
+    #[should_panic(expected = "ArrayRingBuf is full")]
+    fn test_insert_full() {
+        let mut ringbuf = ArrayRingBuf::from([1, 2]);
+        ringbuf.insert(1, 3);
+    }
+
+    #[test]
+    fn test_search_wrapped() {
+        let mut ringbuf: ArrayRingBuf<i32, 6> = ArrayRingBuf::new();
+        ringbuf.extend([5, 7, 9]);
+        ringbuf.push_front(3);
+        ringbuf.push_front(1);
+        assert_eq!(ringbuf.as_slices(), (&[1, 3][..], &[5, 7, 9][..]));
+        for (i, x) in [1, 3, 5, 7, 9].iter().enumerate() {
+            assert_eq!(ringbuf.binary_search(x), Ok(i));
+            assert_eq!(ringbuf.binary_search(&(x + 1)), Err(i + 1));
+            assert_eq!(ringbuf.partition_point(|y| y < x), i);
+        }
+        assert_eq!(ringbuf.binary_search(&0), Err(0));
+        assert_eq!(ringbuf.binary_search_by_key(&14, |x| x * 2), Ok(3));
+    }
+
+    #[test]
+    #[should_panic(expected = "ArrayRingBuf is full")]
+    fn test_append_full() {
+        let mut ringbuf: ArrayRingBuf<i32, 3> = [1, 2].into_iter().collect();
+        let mut other = ArrayRingBuf::from([3, 4]);
+        ringbuf.append(&mut other);
+    }
+
+    #[test]
+    fn test_zero_capacity() {
+        let mut ringbuf: ArrayRingBuf<i32, 0> = ArrayRingBuf::new();
+        assert!(ringbuf.is_full());
+        assert!(ringbuf.try_push_back(1).is_err());
+        assert!(ringbuf.try_push_front(1).is_err());
+        assert_eq!(ringbuf.pop_front(), None);
+        assert_eq!(ringbuf.make_contiguous(), &[]);
+        ringbuf.rotate_left(0);
+        ringbuf.rotate_right(0);
+        assert_eq!(ringbuf.drain(..).count(), 0);
+    }
+
+    #[test]
+    fn test_zero_sized() {
+        model::check_zero_sized::<ArrayRingBuf<(), 3>>(3);
+    }
+}
@@ -0,0 +1,630 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+//
+
+//! An asynchronous bounded channel backed by a `RingBuf`.
+
+use std::fmt;
+use std::future::Future;
+use std::mem;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::task::{Context, Poll, Waker};
+
+use futures_core::{FusedStream, Stream};
+use futures_sink::Sink;
+
+use super::error::{SendError, TryRecvError, TrySendError};
+use super::RingBuf;
+
+/// Creates an asynchronous bounded channel which holds at most `capacity`
+/// values.
+///
+/// Sending to a full channel and receiving from an empty one return
+/// `Pending`, and park the task's `Waker` until the channel changes. The
+/// channel does not depend on any particular executor. Both halves can be
+/// cloned, and the channel is disconnected once every `AsyncSender` or every
+/// `AsyncReceiver` has been dropped.
+///
+/// `AsyncSender` implements `Sink`, and `AsyncReceiver` implements `Stream`.
+pub fn async_channel<T>(capacity: usize) -> (AsyncSender<T>, AsyncReceiver<T>) {
+    let shared = Arc::new(Shared {
+        state: Mutex::new(State {
+            buffer: RingBuf::with_capacity(capacity),
+            capacity,
+            reserved: 0,
+            senders: 1,
+            receivers: 1,
+            send_wakers: RingBuf::new(),
+            recv_wakers: RingBuf::new(),
+        }),
+    });
+    (AsyncSender { shared: shared.clone(), reserved: false }, AsyncReceiver { shared })
+}
+
+struct State<T> {
+    buffer: RingBuf<T>,
+
+    /// The maximum number of values in the buffer.
+    capacity: usize,
+
+    /// The number of slots reserved by `Sink::poll_ready` for a following
+    /// `Sink::start_send`.
+    reserved: usize,
+
+    senders: usize,
+    receivers: usize,
+
+    /// The tasks waiting for the channel to have room.
+    send_wakers: RingBuf<Waker>,
+
+    /// The tasks waiting for the channel to have values.
+    recv_wakers: RingBuf<Waker>,
+}
+
+impl<T> State<T> {
+
+    #[inline]
+    fn is_full(&self) -> bool {
+        self.buffer.len() + self.reserved >= self.capacity
+    }
+}
+
+/// Parks `waker` in `wakers`, unless it would wake the same task as a waker
+/// which is already parked.
+fn park(wakers: &mut RingBuf<Waker>, waker: &Waker) {
+    if !wakers.iter().any(|parked| parked.will_wake(waker)) {
+        wakers.push_back(waker.clone());
+    }
+}
+
+/// Wakes every task in `wakers`, after the lock has been released.
+fn wake_all(wakers: RingBuf<Waker>) {
+    for waker in wakers {
+        waker.wake();
+    }
+}
+
+struct Shared<T> {
+    state: Mutex<State<T>>,
+}
+
+impl<T> Shared<T> {
+
+    /// Locks the state.
+    ///
+    /// The state is never left inconsistent by a panic, so poisoning is
+    /// ignored.
+    fn lock(&self) -> MutexGuard<'_, State<T>> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Pushes a value onto the channel, and wakes the waiting receivers.
+    fn push(&self, mut state: MutexGuard<'_, State<T>>, value: T) {
+        state.buffer.push_back(value);
+        let wakers = mem::take(&mut state.recv_wakers);
+        drop(state);
+        wake_all(wakers);
+    }
+}
+
+/// The sending half of an asynchronous channel.
+///
+/// Created by `async_channel`.
+pub struct AsyncSender<T> {
+    shared: Arc<Shared<T>>,
+
+    /// Whether this sender holds a slot reserved by `Sink::poll_ready`.
+    reserved: bool,
+}
+
+impl<T> AsyncSender<T> {
+
+    /// Returns a future which sends `value`, waiting while the channel is
+    /// full.
+    ///
+    /// The future resolves to an error holding the value if every
+    /// `AsyncReceiver` has been dropped.
+    pub fn send(&self, value: T) -> SendFuture<'_, T> {
+        SendFuture { sender: self, value: Some(value) }
+    }
+
+    /// Sends a value if the channel has room for it, without waiting.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use ringbuf::TrySendError;
+    /// let (sender, receiver) = ringbuf::async_channel(1);
+    /// assert_eq!(sender.try_send(1), Ok(()));
+    /// assert_eq!(sender.try_send(2), Err(TrySendError::Full(2)));
+    /// drop(receiver);
+    /// assert_eq!(sender.try_send(3), Err(TrySendError::Disconnected(3)));
+    /// ```
+    pub fn try_send(&self, value: T) -> Result<(), TrySendError<T>> {
+        let state = self.shared.lock();
+        if state.receivers == 0 { return Err(TrySendError::Disconnected(value)) }
+        if state.is_full() { return Err(TrySendError::Full(value)) }
+        self.shared.push(state, value);
+        Ok(())
+    }
+
+    /// Returns the number of values in the channel.
+    pub fn len(&self) -> usize {
+        self.shared.lock().buffer.len()
+    }
+
+    /// Returns `true` if the channel is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the maximum number of values the channel holds.
+    pub fn capacity(&self) -> usize {
+        self.shared.lock().capacity
+    }
+
+    /// Returns `true` if every `AsyncReceiver` has been dropped.
+    pub fn is_closed(&self) -> bool {
+        self.shared.lock().receivers == 0
+    }
+
+    /// Releases the slot reserved by `Sink::poll_ready`, if any.
+    fn release(&mut self) {
+        if self.reserved {
+            self.reserved = false;
+            let mut state = self.shared.lock();
+            state.reserved -= 1;
+            let wakers = mem::take(&mut state.send_wakers);
+            drop(state);
+            wake_all(wakers);
+        }
+    }
+}
+
+impl<T> Clone for AsyncSender<T> {
+    fn clone(&self) -> AsyncSender<T> {
+        self.shared.lock().senders += 1;
+        AsyncSender { shared: self.shared.clone(), reserved: false }
+    }
+}
+
+impl<T> Drop for AsyncSender<T> {
+    fn drop(&mut self) {
+        self.release();
+        let mut state = self.shared.lock();
+        state.senders -= 1;
+        if state.senders == 0 {
+            let wakers = mem::take(&mut state.recv_wakers);
+            drop(state);
+            wake_all(wakers);
+        }
+    }
+}
+
+impl<T> fmt::Debug for AsyncSender<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("AsyncSender").finish_non_exhaustive()
+    }
+}
+
+impl<T> Sink<T> for AsyncSender<T> {
+    type Error = SendError<()>;
+
+    /// Reserves a slot for the next `start_send`, waiting while the channel
+    /// is full.
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), SendError<()>>> {
+        let this = self.get_mut();
+        let mut state = this.shared.lock();
+        if state.receivers == 0 { return Poll::Ready(Err(SendError(()))) }
+        if this.reserved { return Poll::Ready(Ok(())) }
+        if state.is_full() {
+            park(&mut state.send_wakers, cx.waker());
+            return Poll::Pending;
+        }
+        state.reserved += 1;
+        this.reserved = true;
+        Poll::Ready(Ok(()))
+    }
+
+    fn start_send(self: Pin<&mut Self>, value: T) -> Result<(), SendError<()>> {
+        let this = self.get_mut();
+        assert!(this.reserved, "start_send called without a successful poll_ready");
+        this.reserved = false;
+        let mut state = this.shared.lock();
+        state.reserved -= 1;
+        if state.receivers == 0 { return Err(SendError(())) }
+        this.shared.push(state, value);
+        Ok(())
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context) -> Poll<Result<(), SendError<()>>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _cx: &mut Context) -> Poll<Result<(), SendError<()>>> {
+        self.get_mut().release();
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// A future which sends a value on an asynchronous channel.
+///
+/// Created by `AsyncSender::send`.
+#[must_use = "futures do nothing unless polled"]
+pub struct SendFuture<'a, T> {
+    sender: &'a AsyncSender<T>,
+    value: Option<T>,
+}
+
+// The value is never pinned.
+impl<'a, T> Unpin for SendFuture<'a, T> {}
+
+impl<'a, T> Future for SendFuture<'a, T> {
+    type Output = Result<(), SendError<T>>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), SendError<T>>> {
+        let this = self.get_mut();
+        let value = this.value.take().expect("SendFuture polled after completion");
+        let mut state = this.sender.shared.lock();
+        if state.receivers == 0 { return Poll::Ready(Err(SendError(value))) }
+        if state.is_full() {
+            park(&mut state.send_wakers, cx.waker());
+            this.value = Some(value);
+            return Poll::Pending;
+        }
+        this.sender.shared.push(state, value);
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<'a, T> fmt::Debug for SendFuture<'a, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SendFuture").finish_non_exhaustive()
+    }
+}
+
+/// The receiving half of an asynchronous channel.
+///
+/// Created by `async_channel`.
+pub struct AsyncReceiver<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> AsyncReceiver<T> {
+
+    /// Returns a future which receives a value, waiting while the channel is
+    /// empty.
+    ///
+    /// The future resolves to `None` if the channel is empty and every
+    /// `AsyncSender` has been dropped.
+    pub fn recv(&self) -> RecvFuture<'_, T> {
+        RecvFuture { receiver: self }
+    }
+
+    /// Receives a value if there is one, without waiting.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use ringbuf::TryRecvError;
+    /// let (sender, receiver) = ringbuf::async_channel(1);
+    /// sender.try_send(1).unwrap();
+    /// assert_eq!(receiver.try_recv(), Ok(1));
+    /// assert_eq!(receiver.try_recv(), Err(TryRecvError::Empty));
+    /// drop(sender);
+    /// assert_eq!(receiver.try_recv(), Err(TryRecvError::Disconnected));
+    /// ```
+    pub fn try_recv(&self) -> Result<T, TryRecvError> {
+        let state = self.shared.lock();
+        if !state.buffer.is_empty() { return Ok(self.pop(state)) }
+        if state.senders == 0 { Err(TryRecvError::Disconnected) } else { Err(TryRecvError::Empty) }
+    }
+
+    /// Polls for a value, parking the task's waker if the channel is empty.
+    ///
+    /// Returns `Ready(None)` if the channel is empty and every `AsyncSender`
+    /// has been dropped.
+    pub fn poll_recv(&self, cx: &mut Context) -> Poll<Option<T>> {
+        let mut state = self.shared.lock();
+        if !state.buffer.is_empty() { return Poll::Ready(Some(self.pop(state))) }
+        if state.senders == 0 { return Poll::Ready(None) }
+        park(&mut state.recv_wakers, cx.waker());
+        Poll::Pending
+    }
+
+    /// Pops a value from the channel, and wakes the waiting senders.
+    fn pop(&self, mut state: MutexGuard<'_, State<T>>) -> T {
+        let value = state.buffer.pop_front().unwrap();
+        let wakers = mem::take(&mut state.send_wakers);
+        drop(state);
+        wake_all(wakers);
+        value
+    }
+
+    /// Returns the number of values in the channel.
+    pub fn len(&self) -> usize {
+        self.shared.lock().buffer.len()
+    }
+
+    /// Returns `true` if the channel is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the maximum number of values the channel holds.
+    pub fn capacity(&self) -> usize {
+        self.shared.lock().capacity
+    }
+}
+
+impl<T> Clone for AsyncReceiver<T> {
+    fn clone(&self) -> AsyncReceiver<T> {
+        self.shared.lock().receivers += 1;
+        AsyncReceiver { shared: self.shared.clone() }
+    }
+}
+
+impl<T> Drop for AsyncReceiver<T> {
+    fn drop(&mut self) {
+        let mut state = self.shared.lock();
+        state.receivers -= 1;
+        if state.receivers == 0 {
+            let wakers = mem::take(&mut state.send_wakers);
+            drop(state);
+            wake_all(wakers);
+        }
+    }
+}
+
+impl<T> fmt::Debug for AsyncReceiver<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("AsyncReceiver").finish_non_exhaustive()
+    }
+}
+
+impl<T> Stream for AsyncReceiver<T> {
+    type Item = T;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<T>> {
+        self.poll_recv(cx)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let state = self.shared.lock();
+        let upper = if state.senders == 0 { Some(state.buffer.len()) } else { None };
+        (state.buffer.len(), upper)
+    }
+}
+
+impl<T> FusedStream for AsyncReceiver<T> {
+    fn is_terminated(&self) -> bool {
+        let state = self.shared.lock();
+        state.senders == 0 && state.buffer.is_empty()
+    }
+}
+
+/// A future which receives a value from an asynchronous channel.
+///
+/// Created by `AsyncReceiver::recv`.
+#[must_use = "futures do nothing unless polled"]
+#[derive(Debug)]
+pub struct RecvFuture<'a, T> {
+    receiver: &'a AsyncReceiver<T>,
+}
+
+impl<'a, T> Future for RecvFuture<'a, T> {
+    type Output = Option<T>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<T>> {
+        self.receiver.poll_recv(cx)
+    }
+}
+
+#[cfg(test)]
+mod checks {
+    use std::future::{poll_fn, Future};
+    use std::pin::{pin, Pin};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+    use std::task::{Context, Poll, Wake, Waker};
+    use std::thread::{self, Thread};
+
+    use futures_core::{FusedStream, Stream};
+    use futures_sink::Sink;
+
+    use crate::{SendError, TrySendError};
+
+    use super::async_channel;
+
+    /// Wakes a thread blocked in `block_on`.
+    struct ThreadWaker(Thread);
+
+    impl Wake for ThreadWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    /// A minimal executor, which polls the future on the current thread and
+    /// parks the thread while the future is pending.
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let mut future = pin!(future);
+        let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+        let mut cx = Context::from_waker(&waker);
+        loop {
+            match future.as_mut().poll(&mut cx) {
+                Poll::Ready(output) => return output,
+                Poll::Pending => thread::park(),
+            }
+        }
+    }
+
+    /// Counts how many times it has been woken.
+    #[derive(Default)]
+    struct CountingWaker(AtomicUsize);
+
+    impl Wake for CountingWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    fn counting_waker() -> (Arc<CountingWaker>, Waker) {
+        let counter = Arc::new(CountingWaker::default());
+        (counter.clone(), Waker::from(counter))
+    }
+
+    #[test]
+    fn test_send_recv() {
+        let (sender, receiver) = async_channel(2);
+        block_on(async {
+            sender.send(1).await.unwrap();
+            sender.send(2).await.unwrap();
+            assert_eq!(sender.try_send(3), Err(TrySendError::Full(3)));
+            assert_eq!(receiver.recv().await, Some(1));
+            assert_eq!(receiver.recv().await, Some(2));
+            drop(sender);
+            assert_eq!(receiver.recv().await, None);
+        });
+    }
+
+    #[test]
+    fn test_wake_receiver() {
+        let (sender, receiver) = async_channel(2);
+        let (counter, waker) = counting_waker();
+        let mut cx = Context::from_waker(&waker);
+
+        let mut recv = receiver.recv();
+        assert_eq!(Pin::new(&mut recv).poll(&mut cx), Poll::Pending);
+        // Polling again does not park the waker twice.
+        assert_eq!(Pin::new(&mut recv).poll(&mut cx), Poll::Pending);
+        assert_eq!(counter.0.load(Ordering::SeqCst), 0);
+
+        sender.try_send(1).unwrap();
+        assert_eq!(counter.0.load(Ordering::SeqCst), 1);
+        assert_eq!(Pin::new(&mut recv).poll(&mut cx), Poll::Ready(Some(1)));
+
+        assert_eq!(Pin::new(&mut recv).poll(&mut cx), Poll::Pending);
+        drop(sender);
+        assert_eq!(counter.0.load(Ordering::SeqCst), 2);
+        assert_eq!(Pin::new(&mut recv).poll(&mut cx), Poll::Ready(None));
+    }
+
+    #[test]
+    fn test_wake_sender() {
+        let (sender, receiver) = async_channel(1);
+        let (counter, waker) = counting_waker();
+        let mut cx = Context::from_waker(&waker);
+
+        sender.try_send(1).unwrap();
+        let mut send = sender.send(2);
+        assert_eq!(Pin::new(&mut send).poll(&mut cx), Poll::Pending);
+        assert_eq!(counter.0.load(Ordering::SeqCst), 0);
+
+        assert_eq!(receiver.try_recv(), Ok(1));
+        assert_eq!(counter.0.load(Ordering::SeqCst), 1);
+        assert_eq!(Pin::new(&mut send).poll(&mut cx), Poll::Ready(Ok(())));
+
+        let mut send = sender.send(3);
+        assert_eq!(Pin::new(&mut send).poll(&mut cx), Poll::Pending);
+        drop(receiver);
+        assert_eq!(counter.0.load(Ordering::SeqCst), 2);
+        assert_eq!(Pin::new(&mut send).poll(&mut cx), Poll::Ready(Err(SendError(3))));
+    }
+
+    #[test]
+    fn test_sink_reserves() {
+        let (mut sender, receiver) = async_channel(1);
+        let mut other = sender.clone();
+        let (_, waker) = counting_waker();
+        let mut cx = Context::from_waker(&waker);
+
+        // A slot reserved by one sender can not be taken by another.
+        assert_eq!(Pin::new(&mut sender).poll_ready(&mut cx), Poll::Ready(Ok(())));
+        assert_eq!(other.try_send(1), Err(TrySendError::Full(1)));
+        assert_eq!(Pin::new(&mut other).poll_ready(&mut cx), Poll::Pending);
+        assert_eq!(Pin::new(&mut sender).start_send(2), Ok(()));
+        assert_eq!(receiver.try_recv(), Ok(2));
+
+        // Dropping a sender releases its reservation.
+        assert_eq!(Pin::new(&mut other).poll_ready(&mut cx), Poll::Ready(Ok(())));
+        assert_eq!(Pin::new(&mut sender).poll_ready(&mut cx), Poll::Pending);
+        drop(other);
+        assert_eq!(Pin::new(&mut sender).poll_ready(&mut cx), Poll::Ready(Ok(())));
+
+        drop(receiver);
+        assert_eq!(Pin::new(&mut sender).start_send(3), Err(SendError(())));
+        assert_eq!(Pin::new(&mut sender).poll_ready(&mut cx), Poll::Ready(Err(SendError(()))));
+    }
+
+    #[test]
+    fn test_stream() {
+        let (sender, mut receiver) = async_channel(4);
+        sender.try_send(1).unwrap();
+        sender.try_send(2).unwrap();
+        drop(sender);
+        assert_eq!(receiver.size_hint(), (2, Some(2)));
+
+        let mut received = Vec::new();
+        block_on(poll_fn(|cx| {
+            while let Some(value) = std::task::ready!(Pin::new(&mut receiver).poll_next(cx)) {
+                received.push(value);
+            }
+            Poll::Ready(())
+        }));
+        assert_eq!(received, [1, 2]);
+        assert!(receiver.is_terminated());
+    }
+
+    #[test]
+    fn test_threaded_stress() {
+        const SENDERS: usize = 4;
+        const PER_SENDER: usize = 10_000;
+
+        let (sender, receiver) = async_channel(8);
+        let received = Arc::new(Mutex::new(Vec::new()));
+
+        let mut handles = Vec::new();
+        for s in 0..SENDERS {
+            let mut sender = sender.clone();
+            handles.push(thread::spawn(move || {
+                block_on(async {
+                    for i in 0..PER_SENDER {
+                        let value = s * PER_SENDER + i;
+                        if i % 2 == 0 {
+                            sender.send(value).await.unwrap();
+                        } else {
+                            poll_fn(|cx| Pin::new(&mut sender).poll_ready(cx)).await.unwrap();
+                            Pin::new(&mut sender).start_send(value).unwrap();
+                        }
+                    }
+                })
+            }));
+        }
+        drop(sender);
+        for _ in 0..2 {
+            let receiver = receiver.clone();
+            let received = received.clone();
+            handles.push(thread::spawn(move || {
+                let mut local = Vec::new();
+                block_on(async {
+                    while let Some(value) = receiver.recv().await {
+                        local.push(value);
+                    }
+                });
+                received.lock().unwrap().extend(local);
+            }));
+        }
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        let mut received = received.lock().unwrap();
+        received.sort_unstable();
+        assert_eq!(*received, (0..SENDERS * PER_SENDER).collect::<Vec<_>>());
+    }
+}
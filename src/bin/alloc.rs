@@ -1,155 +1,213 @@
-extern crate debug;
-extern crate test;
-extern crate core;
-
 extern crate criterion;
+extern crate ringbuf;
 
-use std::rt::heap::{allocate, reallocate, deallocate, reallocate_inplace};
-use std::mem;
+use std::alloc::{alloc as allocate, dealloc as deallocate, realloc as reallocate, Layout};
+use std::hint::black_box;
 use std::ptr;
 
-use criterion::{Bencher, Criterion};
+use criterion::{BatchSize, Bencher, BenchmarkId, Criterion};
+use ringbuf::RingBuf;
 
 #[allow(dead_code)]
 fn main() {
-  //let mut b = Criterion::default();
+  let mut b = Criterion::default().configure_from_args();
   //let sizes = &[8, 128, 1024, 32 * 1024];
-  //b.bench_group("alloc", sizes, alloc);
-  //b.bench_group("alloc", sizes, alloc_vec);
-  //b.bench_group("alloc-vec-no-forget", sizes, alloc_vec_no_forget);
-  //b.bench_group("alloc_vec", sizes, alloc_vec);
-  //b.bench_group("realloc", sizes, realloc);
-  //b.bench_group("realloc_manual", sizes, realloc_manual);
-  //b.bench_group("realloc_manual_on_fail", sizes, realloc_manual_on_fail);
+  //bench_family(&mut b, "alloc", alloc, sizes);
+  //bench_family(&mut b, "alloc_vec", alloc_vec, sizes);
+  //bench_family(&mut b, "alloc-vec-no-forget", alloc_vec_no_forget, sizes);
+  //bench_family(&mut b, "realloc", realloc, sizes);
+  //bench_family(&mut b, "realloc_manual", realloc_manual, sizes);
+  //bench_family(&mut b, "realloc_manual_on_fail", realloc_manual_on_fail, sizes);
+  let ringbuf_sizes = &[1024, 32 * 1024, 1024 * 1024, 16 * 1024 * 1024];
+  bench_family(&mut b, "ringbuf_grow", ringbuf_grow, ringbuf_sizes);
+  bench_family(&mut b, "ringbuf_grow_wrapped", ringbuf_grow_wrapped, ringbuf_sizes);
+  b.final_summary();
+}
+
+/// Runs the benchmark function once for each of the provided sizes.
+#[allow(dead_code)]
+fn bench_family(b: &mut Criterion, name: &str, f: fn(&mut Bencher, &usize), sizes: &[usize]) {
+  let mut group = b.benchmark_group(name);
+  for size in sizes {
+    group.bench_with_input(BenchmarkId::from_parameter(size), size, f);
+  }
+  group.finish();
 }
 
 #[allow(dead_code)]
-fn alloc(b: &mut Bencher, n: uint) {
-    let alignment = mem::min_align_of::<u8>();
+fn alloc(b: &mut Bencher, n: &usize) {
+    let n = *n;
     b.iter(|| {
         unsafe {
+            let layout = Layout::array::<u8>(n).expect("capacity overflow");
             let ptr =
-                if mem::size_of::<u8>() == 0 {
-                    0 as *mut u8
-                } else if n == 0 {
-                    0 as *mut u8
+                if n == 0 {
+                    ptr::NonNull::<u8>::dangling().as_ptr()
                 } else {
-                    let size = n.checked_mul(&mem::size_of::<u8>())
-                                .expect("capacity overflow");
-                    allocate(size, mem::min_align_of::<u8>())
+                    allocate(layout)
                 };
-            for i in range(0, n) {
-                let slot = ptr.offset(i as int);
-                ptr::write(&mut *slot, i as u8);
+            for i in 0..n {
+                let slot = ptr.add(i);
+                ptr::write(slot, i as u8);
+            }
+            black_box(&ptr);
+            if n != 0 {
+                deallocate(ptr, layout);
             }
-            test::black_box(&ptr);
-            deallocate(ptr, n, alignment);
         }
     })
 }
 
 #[allow(dead_code)]
-fn alloc_vec(b: &mut Bencher, n: uint) {
+fn alloc_vec(b: &mut Bencher, n: &usize) {
+  let n = *n;
   b.iter(|| {
     unsafe {
-      let mut vec = Vec::with_capacity(n);
+      let mut vec = std::mem::ManuallyDrop::new(Vec::<u8>::with_capacity(n));
       let mut ptr = vec.as_mut_ptr();
-      mem::forget(vec);
 
-      for i in range(0, n) {
-        let slot = ptr.offset(i as int);
-        ptr::write(&mut *slot, i as u8);
+      for i in 0..n {
+        let slot = ptr.add(i);
+        ptr::write(slot, i as u8);
       }
 
-      test::black_box(&mut ptr);
-      Vec::from_raw_parts(0, n, ptr);
+      black_box(&mut ptr);
+      drop(Vec::from_raw_parts(ptr, 0, n));
     }
   })
 }
 
 #[allow(dead_code)]
-fn alloc_vec_no_forget(b: &mut Bencher, n: uint) {
+fn alloc_vec_no_forget(b: &mut Bencher, n: &usize) {
+  let n = *n;
   b.iter(|| {
     unsafe {
-      let mut vec = Vec::with_capacity(n);
+      let mut vec = Vec::<u8>::with_capacity(n);
       let mut ptr = vec.as_mut_ptr();
 
-      for i in range(0, n) {
-        let slot = ptr.offset(i as int);
-        ptr::write(&mut *slot, i as u8);
+      for i in 0..n {
+        let slot = ptr.add(i);
+        ptr::write(slot, i as u8);
       }
 
-      test::black_box(&mut ptr);
+      black_box(&mut ptr);
     }
   })
 }
 
 #[allow(dead_code)]
-fn realloc(b: &mut Bencher, n: uint) {
-  let alignment = mem::min_align_of::<u8>();
+fn realloc(b: &mut Bencher, n: &usize) {
+  let n = *n;
+  let layout = Layout::array::<u8>(n).expect("capacity overflow");
   b.iter(|| {
     unsafe {
-      let mut ptr = allocate(n, alignment);
+      let mut ptr = allocate(layout);
 
-      for i in range(0, n) {
-        let slot = ptr.offset(i as int);
-        ptr::write(&mut *slot, i as u8);
+      for i in 0..n {
+        let slot = ptr.add(i);
+        ptr::write(slot, i as u8);
       }
 
-      ptr = reallocate(ptr, 2 * n, alignment, n);
+      ptr = reallocate(ptr, layout, 2 * n);
 
-      test::black_box(&mut ptr);
-      deallocate(ptr, n * 2, alignment);
+      black_box(&mut ptr);
+      deallocate(ptr, Layout::array::<u8>(2 * n).expect("capacity overflow"));
     }
   })
 }
 
 #[allow(dead_code)]
-fn realloc_manual(b: &mut Bencher, n: uint) {
+fn realloc_manual(b: &mut Bencher, n: &usize) {
+  let n = *n;
+  let layout = Layout::array::<u8>(n).expect("capacity overflow");
+  let layout2 = Layout::array::<u8>(2 * n).expect("capacity overflow");
   b.iter(|| {
-    let alignment = mem::min_align_of::<u8>();
     unsafe {
-      let mut ptr = allocate(n, alignment);
+      let mut ptr = allocate(layout);
 
-      for i in range(0, n) {
-        let slot = ptr.offset(i as int);
-        ptr::write(&mut *slot, i as u8);
+      for i in 0..n {
+        let slot = ptr.add(i);
+        ptr::write(slot, i as u8);
       }
 
-      let ptr2 = allocate(2 * n, alignment);
-      ptr::copy_nonoverlapping_memory(ptr2, ptr as *const u8, n);
+      let ptr2 = allocate(layout2);
+      ptr::copy_nonoverlapping(ptr as *const u8, ptr2, n);
 
-      deallocate(ptr, n, alignment);
+      deallocate(ptr, layout);
 
-      test::black_box(&mut ptr);
-      deallocate(ptr2, n * 2, alignment);
+      black_box(&mut ptr);
+      deallocate(ptr2, layout2);
     }
   })
 }
 
+/// Stable Rust has no way to ask the allocator to grow a buffer only in
+/// place, so this reallocates with `realloc` and only falls back to a manual
+/// allocate and copy if `realloc` fails.
 #[allow(dead_code)]
-fn realloc_manual_on_fail(b: &mut Bencher, n: uint) {
+fn realloc_manual_on_fail(b: &mut Bencher, n: &usize) {
+  let n = *n;
+  let layout = Layout::array::<u8>(n).expect("capacity overflow");
+  let layout2 = Layout::array::<u8>(2 * n).expect("capacity overflow");
   b.iter(|| {
-    let alignment = mem::min_align_of::<u8>();
     unsafe {
-      let mut ptr = allocate(n, alignment);
+      let mut ptr = allocate(layout);
 
-      for i in range(0, n) {
-        let slot = ptr.offset(i as int);
-        ptr::write(&mut *slot, i as u8);
+      for i in 0..n {
+        let slot = ptr.add(i);
+        ptr::write(slot, i as u8);
       }
 
-      let inplace = reallocate_inplace(ptr, 2 * n, alignment, n);
-      if !inplace {
-        let temp = allocate(2 * n, alignment);
-        ptr::copy_nonoverlapping_memory(temp, ptr as *const u8, n);
+      let reallocated = reallocate(ptr, layout, 2 * n);
+      if reallocated.is_null() {
+        let temp = allocate(layout2);
+        ptr::copy_nonoverlapping(ptr as *const u8, temp, n);
 
-        deallocate(ptr, n, alignment);
+        deallocate(ptr, layout);
         ptr = temp;
+      } else {
+        ptr = reallocated;
       }
 
-      test::black_box(&mut ptr);
-      deallocate(ptr, n * 2, alignment);
+      black_box(&mut ptr);
+      deallocate(ptr, layout2);
     }
   })
 }
+
+/// Doubles the capacity of a full `RingBuf` whose elements are contiguous.
+#[allow(dead_code)]
+fn ringbuf_grow(b: &mut Bencher, n: &usize) {
+  let n = *n;
+  b.iter_batched(
+    || {
+      let mut ringbuf = RingBuf::<u8>::with_capacity(n);
+      ringbuf.extend((0..n).map(|i| i as u8));
+      ringbuf
+    },
+    |mut ringbuf| {
+      ringbuf.reserve_exact(n);
+      ringbuf
+    },
+    BatchSize::LargeInput)
+}
+
+/// Doubles the capacity of a full `RingBuf` whose elements wrap around the
+/// end of the buffer, with a quarter of them at the start of the buffer.
+#[allow(dead_code)]
+fn ringbuf_grow_wrapped(b: &mut Bencher, n: &usize) {
+  let n = *n;
+  b.iter_batched(
+    || {
+      let mut ringbuf = RingBuf::<u8>::with_capacity(n);
+      ringbuf.extend((0..n / 4).map(|i| i as u8));
+      while ringbuf.pop_front().is_some() {}
+      ringbuf.extend((0..n).map(|i| i as u8));
+      ringbuf
+    },
+    |mut ringbuf| {
+      ringbuf.reserve_exact(n);
+      ringbuf
+    },
+    BatchSize::LargeInput)
+}
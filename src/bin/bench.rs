@@ -1,150 +1,224 @@
-extern crate debug;
-extern crate test;
-extern crate core;
-extern crate collections;
-
 extern crate ringbuf;
 extern crate criterion;
+extern crate rand;
 
 // Switch from benchmarking the stdlib implementation to the new
 // implementation by changing the import
-//use collections::RingBuf;
+//use std::collections::VecDeque as RingBuf;
 use ringbuf::RingBuf;
+use ringbuf::Pow2RingBuf;
+
+use std::hint::black_box;
 
-use collections::Deque;
-use std::rand::Rng;
-use std::rand::SeedableRng;
-use std::rand::StdRng;
+use rand::distributions::Standard;
+use rand::rngs::StdRng;
+use rand::Rng;
+use rand::SeedableRng;
 
-use criterion::{Bencher, Criterion};
+use criterion::{Bencher, BenchmarkId, Criterion};
 
 #[allow(dead_code)]
 fn main() {
-    let mut b = Criterion::default();
-    let capacities = &[8u, 1024, 32 * 1024];
-    //b.bench_group("push_back_default_allocate", capacities, push_back_default_allocate);
-    //b.bench_group("push_back_pre_allocate", capacities, push_back_pre_allocate);
-    //b.bench_group("push_pre_default_allocate", capacities, push_front_default_allocate);
-    //b.bench_group("push_pre_allocate", capacities, push_front_pre_allocate);
-    b.bench_family("iterate", iterate, capacities);
-    //b.bench_group("get", capacities, get);
-    b.bench_family("move_iterator", move_iterator, capacities);
-    b.bench_family("safe_move_iterator", safe_move_iterator, capacities);
+    let mut b = Criterion::default().configure_from_args();
+    let capacities = &[8usize, 1024, 32 * 1024];
+    //bench_family(&mut b, "push_back_default_allocate", push_back_default_allocate, capacities);
+    //bench_family(&mut b, "push_back_pre_allocate", push_back_pre_allocate, capacities);
+    //bench_family(&mut b, "push_pre_default_allocate", push_front_default_allocate, capacities);
+    //bench_family(&mut b, "push_pre_allocate", push_front_pre_allocate, capacities);
+    bench_family(&mut b, "iterate", iterate::<RingBuf<isize>>, capacities);
+    bench_family(&mut b, "pow2_iterate", iterate::<Pow2RingBuf<isize>>, capacities);
+    bench_family(&mut b, "get", get::<RingBuf<isize>>, capacities);
+    bench_family(&mut b, "pow2_get", get::<Pow2RingBuf<isize>>, capacities);
+    bench_family(&mut b, "push_pop", push_pop::<RingBuf<isize>>, capacities);
+    bench_family(&mut b, "pow2_push_pop", push_pop::<Pow2RingBuf<isize>>, capacities);
+    bench_family(&mut b, "move_iterator", move_iterator, capacities);
+    bench_family(&mut b, "safe_move_iterator", safe_move_iterator, capacities);
+    b.final_summary();
+}
+
+/// Runs the benchmark function once for each of the provided capacities.
+fn bench_family(b: &mut Criterion,
+                name: &str,
+                f: fn(&mut Bencher, &usize),
+                capacities: &[usize]) {
+    let mut group = b.benchmark_group(name);
+    for capacity in capacities {
+        group.bench_with_input(BenchmarkId::from_parameter(capacity), capacity, f);
+    }
+    group.finish();
 }
 
 fn get_rng() -> StdRng {
-    SeedableRng::from_seed(&[1, 2, 3, 4])
+    SeedableRng::seed_from_u64(1234)
+}
+
+/// The operations shared by the benchmarks which compare `RingBuf` with
+/// `Pow2RingBuf`.
+trait Queue: Clone {
+    fn with_capacity(capacity: usize) -> Self;
+    fn push_back(&mut self, value: isize);
+    fn pop_front(&mut self) -> Option<isize>;
+    fn get(&self, index: usize) -> Option<&isize>;
+    fn for_each<F: FnMut(&isize)>(&self, f: F);
+}
+
+macro_rules! impl_queue {
+    ($ty:ty) => {
+        impl Queue for $ty {
+            fn with_capacity(capacity: usize) -> Self { <$ty>::with_capacity(capacity) }
+            #[inline]
+            fn push_back(&mut self, value: isize) { <$ty>::push_back(self, value) }
+            #[inline]
+            fn pop_front(&mut self) -> Option<isize> { <$ty>::pop_front(self) }
+            #[inline]
+            fn get(&self, index: usize) -> Option<&isize> { <$ty>::get(self, index) }
+            #[inline]
+            fn for_each<F: FnMut(&isize)>(&self, f: F) { self.iter().for_each(f) }
+        }
+    }
+}
+
+impl_queue!(RingBuf<isize>);
+impl_queue!(Pow2RingBuf<isize>);
+
+/// Fills a queue to `capacity`, with its elements wrapping around the end of
+/// the buffer.
+fn filled<Q: Queue>(capacity: usize) -> Q {
+    let mut rb = Q::with_capacity(capacity);
+    let mut rng = get_rng();
+    for _ in 0..capacity / 2 {
+        rb.push_back(0);
+    }
+    for _ in 0..capacity / 2 {
+        rb.pop_front();
+        rb.push_back(rng.gen());
+    }
+    for _ in 0..capacity - capacity / 2 {
+        rb.push_back(rng.gen());
+    }
+    rb
 }
 
 #[allow(dead_code)]
-fn allocate(b: &mut Bencher, capacity: &uint) {
+fn allocate(b: &mut Bencher, capacity: &usize) {
     b.iter(|| {
-        let mut rb = RingBuf::<int>::with_capacity(*capacity);
-        test::black_box(&mut rb);
+        let mut rb = RingBuf::<isize>::with_capacity(*capacity);
+        black_box(&mut rb);
     })
 }
 
 #[allow(dead_code)]
-fn push_back_pre_allocate(b: &mut Bencher, capacity: &uint) {
-    let items: Vec<int> = get_rng().gen_iter::<int>().take(*capacity).collect();
+fn push_back_pre_allocate(b: &mut Bencher, capacity: &usize) {
+    let items: Vec<isize> = get_rng().sample_iter(Standard).take(*capacity).collect();
     b.iter(|| {
-        let mut rb: RingBuf<int> = RingBuf::with_capacity(*capacity);
+        let mut rb: RingBuf<isize> = RingBuf::with_capacity(*capacity);
         for &item in items.iter() {
             rb.push_back(item);
         }
-        test::black_box(&mut rb);
+        black_box(&mut rb);
     })
 }
 
 #[allow(dead_code)]
-fn push_back_default_allocate(b: &mut Bencher, capacity: &uint) {
-    let items: Vec<int> = get_rng().gen_iter::<int>().take(*capacity).collect();
+fn push_back_default_allocate(b: &mut Bencher, capacity: &usize) {
+    let items: Vec<isize> = get_rng().sample_iter(Standard).take(*capacity).collect();
     b.iter(|| {
-        let mut rb: RingBuf<int> = RingBuf::with_capacity(8);
+        let mut rb: RingBuf<isize> = RingBuf::with_capacity(8);
         for &item in items.iter() {
             rb.push_back(item);
         }
-        test::black_box(&mut rb);
+        black_box(&mut rb);
     })
 }
 
 #[allow(dead_code)]
-fn push_front_pre_allocate(b: &mut Bencher, capacity: &uint) {
-    let items: Vec<int> = get_rng().gen_iter::<int>().take(*capacity).collect();
+fn push_front_pre_allocate(b: &mut Bencher, capacity: &usize) {
+    let items: Vec<isize> = get_rng().sample_iter(Standard).take(*capacity).collect();
     b.iter(|| {
-        let mut rb: RingBuf<int> = RingBuf::with_capacity(*capacity);
+        let mut rb: RingBuf<isize> = RingBuf::with_capacity(*capacity);
         for &item in items.iter() {
             rb.push_front(item);
         }
-        test::black_box(&mut rb);
+        black_box(&mut rb);
     })
 }
 
 #[allow(dead_code)]
-fn push_front_default_allocate(b: &mut Bencher, capacity: &uint) {
-    let items: Vec<int> = get_rng().gen_iter::<int>().take(*capacity).collect();
+fn push_front_default_allocate(b: &mut Bencher, capacity: &usize) {
+    let items: Vec<isize> = get_rng().sample_iter(Standard).take(*capacity).collect();
     b.iter(|| {
-        let mut rb: RingBuf<int> = RingBuf::with_capacity(8);
+        let mut rb: RingBuf<isize> = RingBuf::with_capacity(8);
         for &item in items.iter() {
             rb.push_front(item);
         }
-        test::black_box(&mut rb);
+        black_box(&mut rb);
     })
 }
 
 #[allow(dead_code)]
-fn iterate(b: &mut Bencher, capacity: &uint) {
-    let mut rb = RingBuf::with_capacity(*capacity);
-    for element in get_rng().gen_iter::<int>().take(*capacity) {
-        rb.push_back(element);
-    }
+fn iterate<Q: Queue>(b: &mut Bencher, capacity: &usize) {
+    let rb: Q = filled(*capacity);
 
     b.iter(|| {
-        for &element in rb.clone().iter() {
-            test::black_box(&element);
-        }
+        rb.clone().for_each(|element| {
+            black_box(element);
+        })
     })
 }
 
 #[allow(dead_code)]
-fn move_iterator(b: &mut Bencher, capacity: &uint) {
+fn move_iterator(b: &mut Bencher, capacity: &usize) {
     let mut rb = RingBuf::with_capacity(*capacity);
-    for element in get_rng().gen_iter::<int>().take(*capacity) {
+    for element in get_rng().sample_iter::<isize, _>(Standard).take(*capacity) {
         rb.push_back(element);
     }
 
     b.iter(|| {
-        for element in rb.clone().move_iter() {
-            test::black_box(element);
+        for element in rb.clone() {
+            black_box(element);
         }
     })
 }
 
 #[allow(dead_code)]
-fn safe_move_iterator(b: &mut Bencher, capacity: &uint) {
+fn safe_move_iterator(b: &mut Bencher, capacity: &usize) {
     let mut rb = RingBuf::with_capacity(*capacity);
-    for element in get_rng().gen_iter::<int>().take(*capacity) {
+    for element in get_rng().sample_iter::<isize, _>(Standard).take(*capacity) {
         rb.push_back(element);
     }
 
     b.iter(|| {
         for element in (SafeMoveItems{ ringbuf: rb.clone() }) {
-            test::black_box(element);
+            black_box(element);
         }
     })
 }
 
 
 #[allow(dead_code)]
-fn get(b: &mut Bencher, capacity: &uint) {
-    let mut rb = RingBuf::with_capacity(*capacity);
-    for element in get_rng().gen_iter::<int>().take(*capacity) {
-        rb.push_back(element);
+fn get<Q: Queue>(b: &mut Bencher, capacity: &usize) {
+    let rb: Q = filled(*capacity);
+
+    b.iter(|| {
+        for i in 0..*capacity {
+            black_box(rb.get(i));
+        }
+    })
+}
+
+/// Pushes and pops through a half-full queue, so that the front and back
+/// wrap around the buffer without it growing.
+#[allow(dead_code)]
+fn push_pop<Q: Queue>(b: &mut Bencher, capacity: &usize) {
+    let mut rb = Q::with_capacity(*capacity);
+    for _ in 0..*capacity / 2 {
+        rb.push_back(0);
     }
 
     b.iter(|| {
-        for i in range(0, *capacity) {
-            test::black_box(rb.get(i));
+        for i in 0..*capacity {
+            rb.push_back(i as isize);
+            black_box(rb.pop_front());
         }
     })
 }
@@ -154,19 +228,21 @@ pub struct SafeMoveItems<T> {
     ringbuf: RingBuf<T>
 }
 
-impl<T> Iterator<T> for SafeMoveItems<T> {
+impl<T> Iterator for SafeMoveItems<T> {
+    type Item = T;
+
     #[inline]
     fn next(&mut self) -> Option<T> {
         self.ringbuf.pop_front()
     }
 
     #[inline]
-    fn size_hint(&self) -> (uint, Option<uint>) {
+    fn size_hint(&self) -> (usize, Option<usize>) {
         (self.ringbuf.len(), Some(self.ringbuf.len()))
     }
 }
 
-impl<T> DoubleEndedIterator<T> for SafeMoveItems<T> {
+impl<T> DoubleEndedIterator for SafeMoveItems<T> {
     #[inline]
     fn next_back(&mut self) -> Option<T> {
         self.ringbuf.pop_back()
@@ -0,0 +1,474 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+//
+
+//! A fixed-capacity ring buffer which overwrites its oldest elements when
+//! full.
+
+use core::fmt;
+use core::ops::{Deref, RangeBounds};
+
+use super::{Drain, GrowthPolicy, IntoIter, IterMut, RingBuf, TryPushError};
+
+/// A ring buffer whose capacity never changes.
+///
+/// Pushing onto a full `BoundedRingBuf` overwrites the element at the
+/// opposite end and returns it, which makes it suitable for keeping the last
+/// `N` items of a stream. All of the read-only methods of `RingBuf` are
+/// available through `Deref`.
+///
+/// # Example
+///
+/// ```rust
+/// # use ringbuf::BoundedRingBuf;
+/// let mut events = BoundedRingBuf::with_capacity(2);
+/// assert_eq!(events.push_back(1), None);
+/// assert_eq!(events.push_back(2), None);
+/// assert_eq!(events.push_back(3), Some(1));
+///
+/// assert_eq!(events.capacity(), 2);
+/// assert_eq!(events.iter().collect::<Vec<_>>(), vec![&2, &3]);
+/// ```
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct BoundedRingBuf<T> {
+
+    /// The elements. The ring is never allowed to grow.
+    ringbuf: RingBuf<T>,
+
+    /// The maximum number of elements.
+    /// invariant: `ringbuf.len() <= capacity <= ringbuf.capacity()`
+    capacity: usize,
+}
+
+impl<T> BoundedRingBuf<T> {
+
+    /// Constructs a new, empty `BoundedRingBuf` which holds at most
+    /// `capacity` elements.
+    ///
+    /// The buffer is allocated up front, and is never reallocated.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use ringbuf::BoundedRingBuf;
+    /// let ring: BoundedRingBuf<i32> = BoundedRingBuf::with_capacity(10);
+    /// assert_eq!(ring.capacity(), 10);
+    /// ```
+    pub fn with_capacity(capacity: usize) -> BoundedRingBuf<T> {
+        BoundedRingBuf { ringbuf: fixed(RingBuf::with_capacity(capacity)), capacity }
+    }
+
+    /// Constructs a new `BoundedRingBuf` from the elements of a `RingBuf`.
+    ///
+    /// The capacity of the bounded ring is the capacity of the provided ring
+    /// buffer. No copying will be done. The growth policy and automatic
+    /// shrinking of the ring buffer are turned off, so that its buffer is
+    /// never reallocated.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use ringbuf::{BoundedRingBuf, RingBuf};
+    /// let mut ringbuf = RingBuf::with_capacity(3);
+    /// ringbuf.push_back(1);
+    /// let bounded = BoundedRingBuf::from_ringbuf(ringbuf);
+    /// assert_eq!(bounded.capacity(), 3);
+    /// ```
+    pub fn from_ringbuf(ringbuf: RingBuf<T>) -> BoundedRingBuf<T> {
+        let capacity = ringbuf.capacity();
+        BoundedRingBuf { ringbuf: fixed(ringbuf), capacity }
+    }
+
+    /// Converts the `BoundedRingBuf` into a growable `RingBuf` holding the
+    /// same elements. No copying will be done.
+    pub fn into_ringbuf(self) -> RingBuf<T> {
+        self.ringbuf
+    }
+
+    /// Returns the number of elements the ring can hold.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Returns `true` if the ring holds `capacity` elements, in which case
+    /// the next push will overwrite an element.
+    #[inline]
+    pub fn is_full(&self) -> bool {
+        self.ringbuf.len() == self.capacity
+    }
+
+    /// Append an element to the ring.
+    ///
+    /// If the ring is full, the first (oldest) element is removed to make
+    /// room and returned. If the capacity is 0, `value` itself is returned.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use ringbuf::BoundedRingBuf;
+    /// let mut ring = BoundedRingBuf::with_capacity(1);
+    /// assert_eq!(ring.push_back(1), None);
+    /// assert_eq!(ring.push_back(2), Some(1));
+    /// assert_eq!(ring.front(), Some(&2));
+    /// ```
+    pub fn push_back(&mut self, value: T) -> Option<T> {
+        if self.capacity == 0 { return Some(value) }
+        let evicted = if self.is_full() { self.ringbuf.pop_front() } else { None };
+        self.ringbuf.push_back(value);
+        evicted
+    }
+
+    /// Prepend an element to the ring.
+    ///
+    /// If the ring is full, the last (newest) element is removed to make
+    /// room and returned. If the capacity is 0, `value` itself is returned.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use ringbuf::BoundedRingBuf;
+    /// let mut ring = BoundedRingBuf::with_capacity(2);
+    /// ring.push_back(1);
+    /// ring.push_back(2);
+    /// assert_eq!(ring.push_front(0), Some(2));
+    /// assert_eq!(ring.iter().collect::<Vec<_>>(), vec![&0, &1]);
+    /// ```
+    pub fn push_front(&mut self, value: T) -> Option<T> {
+        if self.capacity == 0 { return Some(value) }
+        let evicted = if self.is_full() { self.ringbuf.pop_back() } else { None };
+        self.ringbuf.push_front(value);
+        evicted
+    }
+
+    /// Append an element to the ring if it is not full. Unlike `push_back`,
+    /// this never overwrites an element.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use ringbuf::{BoundedRingBuf, TryPushError};
+    /// let mut ring = BoundedRingBuf::with_capacity(1);
+    /// assert!(ring.try_push_back(1).is_ok());
+    /// assert_eq!(ring.try_push_back(2), Err(TryPushError::Full(2)));
+    /// ```
+    pub fn try_push_back(&mut self, value: T) -> Result<(), TryPushError<T>> {
+        if self.is_full() { return Err(TryPushError::Full(value)) }
+        self.ringbuf.push_back(value);
+        Ok(())
+    }
+
+    /// Prepend an element to the ring if it is not full. Unlike
+    /// `push_front`, this never overwrites an element.
+    pub fn try_push_front(&mut self, value: T) -> Result<(), TryPushError<T>> {
+        if self.is_full() { return Err(TryPushError::Full(value)) }
+        self.ringbuf.push_front(value);
+        Ok(())
+    }
+
+    /// Remove the first element and return it, or `None` if the ring is
+    /// empty.
+    #[inline]
+    pub fn pop_front(&mut self) -> Option<T> {
+        self.ringbuf.pop_front()
+    }
+
+    /// Remove the last element and return it, or `None` if the ring is
+    /// empty.
+    #[inline]
+    pub fn pop_back(&mut self) -> Option<T> {
+        self.ringbuf.pop_back()
+    }
+
+    /// Return a mutable reference to the first element.
+    #[inline]
+    pub fn front_mut(&mut self) -> Option<&mut T> {
+        self.ringbuf.front_mut()
+    }
+
+    /// Return a mutable reference to the last element.
+    #[inline]
+    pub fn back_mut(&mut self) -> Option<&mut T> {
+        self.ringbuf.back_mut()
+    }
+
+    /// Returns a mutable reference to the value at index `index`, or `None`
+    /// if the index is out of bounds.
+    #[inline]
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        self.ringbuf.get_mut(index)
+    }
+
+    /// Returns an iterator over mutable references to the elements of the
+    /// ring in order.
+    #[inline]
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        self.ringbuf.iter_mut()
+    }
+
+    /// Work with `self` as a pair of mutable slices.
+    #[inline]
+    pub fn as_mut_slices(&mut self) -> (&mut [T], &mut [T]) {
+        self.ringbuf.as_mut_slices()
+    }
+
+    /// Rearrange the elements so that they are stored contiguously, and
+    /// return a mutable slice of them. This never allocates.
+    #[inline]
+    pub fn make_contiguous(&mut self) -> &mut [T] {
+        self.ringbuf.make_contiguous()
+    }
+
+    /// Swap elements at indices `i` and `j`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is no element with the given index
+    #[inline]
+    pub fn swap(&mut self, i: usize, j: usize) {
+        self.ringbuf.swap(i, j)
+    }
+
+    /// Remove the element at `index` and return it, or `None` if `index` is
+    /// out of bounds.
+    #[inline]
+    pub fn remove(&mut self, index: usize) -> Option<T> {
+        self.ringbuf.remove(index)
+    }
+
+    /// Shorten the ring, dropping excess elements from the back.
+    #[inline]
+    pub fn truncate(&mut self, len: usize) {
+        self.ringbuf.truncate(len)
+    }
+
+    /// Remove all elements from the ring.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.ringbuf.clear()
+    }
+
+    /// Retain only the elements specified by the predicate.
+    #[inline]
+    pub fn retain<F>(&mut self, f: F) where F: FnMut(&T) -> bool {
+        self.ringbuf.retain(f)
+    }
+
+    /// Removes the elements in the given range from the ring and returns
+    /// them as an iterator.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the starting point is greater than the end point or if the
+    /// end point is greater than the length of the ring.
+    #[inline]
+    pub fn drain<R>(&mut self, range: R) -> Drain<'_, T> where R: RangeBounds<usize> {
+        self.ringbuf.drain(range)
+    }
+}
+
+/// Stops a ring buffer from reallocating its buffer on its own. It can still
+/// grow when pushed onto while full, which a `BoundedRingBuf` never does.
+fn fixed<T>(mut ringbuf: RingBuf<T>) -> RingBuf<T> {
+    ringbuf.set_growth_policy(GrowthPolicy::Exact);
+    ringbuf.set_auto_shrink(None);
+    ringbuf
+}
+
+impl<T: Clone> Clone for BoundedRingBuf<T> {
+    fn clone(&self) -> BoundedRingBuf<T> {
+        // `RingBuf::clone` allocates only as many slots as there are
+        // elements, so allocate the full capacity up front.
+        let mut ring = BoundedRingBuf::with_capacity(self.capacity);
+        ring.ringbuf.extend(self.ringbuf.iter().cloned());
+        ring
+    }
+}
+
+impl<T> Deref for BoundedRingBuf<T> {
+    type Target = RingBuf<T>;
+
+    #[inline]
+    fn deref(&self) -> &RingBuf<T> {
+        &self.ringbuf
+    }
+}
+
+impl<T> Extend<T> for BoundedRingBuf<T> {
+    /// Appends the elements of the iterator, overwriting the oldest elements
+    /// once the ring is full.
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iterator: I) {
+        for element in iterator {
+            self.push_back(element);
+        }
+    }
+}
+
+impl<T> IntoIterator for BoundedRingBuf<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    #[inline]
+    fn into_iter(self) -> IntoIter<T> {
+        self.ringbuf.into_iter()
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for BoundedRingBuf<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.ringbuf.fmt(f)
+    }
+}
+
+#[cfg(test)]
+mod checks {
+    use quickcheck::quickcheck;
+
+    use super::BoundedRingBuf;
+    use crate::RingBuf;
+
+    #[test]
+    fn check_push_back_keeps_last() {
+        fn prop(items: Vec<i32>, capacity: u8) -> bool {
+            let capacity = capacity as usize % 16;
+            let mut ring = BoundedRingBuf::with_capacity(capacity);
+            let mut evicted = Vec::new();
+            for &item in &items {
+                evicted.extend(ring.push_back(item));
+            }
+
+            let split = items.len().saturating_sub(capacity);
+            ring.capacity() == capacity
+                && ring.len() == items.len() - split
+                && ring.iter().eq(items[split..].iter())
+                && evicted == items[..split]
+        }
+
+        quickcheck(prop as fn(Vec<i32>, u8) -> bool);
+    }
+
+    #[test]
+    fn check_push_front_keeps_first() {
+        fn prop(items: Vec<i32>, capacity: u8) -> bool {
+            let capacity = capacity as usize % 16;
+            let mut ring = BoundedRingBuf::with_capacity(capacity);
+            let mut evicted = Vec::new();
+            for &item in &items {
+                evicted.extend(ring.push_front(item));
+            }
+
+            // The ring holds the newest items, newest first, and each push
+            // onto a full ring evicted the item at the back.
+            let split = items.len().saturating_sub(capacity);
+            let mut expected = items[split..].to_vec();
+            expected.reverse();
+            let expected_evicted = if capacity == 0 { &items[..] } else { &items[..split] };
+            ring.capacity() == capacity
+                && ring.iter().eq(expected.iter())
+                && evicted == expected_evicted
+        }
+
+        quickcheck(prop as fn(Vec<i32>, u8) -> bool);
+    }
+
+    #[test]
+    fn check_try_push_never_overwrites() {
+        fn prop(items: Vec<i32>, capacity: u8) -> bool {
+            let capacity = capacity as usize % 16;
+            let mut ring = BoundedRingBuf::with_capacity(capacity);
+            let mut rejected = Vec::new();
+            for &item in &items {
+                if let Err(error) = ring.try_push_back(item) {
+                    rejected.push(error.into_inner());
+                }
+            }
+
+            let split = std::cmp::min(items.len(), capacity);
+            ring.iter().eq(items[..split].iter()) && rejected == items[split..]
+        }
+
+        quickcheck(prop as fn(Vec<i32>, u8) -> bool);
+    }
+
+    #[test]
+    fn test_from_ringbuf_never_shrinks() {
+        let mut ringbuf = RingBuf::with_capacity(100);
+        ringbuf.set_auto_shrink(Some(4));
+        let mut ring = BoundedRingBuf::from_ringbuf(ringbuf);
+        ring.extend(0..100);
+        for _ in 0..90 {
+            ring.pop_front();
+        }
+        assert_eq!(RingBuf::capacity(&ring), 100);
+
+        ring.extend(0..200);
+        assert_eq!(RingBuf::capacity(&ring), 100);
+    }
+
+    #[test]
+    #[cfg(target_has_atomic = "ptr")]
+    fn test_split_from_ringbuf() {
+        let mut ringbuf = RingBuf::with_capacity(100);
+        ringbuf.set_auto_shrink(Some(4));
+        let (mut producer, mut consumer) = BoundedRingBuf::from_ringbuf(ringbuf).split();
+        for i in 0..300 {
+            if producer.try_push(i).is_err() {
+                assert!(consumer.try_pop().is_some());
+                assert!(producer.try_push(i).is_ok());
+            }
+        }
+        assert_eq!(consumer.len(), 100);
+    }
+
+    #[test]
+    #[cfg(target_has_atomic = "ptr")]
+    fn test_clone_keeps_capacity() {
+        let mut ring = BoundedRingBuf::with_capacity(4);
+        ring.push_back(1);
+        let mut clone = ring.clone();
+        assert_eq!(RingBuf::capacity(&clone), 4);
+        while !clone.is_full() {
+            clone.push_back(2);
+        }
+        assert_eq!(RingBuf::capacity(&clone), 4);
+
+        let (mut producer, mut consumer) = clone.split();
+        assert!(producer.try_push(3).is_err());
+        assert_eq!(consumer.try_pop(), Some(1));
+        assert!(producer.try_push(3).is_ok());
+        assert_eq!(consumer.len(), 4);
+    }
+
+    #[test]
+    #[cfg(target_has_atomic = "ptr")]
+    fn test_split_mismatched_buffer() {
+        // Buffers larger and smaller than the capacity are replaced by one of
+        // exactly the capacity.
+        for buffer in [10, 1] {
+            let mut ring = BoundedRingBuf { ringbuf: RingBuf::with_capacity(buffer), capacity: 4 };
+            ring.extend(0..6);
+            let (mut producer, mut consumer) = ring.split();
+            assert!(producer.try_push(6).is_err());
+            for i in 2..100 {
+                assert_eq!(consumer.try_pop(), Some(i));
+                assert!(producer.try_push(i + 4).is_ok());
+                assert_eq!(consumer.len(), 4);
+            }
+        }
+    }
+
+    #[test]
+    fn check_zero_sized() {
+        fn prop(pushes: u8, capacity: u8) -> bool {
+            let mut ring = BoundedRingBuf::with_capacity(capacity as usize);
+            let evicted = (0..pushes).filter_map(|_| ring.push_back(())).count();
+            ring.len() == std::cmp::min(pushes, capacity) as usize
+                && evicted == (pushes as usize).saturating_sub(capacity as usize)
+        }
+
+        quickcheck(prop as fn(u8, u8) -> bool);
+    }
+}
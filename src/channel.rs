@@ -0,0 +1,656 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+//
+
+//! A blocking bounded channel backed by a `RingBuf` behind a mutex.
+
+use std::cmp;
+use std::fmt;
+use std::ptr;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError};
+use std::time::Duration;
+
+use super::error::{RecvError, RecvTimeoutError, SendError, SendTimeoutError, TryRecvError,
+                   TrySendError};
+use super::RingBuf;
+
+/// Creates a bounded channel which holds at most `capacity` values.
+///
+/// Senders block while the channel is full, and receivers block while it is
+/// empty. Both halves can be cloned, and the channel is disconnected once
+/// every `Sender` or every `Receiver` has been dropped.
+///
+/// Unlike `std::sync::mpsc::sync_channel`, the buffer can be inspected, and
+/// the capacity changed while the channel is in use. A channel with capacity
+/// `0` blocks every send until the capacity is raised.
+///
+/// # Example
+///
+/// ```rust
+/// use std::thread;
+///
+/// let (sender, receiver) = ringbuf::channel(4);
+/// let handle = thread::spawn(move || {
+///     for i in 0..100 {
+///         sender.send(i).unwrap();
+///     }
+/// });
+///
+/// let received: Vec<i32> = receiver.iter().collect();
+/// handle.join().unwrap();
+/// assert_eq!(received, (0..100).collect::<Vec<_>>());
+/// ```
+pub fn channel<T>(capacity: usize) -> (Sender<T>, Receiver<T>) {
+    let shared = Arc::new(Shared {
+        state: Mutex::new(State {
+            buffer: RingBuf::with_capacity(capacity),
+            capacity,
+            senders: 1,
+            receivers: 1,
+        }),
+        not_empty: Condvar::new(),
+        not_full: Condvar::new(),
+    });
+    (Sender { shared: shared.clone() }, Receiver { shared })
+}
+
+struct State<T> {
+    buffer: RingBuf<T>,
+
+    /// The maximum number of values in the buffer.
+    capacity: usize,
+
+    senders: usize,
+    receivers: usize,
+}
+
+impl<T> State<T> {
+
+    #[inline]
+    fn is_full(&self) -> bool {
+        self.buffer.len() >= self.capacity
+    }
+}
+
+struct Shared<T> {
+    state: Mutex<State<T>>,
+
+    /// Signalled when a value is sent, or the last sender is dropped.
+    not_empty: Condvar,
+
+    /// Signalled when a value is received, the capacity is raised, or the
+    /// last receiver is dropped.
+    not_full: Condvar,
+}
+
+impl<T> Shared<T> {
+
+    /// Locks the state.
+    ///
+    /// The state is never left inconsistent by a panic, so poisoning is
+    /// ignored.
+    fn lock(&self) -> MutexGuard<'_, State<T>> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn len(&self) -> usize {
+        self.lock().buffer.len()
+    }
+
+    fn capacity(&self) -> usize {
+        self.lock().capacity
+    }
+
+    fn set_capacity(&self, capacity: usize) {
+        let mut state = self.lock();
+        let old_capacity = state.capacity;
+        state.capacity = capacity;
+        if capacity > state.buffer.capacity() {
+            let additional = capacity - state.buffer.len();
+            state.buffer.reserve_exact(additional);
+        } else {
+            state.buffer.shrink_to(capacity);
+        }
+        drop(state);
+        if capacity > old_capacity {
+            self.not_full.notify_all();
+        }
+    }
+
+    fn inspect<F, R>(&self, f: F) -> R where F: FnOnce(&RingBuf<T>) -> R {
+        f(&self.lock().buffer)
+    }
+}
+
+/// The sending half of a channel.
+///
+/// Created by `channel`.
+pub struct Sender<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> Sender<T> {
+
+    /// Sends a value, blocking while the channel is full.
+    ///
+    /// Returns the value in an error if every `Receiver` has been dropped.
+    pub fn send(&self, value: T) -> Result<(), SendError<T>> {
+        let mut state = self.shared.lock();
+        while state.receivers > 0 && state.is_full() {
+            state = self.shared.not_full.wait(state).unwrap_or_else(PoisonError::into_inner);
+        }
+        if state.receivers == 0 { return Err(SendError(value)) }
+        self.push(state, value);
+        Ok(())
+    }
+
+    /// Sends a value if the channel has room for it, without blocking.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use ringbuf::TrySendError;
+    /// let (sender, receiver) = ringbuf::channel(1);
+    /// assert_eq!(sender.try_send(1), Ok(()));
+    /// assert_eq!(sender.try_send(2), Err(TrySendError::Full(2)));
+    /// drop(receiver);
+    /// assert_eq!(sender.try_send(3), Err(TrySendError::Disconnected(3)));
+    /// ```
+    pub fn try_send(&self, value: T) -> Result<(), TrySendError<T>> {
+        let state = self.shared.lock();
+        if state.receivers == 0 { return Err(TrySendError::Disconnected(value)) }
+        if state.is_full() { return Err(TrySendError::Full(value)) }
+        self.push(state, value);
+        Ok(())
+    }
+
+    /// Sends a value, blocking while the channel is full for at most
+    /// `timeout`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use ringbuf::SendTimeoutError;
+    /// use std::time::Duration;
+    ///
+    /// let (sender, _receiver) = ringbuf::channel(1);
+    /// sender.send(1).unwrap();
+    /// assert_eq!(sender.send_timeout(2, Duration::from_millis(10)),
+    ///            Err(SendTimeoutError::Timeout(2)));
+    /// ```
+    pub fn send_timeout(&self, value: T, timeout: Duration) -> Result<(), SendTimeoutError<T>> {
+        let state = self.shared.lock();
+        let (state, _) = self.shared
+                             .not_full
+                             .wait_timeout_while(state, timeout, |state| {
+                                 state.receivers > 0 && state.is_full()
+                             })
+                             .unwrap_or_else(PoisonError::into_inner);
+        if state.receivers == 0 { return Err(SendTimeoutError::Disconnected(value)) }
+        if state.is_full() { return Err(SendTimeoutError::Timeout(value)) }
+        self.push(state, value);
+        Ok(())
+    }
+
+    fn push(&self, mut state: MutexGuard<State<T>>, value: T) {
+        state.buffer.push_back(value);
+        drop(state);
+        self.shared.not_empty.notify_one();
+    }
+
+    /// Returns the number of values in the channel.
+    pub fn len(&self) -> usize {
+        self.shared.len()
+    }
+
+    /// Returns `true` if the channel is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the maximum number of values the channel holds.
+    pub fn capacity(&self) -> usize {
+        self.shared.capacity()
+    }
+
+    /// Changes the maximum number of values the channel holds, resizing the
+    /// buffer to match.
+    ///
+    /// Values already in the channel are never dropped: if there are more
+    /// than `capacity`, senders block until enough have been received.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let (sender, receiver) = ringbuf::channel(1);
+    /// sender.send(1).unwrap();
+    /// assert!(sender.try_send(2).is_err());
+    /// sender.set_capacity(2);
+    /// assert!(sender.try_send(2).is_ok());
+    /// assert_eq!(receiver.len(), 2);
+    /// ```
+    pub fn set_capacity(&self, capacity: usize) {
+        self.shared.set_capacity(capacity)
+    }
+
+    /// Calls `f` with the buffer of the channel, holding the lock so that no
+    /// values are sent or received in the meantime.
+    pub fn inspect<F, R>(&self, f: F) -> R where F: FnOnce(&RingBuf<T>) -> R {
+        self.shared.inspect(f)
+    }
+}
+
+impl<T> Clone for Sender<T> {
+    fn clone(&self) -> Sender<T> {
+        self.shared.lock().senders += 1;
+        Sender { shared: self.shared.clone() }
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        let mut state = self.shared.lock();
+        state.senders -= 1;
+        if state.senders == 0 {
+            drop(state);
+            self.shared.not_empty.notify_all();
+        }
+    }
+}
+
+impl<T> fmt::Debug for Sender<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Sender").finish_non_exhaustive()
+    }
+}
+
+/// The receiving half of a channel.
+///
+/// Created by `channel`.
+pub struct Receiver<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> Receiver<T> {
+
+    /// Receives a value, blocking while the channel is empty.
+    ///
+    /// Returns an error if the channel is empty and every `Sender` has been
+    /// dropped.
+    pub fn recv(&self) -> Result<T, RecvError> {
+        let state = self.wait_for_values()?;
+        Ok(self.pop(state))
+    }
+
+    /// Receives a value if there is one, without blocking.
+    pub fn try_recv(&self) -> Result<T, TryRecvError> {
+        let state = self.shared.lock();
+        if !state.buffer.is_empty() { return Ok(self.pop(state)) }
+        if state.senders == 0 { Err(TryRecvError::Disconnected) } else { Err(TryRecvError::Empty) }
+    }
+
+    /// Receives a value, blocking while the channel is empty for at most
+    /// `timeout`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use ringbuf::RecvTimeoutError;
+    /// use std::time::Duration;
+    ///
+    /// let (sender, receiver) = ringbuf::channel::<i32>(1);
+    /// assert_eq!(receiver.recv_timeout(Duration::from_millis(10)),
+    ///            Err(RecvTimeoutError::Timeout));
+    /// drop(sender);
+    /// assert_eq!(receiver.recv_timeout(Duration::from_millis(10)),
+    ///            Err(RecvTimeoutError::Disconnected));
+    /// ```
+    pub fn recv_timeout(&self, timeout: Duration) -> Result<T, RecvTimeoutError> {
+        let state = self.shared.lock();
+        let (state, _) = self.shared
+                             .not_empty
+                             .wait_timeout_while(state, timeout, |state| {
+                                 state.senders > 0 && state.buffer.is_empty()
+                             })
+                             .unwrap_or_else(PoisonError::into_inner);
+        if !state.buffer.is_empty() { return Ok(self.pop(state)) }
+        if state.senders == 0 { Err(RecvTimeoutError::Disconnected) } else { Err(RecvTimeoutError::Timeout) }
+    }
+
+    /// Receives up to `max` values into `values`, blocking while the channel
+    /// is empty, and returns the number received.
+    ///
+    /// The values are moved out of the buffer in at most two bulk copies.
+    /// Returns an error if the channel is empty and every `Sender` has been
+    /// dropped.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let (sender, receiver) = ringbuf::channel(8);
+    /// for i in 0..5 {
+    ///     sender.send(i).unwrap();
+    /// }
+    /// let mut values = Vec::new();
+    /// assert_eq!(receiver.recv_many(&mut values, 3), Ok(3));
+    /// assert_eq!(receiver.recv_many(&mut values, 3), Ok(2));
+    /// assert_eq!(values, [0, 1, 2, 3, 4]);
+    /// ```
+    pub fn recv_many(&self, values: &mut Vec<T>, max: usize) -> Result<usize, RecvError> {
+        if max == 0 { return Ok(0) }
+        let mut state = self.wait_for_values()?;
+
+        let buffer = &mut state.buffer;
+        let count = cmp::min(max, buffer.len());
+        values.reserve(count);
+        unsafe {
+            let (slice1, slice2) = buffer.as_slices();
+            let len1 = cmp::min(count, slice1.len());
+            let dst = values.as_mut_ptr().add(values.len());
+            ptr::copy_nonoverlapping(slice1.as_ptr(), dst, len1);
+            ptr::copy_nonoverlapping(slice2.as_ptr(), dst.add(len1), count - len1);
+            values.set_len(values.len() + count);
+            buffer.forget_front(count);
+        }
+
+        drop(state);
+        if count == 1 {
+            self.shared.not_full.notify_one();
+        } else {
+            self.shared.not_full.notify_all();
+        }
+        Ok(count)
+    }
+
+    /// Returns an iterator which blocks receiving values until the channel
+    /// is disconnected.
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter { receiver: self }
+    }
+
+    /// Blocks until the channel holds a value, or is disconnected.
+    fn wait_for_values(&self) -> Result<MutexGuard<'_, State<T>>, RecvError> {
+        let mut state = self.shared.lock();
+        while state.senders > 0 && state.buffer.is_empty() {
+            state = self.shared.not_empty.wait(state).unwrap_or_else(PoisonError::into_inner);
+        }
+        if state.buffer.is_empty() { Err(RecvError) } else { Ok(state) }
+    }
+
+    fn pop(&self, mut state: MutexGuard<State<T>>) -> T {
+        let value = state.buffer.pop_front().unwrap();
+        drop(state);
+        self.shared.not_full.notify_one();
+        value
+    }
+
+    /// Returns the number of values in the channel.
+    pub fn len(&self) -> usize {
+        self.shared.len()
+    }
+
+    /// Returns `true` if the channel is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the maximum number of values the channel holds.
+    pub fn capacity(&self) -> usize {
+        self.shared.capacity()
+    }
+
+    /// Changes the maximum number of values the channel holds, resizing the
+    /// buffer to match.
+    ///
+    /// Values already in the channel are never dropped: if there are more
+    /// than `capacity`, senders block until enough have been received.
+    pub fn set_capacity(&self, capacity: usize) {
+        self.shared.set_capacity(capacity)
+    }
+
+    /// Calls `f` with the buffer of the channel, holding the lock so that no
+    /// values are sent or received in the meantime.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let (sender, receiver) = ringbuf::channel(4);
+    /// sender.send(1).unwrap();
+    /// sender.send(2).unwrap();
+    /// assert_eq!(receiver.inspect(|buffer| buffer.iter().sum::<i32>()), 3);
+    /// ```
+    pub fn inspect<F, R>(&self, f: F) -> R where F: FnOnce(&RingBuf<T>) -> R {
+        self.shared.inspect(f)
+    }
+}
+
+impl<T> Clone for Receiver<T> {
+    fn clone(&self) -> Receiver<T> {
+        self.shared.lock().receivers += 1;
+        Receiver { shared: self.shared.clone() }
+    }
+}
+
+impl<T> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        let mut state = self.shared.lock();
+        state.receivers -= 1;
+        if state.receivers == 0 {
+            drop(state);
+            self.shared.not_full.notify_all();
+        }
+    }
+}
+
+impl<T> fmt::Debug for Receiver<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Receiver").finish_non_exhaustive()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a Receiver<T> {
+    type Item = T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+/// A blocking iterator over the values received from a channel.
+///
+/// Created by `Receiver::iter`.
+#[derive(Debug)]
+pub struct Iter<'a, T: 'a> {
+    receiver: &'a Receiver<T>,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.receiver.recv().ok()
+    }
+}
+
+#[cfg(test)]
+mod checks {
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+    use std::time::Duration;
+
+    use crate::{RecvError, RecvTimeoutError, SendError, SendTimeoutError, TryRecvError,
+                TrySendError};
+
+    use super::channel;
+
+    #[test]
+    fn test_send_recv() {
+        let (sender, receiver) = channel(2);
+        sender.send(1).unwrap();
+        sender.send(2).unwrap();
+        assert_eq!(sender.try_send(3), Err(TrySendError::Full(3)));
+        assert_eq!(receiver.recv(), Ok(1));
+        assert_eq!(receiver.try_recv(), Ok(2));
+        assert_eq!(receiver.try_recv(), Err(TryRecvError::Empty));
+    }
+
+    #[test]
+    fn test_disconnect() {
+        let (sender, receiver) = channel(2);
+        let sender2 = sender.clone();
+        sender.send(1).unwrap();
+        drop(sender);
+        assert_eq!(receiver.recv(), Ok(1));
+        assert_eq!(receiver.try_recv(), Err(TryRecvError::Empty));
+        drop(sender2);
+        assert_eq!(receiver.recv(), Err(RecvError));
+        assert_eq!(receiver.try_recv(), Err(TryRecvError::Disconnected));
+
+        let (sender, receiver) = channel(2);
+        let receiver2 = receiver.clone();
+        drop(receiver);
+        sender.send(1).unwrap();
+        drop(receiver2);
+        assert_eq!(sender.send(2), Err(SendError(2)));
+        assert_eq!(sender.send_timeout(3, Duration::from_secs(1)),
+                   Err(SendTimeoutError::Disconnected(3)));
+    }
+
+    #[test]
+    fn test_disconnect_wakes_blocked() {
+        let (sender, receiver) = channel::<i32>(1);
+        let handle = thread::spawn(move || receiver.recv());
+        thread::sleep(Duration::from_millis(10));
+        drop(sender);
+        assert_eq!(handle.join().unwrap(), Err(RecvError));
+
+        let (sender, receiver) = channel(1);
+        sender.send(1).unwrap();
+        let handle = thread::spawn(move || sender.send(2));
+        thread::sleep(Duration::from_millis(10));
+        drop(receiver);
+        assert_eq!(handle.join().unwrap(), Err(SendError(2)));
+    }
+
+    #[test]
+    fn test_timeouts() {
+        let (sender, receiver) = channel(1);
+        assert_eq!(receiver.recv_timeout(Duration::from_millis(5)), Err(RecvTimeoutError::Timeout));
+        sender.send(1).unwrap();
+        assert_eq!(sender.send_timeout(2, Duration::from_millis(5)),
+                   Err(SendTimeoutError::Timeout(2)));
+
+        let handle = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(10));
+            assert_eq!(receiver.recv(), Ok(1));
+            receiver
+        });
+        assert_eq!(sender.send_timeout(2, Duration::from_secs(10)), Ok(()));
+        let receiver = handle.join().unwrap();
+        assert_eq!(receiver.try_recv(), Ok(2));
+    }
+
+    #[test]
+    fn test_recv_many() {
+        let (sender, receiver) = channel(4);
+        let mut values = Vec::new();
+
+        // Wrap the buffer around before draining it.
+        for i in 0..3 {
+            sender.send(i).unwrap();
+        }
+        assert_eq!(receiver.recv_many(&mut values, 2), Ok(2));
+        for i in 3..6 {
+            sender.send(i).unwrap();
+        }
+        assert!(receiver.inspect(|buffer| !buffer.as_slices().1.is_empty()));
+        assert_eq!(receiver.recv_many(&mut values, 10), Ok(4));
+        assert_eq!(values, [0, 1, 2, 3, 4, 5]);
+
+        drop(sender);
+        assert_eq!(receiver.recv_many(&mut values, 10), Err(RecvError));
+    }
+
+    #[test]
+    fn test_set_capacity() {
+        let (sender, receiver) = channel(2);
+        sender.send(1).unwrap();
+        sender.send(2).unwrap();
+
+        // Shrinking keeps the values, and blocks senders until they drain.
+        receiver.set_capacity(1);
+        assert_eq!(receiver.capacity(), 1);
+        assert_eq!(receiver.len(), 2);
+        assert!(sender.try_send(3).is_err());
+        assert_eq!(receiver.recv(), Ok(1));
+        assert!(sender.try_send(3).is_err());
+
+        // Growing wakes blocked senders.
+        let handle = thread::spawn(move || {
+            sender.send(3).unwrap();
+            sender.send(4).unwrap();
+            sender
+        });
+        thread::sleep(Duration::from_millis(10));
+        receiver.set_capacity(8);
+        let sender = handle.join().unwrap();
+        assert!(receiver.inspect(|buffer| buffer.capacity()) >= 8);
+        drop(sender);
+        assert_eq!(receiver.iter().collect::<Vec<_>>(), [2, 3, 4]);
+    }
+
+    #[test]
+    fn test_zero_sized() {
+        let (sender, receiver) = channel(3);
+        for _ in 0..3 {
+            sender.send(()).unwrap();
+        }
+        assert_eq!(sender.try_send(()), Err(TrySendError::Full(())));
+        let mut values = Vec::new();
+        assert_eq!(receiver.recv_many(&mut values, 5), Ok(3));
+    }
+
+    #[test]
+    fn test_threaded_stress() {
+        const SENDERS: usize = 4;
+        const PER_SENDER: usize = 10_000;
+
+        let (sender, receiver) = channel(16);
+        let received = Arc::new(Mutex::new(Vec::new()));
+
+        let mut handles = Vec::new();
+        for s in 0..SENDERS {
+            let sender = sender.clone();
+            handles.push(thread::spawn(move || {
+                for i in 0..PER_SENDER {
+                    sender.send(s * PER_SENDER + i).unwrap();
+                }
+            }));
+        }
+        drop(sender);
+        for batch in 0..2 {
+            let receiver = receiver.clone();
+            let received = received.clone();
+            handles.push(thread::spawn(move || {
+                let mut local = Vec::new();
+                if batch == 0 {
+                    local.extend(&receiver);
+                } else {
+                    while receiver.recv_many(&mut local, 7).is_ok() {}
+                }
+                received.lock().unwrap().extend(local);
+            }));
+        }
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        let mut received = received.lock().unwrap();
+        received.sort_unstable();
+        assert_eq!(*received, (0..SENDERS * PER_SENDER).collect::<Vec<_>>());
+    }
+}
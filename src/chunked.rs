@@ -0,0 +1,727 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+//
+
+//! A double-ended queue built from a ring of fixed-size blocks.
+
+use core::cmp;
+use core::fmt;
+use core::iter::FusedIterator;
+use core::mem::{self, MaybeUninit};
+use core::ptr;
+use core::slice;
+
+use alloc::boxed::Box;
+
+use super::RingBuf;
+
+/// A block of element slots, which is never reallocated.
+type Block<T> = Box<[MaybeUninit<T>]>;
+
+/// A double-ended queue which stores its elements in fixed-size blocks.
+///
+/// The blocks are held in a `RingBuf`, so pushing and popping at either end
+/// and indexing are O(1), like a `RingBuf`. Unlike a `RingBuf`, growing never
+/// moves the elements: a full queue allocates one more block, and only the
+/// table of block pointers is ever copied. This avoids the latency spike and
+/// the transient memory peak of doubling a very large buffer, at the cost of
+/// an extra indirection on each access.
+///
+/// Each block holds 4 KiB of elements, rounded down to a power of two
+/// elements, and at least 16 elements.
+///
+/// # Example
+///
+/// ```rust
+/// # use ringbuf::ChunkedDeque;
+/// let mut deque = ChunkedDeque::new();
+/// deque.extend(0..10_000);
+/// deque.push_front(-1);
+/// assert_eq!(deque[0], -1);
+/// assert_eq!(deque[10_000], 9_999);
+/// assert_eq!(deque.pop_back(), Some(9_999));
+/// ```
+pub struct ChunkedDeque<T> {
+
+    /// The blocks, in order. Every block between the first and the last is
+    /// full.
+    /// invariant: `blocks.len() == (head + len).div_ceil(BLOCK_LEN)`
+    blocks: RingBuf<Block<T>>,
+
+    /// The offset of the first element in the first block.
+    /// invariant: `head < BLOCK_LEN`
+    head: usize,
+
+    /// The number of elements in the deque.
+    len: usize,
+}
+
+impl<T> ChunkedDeque<T> {
+
+    /// The number of elements in each block.
+    const BLOCK_LEN: usize = {
+        let n = match 4096usize.checked_div(mem::size_of::<T>()) {
+            Some(n) => n,
+            None => 4096,
+        };
+        if n <= 16 { 16 } else { 1 << (usize::BITS - 1 - n.leading_zeros()) }
+    };
+
+    /// Creates an empty `ChunkedDeque`, which does not allocate.
+    #[inline]
+    pub const fn new() -> ChunkedDeque<T> {
+        ChunkedDeque { blocks: RingBuf::new(), head: 0, len: 0 }
+    }
+
+    /// Creates an empty `ChunkedDeque` whose block table has room for at
+    /// least `capacity` elements. The blocks themselves are allocated as the
+    /// elements are pushed.
+    pub fn with_capacity(capacity: usize) -> ChunkedDeque<T> {
+        let blocks = capacity.div_ceil(Self::BLOCK_LEN) + 1;
+        ChunkedDeque { blocks: RingBuf::with_capacity(blocks), head: 0, len: 0 }
+    }
+
+    /// Returns the number of elements in the deque.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the deque contains no elements.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns a reference to the value at index `index`, or `None` if the
+    /// index is out of bounds.
+    #[inline]
+    pub fn get(&self, index: usize) -> Option<&T> {
+        if index >= self.len { return None }
+        let (block, offset) = self.slot(index);
+        unsafe { Some(self.blocks[block][offset].assume_init_ref()) }
+    }
+
+    /// Returns a mutable reference to the value at index `index`, or `None`
+    /// if the index is out of bounds.
+    #[inline]
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        if index >= self.len { return None }
+        let (block, offset) = self.slot(index);
+        unsafe { Some(self.blocks[block][offset].assume_init_mut()) }
+    }
+
+    /// Swaps elements at indices `i` and `j`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if either index is out of bounds.
+    pub fn swap(&mut self, i: usize, j: usize) {
+        assert!(i < self.len && j < self.len, "index out of bounds");
+        if i == j { return }
+        let (block_i, offset_i) = self.slot(i);
+        let (block_j, offset_j) = self.slot(j);
+        if block_i == block_j {
+            self.blocks[block_i].swap(offset_i, offset_j);
+        } else {
+            let a: *mut MaybeUninit<T> = &mut self.blocks[block_i][offset_i];
+            let b: *mut MaybeUninit<T> = &mut self.blocks[block_j][offset_j];
+            unsafe { ptr::swap(a, b); }
+        }
+    }
+
+    /// Provides a reference to the front element, or `None` if the deque is
+    /// empty.
+    #[inline]
+    pub fn front(&self) -> Option<&T> {
+        self.get(0)
+    }
+
+    /// Provides a mutable reference to the front element, or `None` if the
+    /// deque is empty.
+    #[inline]
+    pub fn front_mut(&mut self) -> Option<&mut T> {
+        self.get_mut(0)
+    }
+
+    /// Provides a reference to the back element, or `None` if the deque is
+    /// empty.
+    #[inline]
+    pub fn back(&self) -> Option<&T> {
+        self.len.checked_sub(1).and_then(|index| self.get(index))
+    }
+
+    /// Provides a mutable reference to the back element, or `None` if the
+    /// deque is empty.
+    #[inline]
+    pub fn back_mut(&mut self) -> Option<&mut T> {
+        self.len.checked_sub(1).and_then(move |index| self.get_mut(index))
+    }
+
+    /// Appends an element to the back of the deque.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of elements overflows a `usize`.
+    pub fn push_back(&mut self, value: T) {
+        let end = self.head.checked_add(self.len).expect("capacity overflow");
+        if end == self.blocks.len() * Self::BLOCK_LEN {
+            self.blocks.push_back(Box::new_uninit_slice(Self::BLOCK_LEN));
+        }
+        let (block, offset) = self.slot(self.len);
+        self.blocks[block][offset].write(value);
+        self.len += 1;
+    }
+
+    /// Prepends an element to the deque.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of elements overflows a `usize`.
+    pub fn push_front(&mut self, value: T) {
+        assert!(self.len < usize::MAX - Self::BLOCK_LEN, "capacity overflow");
+        if self.head == 0 {
+            self.blocks.push_front(Box::new_uninit_slice(Self::BLOCK_LEN));
+            self.head = Self::BLOCK_LEN;
+        }
+        self.head -= 1;
+        self.blocks[0][self.head].write(value);
+        self.len += 1;
+    }
+
+    /// Removes the first element and returns it, or `None` if the deque is
+    /// empty.
+    pub fn pop_front(&mut self) -> Option<T> {
+        if self.len == 0 { return None }
+        let value = unsafe { self.blocks[0][self.head].assume_init_read() };
+        self.head += 1;
+        self.len -= 1;
+        if self.head == Self::BLOCK_LEN || self.len == 0 {
+            self.release_front();
+        }
+        Some(value)
+    }
+
+    /// Removes the last element and returns it, or `None` if the deque is
+    /// empty.
+    pub fn pop_back(&mut self) -> Option<T> {
+        if self.len == 0 { return None }
+        self.len -= 1;
+        let (block, offset) = self.slot(self.len);
+        let value = unsafe { self.blocks[block][offset].assume_init_read() };
+        if offset == 0 {
+            self.blocks.pop_back();
+        }
+        if self.len == 0 {
+            self.release_front();
+        }
+        Some(value)
+    }
+
+    /// Inserts an element at `index` within the deque, shifting whichever
+    /// side of `index` is shorter.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is greater than the length.
+    pub fn insert(&mut self, index: usize, value: T) {
+        assert!(index <= self.len, "index out of bounds");
+        // Push the value onto the shorter side, then shift that side over by
+        // one and move the value into the gap.
+        unsafe {
+            if index < self.len - index {
+                self.push_front(value);
+                let value = ptr::read(self.slot_ptr(0));
+                self.copy_slots(1, 0, index);
+                ptr::write(self.slot_ptr(index), value);
+            } else {
+                self.push_back(value);
+                let last = self.len - 1;
+                let value = ptr::read(self.slot_ptr(last));
+                self.copy_slots(index, index + 1, last - index);
+                ptr::write(self.slot_ptr(index), value);
+            }
+        }
+    }
+
+    /// Removes and returns the element at `index`, shifting whichever side
+    /// of `index` is shorter. Returns `None` if `index` is out of bounds.
+    pub fn remove(&mut self, index: usize) -> Option<T> {
+        if index >= self.len { return None }
+        // Move the value to the end of the shorter side, shifting that side
+        // over by one, and pop it from there.
+        unsafe {
+            let value = ptr::read(self.slot_ptr(index));
+            if index < self.len - index {
+                self.copy_slots(0, 1, index);
+                ptr::write(self.slot_ptr(0), value);
+                self.pop_front()
+            } else {
+                let last = self.len - 1;
+                self.copy_slots(index + 1, index, last - index);
+                ptr::write(self.slot_ptr(last), value);
+                self.pop_back()
+            }
+        }
+    }
+
+    /// Shortens the deque, dropping excess elements from the back and
+    /// freeing the blocks which become empty.
+    pub fn truncate(&mut self, len: usize) {
+        while self.len > len {
+            self.pop_back();
+        }
+    }
+
+    /// Clears the deque, removing all values and freeing every block.
+    pub fn clear(&mut self) {
+        self.truncate(0);
+    }
+
+    /// Retains only the elements specified by the predicate.
+    pub fn retain<F>(&mut self, mut f: F) where F: FnMut(&T) -> bool {
+        self.retain_mut(|elem| f(elem))
+    }
+
+    /// Retains only the elements specified by the predicate, passing a
+    /// mutable reference to it.
+    pub fn retain_mut<F>(&mut self, mut f: F) where F: FnMut(&mut T) -> bool {
+        let len = self.len;
+        let mut deleted = 0;
+        for i in 0..len {
+            if !f(&mut self[i]) {
+                deleted += 1;
+            } else if deleted > 0 {
+                self.swap(i - deleted, i);
+            }
+        }
+        if deleted > 0 {
+            self.truncate(len - deleted);
+        }
+    }
+
+    /// Returns `true` if the deque contains an element equal to the given
+    /// value.
+    pub fn contains(&self, x: &T) -> bool where T: PartialEq {
+        self.iter().any(|elem| elem == x)
+    }
+
+    /// Returns a front-to-back iterator.
+    pub fn iter(&self) -> Iter<'_, T> {
+        let (front, back) = self.edge_ranges();
+        let mut blocks = self.blocks.iter();
+        let front = blocks.next().map_or(&[][..], |block| &block[front]);
+        let back = if back.is_empty() { &[][..] } else { &blocks.next_back().unwrap()[back] };
+        Iter { blocks, front: front.iter(), back: back.iter(), len: self.len }
+    }
+
+    /// Returns a front-to-back iterator which returns mutable references.
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        let (front, back) = self.edge_ranges();
+        let mut blocks = self.blocks.iter_mut();
+        let front = blocks.next().map_or(&mut [][..], |block| &mut block[front]);
+        let back = if back.is_empty() {
+            &mut [][..]
+        } else {
+            &mut blocks.next_back().unwrap()[back]
+        };
+        IterMut { blocks, front: front.iter_mut(), back: back.iter_mut(), len: self.len }
+    }
+}
+
+impl<T> ChunkedDeque<T> {
+
+    /// Returns the block and the offset within it of the element at `index`.
+    #[inline]
+    fn slot(&self, index: usize) -> (usize, usize) {
+        let index = self.head + index;
+        (index / Self::BLOCK_LEN, index % Self::BLOCK_LEN)
+    }
+
+    /// Returns the ranges of the occupied slots in the first block, and in
+    /// the last block if there is more than one.
+    fn edge_ranges(&self) -> (core::ops::Range<usize>, core::ops::Range<usize>) {
+        if self.blocks.len() <= 1 {
+            (self.head..self.head + self.len, 0..0)
+        } else {
+            let end = self.head + self.len - (self.blocks.len() - 1) * Self::BLOCK_LEN;
+            (self.head..Self::BLOCK_LEN, 0..end)
+        }
+    }
+
+    /// Returns a pointer to the first slot of block `block`.
+    ///
+    /// Each call reborrows the whole block, which invalidates the pointers
+    /// into it returned by earlier calls, so take one pointer per block and
+    /// offset from it.
+    #[inline]
+    fn block_ptr(&mut self, block: usize) -> *mut T {
+        self.blocks[block].as_mut_ptr().cast::<T>()
+    }
+
+    /// Returns a pointer to the slot of the element at `index`.
+    ///
+    /// The slot's block must be allocated.
+    #[inline]
+    fn slot_ptr(&mut self, index: usize) -> *mut T {
+        let (block, offset) = self.slot(index);
+        unsafe { self.block_ptr(block).add(offset) }
+    }
+
+    /// Copies `count` elements from index `src` to index `dst`, which may
+    /// overlap, with one `ptr::copy` for each run which stays within a single
+    /// source block and a single destination block.
+    ///
+    /// The slots must be allocated, and the copied-from slots which are not
+    /// copied over are left logically uninitialized.
+    unsafe fn copy_slots(&mut self, src: usize, dst: usize, count: usize) {
+        let block_len = Self::BLOCK_LEN;
+        let mut copied = 0;
+        while copied < count {
+            let remaining = count - copied;
+            if dst <= src {
+                // Copy front to back, so that the source is read before it is
+                // overwritten.
+                let (src, dst) = (src + copied, dst + copied);
+                let n = cmp::min(remaining, cmp::min(block_len - self.slot(src).1,
+                                                     block_len - self.slot(dst).1));
+                self.copy_run(src, dst, n);
+                copied += n;
+            } else {
+                let (src_end, dst_end) = (src + remaining, dst + remaining);
+                let n = cmp::min(remaining, cmp::min(self.slot(src_end - 1).1 + 1,
+                                                     self.slot(dst_end - 1).1 + 1));
+                self.copy_run(src_end - n, dst_end - n, n);
+                copied += n;
+            }
+        }
+    }
+
+    /// Copies `n` elements from index `src` to index `dst`. Neither run may
+    /// cross the end of a block.
+    unsafe fn copy_run(&mut self, src: usize, dst: usize, n: usize) {
+        let (src_block, src_offset) = self.slot(src);
+        let (dst_block, dst_offset) = self.slot(dst);
+        let src_ptr = self.block_ptr(src_block);
+        let dst_ptr = if dst_block == src_block { src_ptr } else { self.block_ptr(dst_block) };
+        ptr::copy(src_ptr.add(src_offset), dst_ptr.add(dst_offset), n);
+    }
+
+    /// Frees the first block once the elements in it have been popped.
+    fn release_front(&mut self) {
+        self.blocks.pop_front();
+        self.head = 0;
+        if self.len == 0 {
+            self.blocks.clear();
+        }
+    }
+}
+
+deque_impls!(ChunkedDeque<T>);
+
+impl<T: Clone> Clone for ChunkedDeque<T> {
+    fn clone(&self) -> ChunkedDeque<T> {
+        self.iter().cloned().collect()
+    }
+}
+
+impl<T> Drop for ChunkedDeque<T> {
+    fn drop(&mut self) {
+        // The blocks are `MaybeUninit`, so they free their memory without
+        // dropping the elements.
+        self.clear();
+    }
+}
+
+impl<T> Extend<T> for ChunkedDeque<T> {
+    fn extend<I: IntoIterator<Item=T>>(&mut self, iterable: I) {
+        for value in iterable {
+            self.push_back(value);
+        }
+    }
+}
+
+impl<T> IntoIterator for ChunkedDeque<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> IntoIter<T> {
+        IntoIter { deque: self }
+    }
+}
+
+/// `ChunkedDeque` iterator.
+///
+/// Yields the occupied part of the first block, then the full blocks in
+/// between, then the occupied part of the last block.
+pub struct Iter<'a, T> {
+    blocks: super::Iter<'a, Block<T>>,
+    front: slice::Iter<'a, MaybeUninit<T>>,
+    back: slice::Iter<'a, MaybeUninit<T>>,
+    len: usize,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    #[inline]
+    fn next(&mut self) -> Option<&'a T> {
+        if self.len == 0 { return None }
+        let elem = match self.front.next() {
+            Some(elem) => elem,
+            None => match self.blocks.next() {
+                Some(block) => {
+                    self.front = block.iter();
+                    self.front.next()?
+                }
+                None => self.back.next()?,
+            },
+        };
+        self.len -= 1;
+        unsafe { Some(elem.assume_init_ref()) }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<'a, T> DoubleEndedIterator for Iter<'a, T> {
+    #[inline]
+    fn next_back(&mut self) -> Option<&'a T> {
+        if self.len == 0 { return None }
+        let elem = match self.back.next_back() {
+            Some(elem) => elem,
+            None => match self.blocks.next_back() {
+                Some(block) => {
+                    self.back = block.iter();
+                    self.back.next_back()?
+                }
+                None => self.front.next_back()?,
+            },
+        };
+        self.len -= 1;
+        unsafe { Some(elem.assume_init_ref()) }
+    }
+}
+
+impl<'a, T> ExactSizeIterator for Iter<'a, T> {}
+
+impl<'a, T> FusedIterator for Iter<'a, T> {}
+
+impl<'a, T> Clone for Iter<'a, T> {
+    fn clone(&self) -> Iter<'a, T> {
+        Iter {
+            blocks: self.blocks.clone(),
+            front: self.front.clone(),
+            back: self.back.clone(),
+            len: self.len,
+        }
+    }
+}
+
+impl<'a, T: fmt::Debug> fmt::Debug for Iter<'a, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.clone()).finish()
+    }
+}
+
+/// `ChunkedDeque` mutable iterator.
+pub struct IterMut<'a, T> {
+    blocks: super::IterMut<'a, Block<T>>,
+    front: slice::IterMut<'a, MaybeUninit<T>>,
+    back: slice::IterMut<'a, MaybeUninit<T>>,
+    len: usize,
+}
+
+impl<'a, T> Iterator for IterMut<'a, T> {
+    type Item = &'a mut T;
+
+    #[inline]
+    fn next(&mut self) -> Option<&'a mut T> {
+        if self.len == 0 { return None }
+        let elem = match self.front.next() {
+            Some(elem) => elem,
+            None => match self.blocks.next() {
+                Some(block) => {
+                    self.front = block.iter_mut();
+                    self.front.next()?
+                }
+                None => self.back.next()?,
+            },
+        };
+        self.len -= 1;
+        unsafe { Some(elem.assume_init_mut()) }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<'a, T> DoubleEndedIterator for IterMut<'a, T> {
+    #[inline]
+    fn next_back(&mut self) -> Option<&'a mut T> {
+        if self.len == 0 { return None }
+        let elem = match self.back.next_back() {
+            Some(elem) => elem,
+            None => match self.blocks.next_back() {
+                Some(block) => {
+                    self.back = block.iter_mut();
+                    self.back.next_back()?
+                }
+                None => self.front.next_back()?,
+            },
+        };
+        self.len -= 1;
+        unsafe { Some(elem.assume_init_mut()) }
+    }
+}
+
+impl<'a, T> ExactSizeIterator for IterMut<'a, T> {}
+
+impl<'a, T> FusedIterator for IterMut<'a, T> {}
+
+impl<'a, T: fmt::Debug> fmt::Debug for IterMut<'a, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("IterMut").field(&self.len).finish()
+    }
+}
+
+/// An iterator that moves out of a `ChunkedDeque`.
+#[derive(Clone)]
+pub struct IntoIter<T> {
+    deque: ChunkedDeque<T>,
+}
+
+impl<T> Iterator for IntoIter<T> {
+    type Item = T;
+
+    #[inline]
+    fn next(&mut self) -> Option<T> {
+        self.deque.pop_front()
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.deque.len, Some(self.deque.len))
+    }
+}
+
+impl<T> DoubleEndedIterator for IntoIter<T> {
+    #[inline]
+    fn next_back(&mut self) -> Option<T> {
+        self.deque.pop_back()
+    }
+}
+
+impl<T> ExactSizeIterator for IntoIter<T> {}
+
+impl<T> FusedIterator for IntoIter<T> {}
+
+impl<T: fmt::Debug> fmt::Debug for IntoIter<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("IntoIter").field(&self.deque).finish()
+    }
+}
+
+#[cfg(test)]
+mod checks {
+    use std::collections::VecDeque;
+    use std::rc::Rc;
+
+    use super::ChunkedDeque;
+    use crate::model::{self, Model};
+
+    impl Model for ChunkedDeque<i32> {
+        fn apply(&mut self, model: &mut VecDeque<i32>, op: u8, value: i32, index: usize) -> bool {
+            // Runs of pushes and pops make the deque span several blocks.
+            let run = value.rem_euclid(1200);
+            match op % 7 {
+                0 => for v in 0..run {
+                    self.push_back(v);
+                    model.push_back(v);
+                },
+                1 => for v in 0..run {
+                    self.push_front(v);
+                    model.push_front(v);
+                },
+                2 => for _ in 0..run {
+                    if self.pop_front() != model.pop_front() { return false }
+                },
+                3 => for _ in 0..run {
+                    if self.pop_back() != model.pop_back() { return false }
+                },
+                4 => {
+                    self.insert(index, value);
+                    model.insert(index, value);
+                }
+                5 => return self.remove(index) == model.remove(index),
+                _ => {
+                    self.retain(|&x| x % 3 != 0);
+                    model.retain(|&x| x % 3 != 0);
+                }
+            }
+            true
+        }
+    }
+
+    #[test]
+    fn check_model() {
+        model::check_model::<ChunkedDeque<i32>>();
+    }
+
+    #[test]
+    fn test_growth_does_not_move() {
+        let mut deque = ChunkedDeque::new();
+        deque.push_back(0u64);
+        let first: *const u64 = &deque[0];
+        for i in 1..100_000 {
+            deque.push_back(i);
+            deque.push_front(i);
+        }
+        assert_eq!(first, &deque[99_999] as *const u64);
+        assert_eq!(deque.len(), 199_999);
+    }
+
+    #[test]
+    fn test_insert_remove_across_blocks() {
+        let mut deque: ChunkedDeque<u64> = (0..10_000).collect();
+        let mut model: VecDeque<u64> = (0..10_000).collect();
+        for index in [0, 1, 1023, 1024, 3000, 5000, 7000, 9999, 10_000] {
+            deque.insert(index, u64::MAX);
+            model.insert(index, u64::MAX);
+            assert!(deque.iter().eq(model.iter()));
+            assert_eq!(deque.remove(index / 2), model.remove(index / 2));
+            assert!(deque.iter().eq(model.iter()));
+        }
+    }
+
+    #[test]
+    fn test_blocks_freed() {
+        let mut deque: ChunkedDeque<u64> = (0..10_000).collect();
+        assert!(deque.blocks.len() > 1);
+        while deque.len() > 1 {
+            deque.pop_front();
+        }
+        assert_eq!(deque.blocks.len(), 1);
+        deque.pop_back();
+        assert_eq!(deque.blocks.len(), 0);
+        assert_eq!(deque.head, 0);
+    }
+
+    #[test]
+    fn test_drop_items() {
+        model::check_drop_items::<ChunkedDeque<Rc<()>>>(2000);
+    }
+
+    #[test]
+    fn test_zero_sized() {
+        model::check_zero_sized::<ChunkedDeque<()>>(10_000);
+    }
+}
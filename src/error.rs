@@ -0,0 +1,298 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+//
+
+//! Errors returned by the fallible ring buffer and channel operations.
+
+use alloc::alloc::Layout;
+use core::error::Error;
+use core::fmt;
+
+/// The error returned by `try_reserve` when the ring buffer could not grow.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TryReserveError {
+
+    /// The requested capacity exceeds the maximum size of an allocation.
+    CapacityOverflow,
+
+    /// The memory allocator failed to allocate the buffer.
+    AllocError {
+        /// The layout of the allocation which failed.
+        layout: Layout,
+    },
+}
+
+impl TryReserveError {
+
+    /// Reports the error the same way the infallible methods do: by
+    /// panicking on capacity overflow, or by calling the global allocation
+    /// error handler.
+    pub(crate) fn handle(self) -> ! {
+        match self {
+            TryReserveError::CapacityOverflow => panic!("capacity overflow"),
+            TryReserveError::AllocError { layout } => alloc::alloc::handle_alloc_error(layout),
+        }
+    }
+}
+
+impl fmt::Display for TryReserveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("memory allocation failed")?;
+        match *self {
+            TryReserveError::CapacityOverflow => {
+                f.write_str(" because the computed capacity exceeded the maximum")
+            }
+            TryReserveError::AllocError { .. } => {
+                f.write_str(" because the memory allocator returned an error")
+            }
+        }
+    }
+}
+
+impl Error for TryReserveError {}
+
+/// The error returned by the fallible push methods. The value which could not
+/// be pushed is handed back to the caller.
+#[derive(Clone, PartialEq, Eq)]
+pub enum TryPushError<T> {
+
+    /// The ring buffer is bounded, and is full.
+    Full(T),
+
+    /// The ring buffer is full, and could not grow.
+    Alloc(T, TryReserveError),
+}
+
+impl<T> TryPushError<T> {
+
+    /// Returns the value which could not be pushed.
+    pub fn into_inner(self) -> T {
+        match self {
+            TryPushError::Full(value) => value,
+            TryPushError::Alloc(value, _) => value,
+        }
+    }
+}
+
+impl<T> fmt::Debug for TryPushError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            TryPushError::Full(..) => f.write_str("Full(..)"),
+            TryPushError::Alloc(_, ref error) => {
+                f.debug_tuple("Alloc").field(&format_args!("..")).field(error).finish()
+            }
+        }
+    }
+}
+
+impl<T> fmt::Display for TryPushError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            TryPushError::Full(..) => f.write_str("pushing onto a full ring buffer"),
+            TryPushError::Alloc(_, ref error) => {
+                write!(f, "failed to grow the ring buffer: {}", error)
+            }
+        }
+    }
+}
+
+impl<T> Error for TryPushError<T> {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            TryPushError::Full(..) => None,
+            TryPushError::Alloc(_, ref error) => Some(error),
+        }
+    }
+}
+
+/// The error returned by the lookups of a `SeqRingBuf` when it holds no
+/// element with the requested sequence number.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SeqError {
+
+    /// The element was popped from the front of the ring.
+    Evicted,
+
+    /// The element has not been pushed yet.
+    Pending,
+}
+
+impl fmt::Display for SeqError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SeqError::Evicted => f.write_str("sequence number has been evicted"),
+            SeqError::Pending => f.write_str("sequence number has not been pushed yet"),
+        }
+    }
+}
+
+impl Error for SeqError {}
+
+/// The error returned by `Sender::send` when every `Receiver` has been
+/// dropped. The value which could not be sent is handed back to the caller.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct SendError<T>(pub T);
+
+impl<T> fmt::Debug for SendError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("SendError(..)")
+    }
+}
+
+impl<T> fmt::Display for SendError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("sending on a disconnected channel")
+    }
+}
+
+impl<T> Error for SendError<T> {}
+
+/// The error returned by `Sender::try_send`.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum TrySendError<T> {
+
+    /// The channel is full.
+    Full(T),
+
+    /// Every `Receiver` has been dropped.
+    Disconnected(T),
+}
+
+impl<T> TrySendError<T> {
+
+    /// Returns the value which could not be sent.
+    pub fn into_inner(self) -> T {
+        match self {
+            TrySendError::Full(value) => value,
+            TrySendError::Disconnected(value) => value,
+        }
+    }
+}
+
+impl<T> fmt::Debug for TrySendError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            TrySendError::Full(..) => f.write_str("Full(..)"),
+            TrySendError::Disconnected(..) => f.write_str("Disconnected(..)"),
+        }
+    }
+}
+
+impl<T> fmt::Display for TrySendError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            TrySendError::Full(..) => f.write_str("sending on a full channel"),
+            TrySendError::Disconnected(..) => f.write_str("sending on a disconnected channel"),
+        }
+    }
+}
+
+impl<T> Error for TrySendError<T> {}
+
+/// The error returned by `Sender::send_timeout`.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum SendTimeoutError<T> {
+
+    /// The channel stayed full until the timeout elapsed.
+    Timeout(T),
+
+    /// Every `Receiver` has been dropped.
+    Disconnected(T),
+}
+
+impl<T> SendTimeoutError<T> {
+
+    /// Returns the value which could not be sent.
+    pub fn into_inner(self) -> T {
+        match self {
+            SendTimeoutError::Timeout(value) => value,
+            SendTimeoutError::Disconnected(value) => value,
+        }
+    }
+}
+
+impl<T> fmt::Debug for SendTimeoutError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SendTimeoutError::Timeout(..) => f.write_str("Timeout(..)"),
+            SendTimeoutError::Disconnected(..) => f.write_str("Disconnected(..)"),
+        }
+    }
+}
+
+impl<T> fmt::Display for SendTimeoutError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SendTimeoutError::Timeout(..) => f.write_str("timed out sending on a full channel"),
+            SendTimeoutError::Disconnected(..) => {
+                f.write_str("sending on a disconnected channel")
+            }
+        }
+    }
+}
+
+impl<T> Error for SendTimeoutError<T> {}
+
+/// The error returned by `Receiver::recv` when the channel is empty and every
+/// `Sender` has been dropped.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RecvError;
+
+impl fmt::Display for RecvError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("receiving on an empty and disconnected channel")
+    }
+}
+
+impl Error for RecvError {}
+
+/// The error returned by `Receiver::try_recv`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TryRecvError {
+
+    /// The channel is empty.
+    Empty,
+
+    /// The channel is empty, and every `Sender` has been dropped.
+    Disconnected,
+}
+
+impl fmt::Display for TryRecvError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            TryRecvError::Empty => f.write_str("receiving on an empty channel"),
+            TryRecvError::Disconnected => {
+                f.write_str("receiving on an empty and disconnected channel")
+            }
+        }
+    }
+}
+
+impl Error for TryRecvError {}
+
+/// The error returned by `Receiver::recv_timeout`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RecvTimeoutError {
+
+    /// The channel stayed empty until the timeout elapsed.
+    Timeout,
+
+    /// The channel is empty, and every `Sender` has been dropped.
+    Disconnected,
+}
+
+impl fmt::Display for RecvTimeoutError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            RecvTimeoutError::Timeout => f.write_str("timed out receiving on an empty channel"),
+            RecvTimeoutError::Disconnected => {
+                f.write_str("receiving on an empty and disconnected channel")
+            }
+        }
+    }
+}
+
+impl Error for RecvTimeoutError {}
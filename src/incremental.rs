@@ -0,0 +1,447 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+//
+
+//! A growable ring buffer which spreads the cost of each resize over the
+//! operations which follow it.
+
+use core::cmp;
+use core::iter::FusedIterator;
+use core::mem;
+
+use super::RingBuf;
+
+/// The number of elements moved from the old buffer by each operation while
+/// a resize is in progress.
+///
+/// This must be at least 2, so that the old buffer shrinks by at least one
+/// element on each operation even when the operation pushes onto it.
+const MIGRATE_STEP: usize = 4;
+
+/// A ring buffer which grows incrementally.
+///
+/// When a `RingBuf` is full, pushing onto it allocates a buffer of twice the
+/// capacity and copies every element before returning. An
+/// `IncrementalRingBuf` instead allocates the new buffer and leaves the
+/// elements where they are; each later push or pop then moves a few of them
+/// from the back of the old buffer to the front of the new one, the way
+/// incremental rehashing works in hash tables. The worst-case time of a push
+/// is one allocation plus a bounded number of moves, rather than a copy of
+/// the whole ring.
+///
+/// Both buffers are alive until the move completes, and the elements may be
+/// split across both, so there is no `as_slices`. Call `finish_resize` to
+/// complete a resize in one step.
+///
+/// # Example
+///
+/// ```rust
+/// # use ringbuf::IncrementalRingBuf;
+/// let mut ringbuf = IncrementalRingBuf::with_capacity(64);
+/// ringbuf.extend(0..64);
+///
+/// // The ring is full, so this push starts a resize, but moves only a few
+/// // elements.
+/// ringbuf.push_back(64);
+/// assert!(ringbuf.is_resizing());
+/// assert_eq!(ringbuf.capacity(), 128);
+/// assert!(ringbuf.iter().copied().eq(0..65));
+///
+/// ringbuf.finish_resize();
+/// assert!(!ringbuf.is_resizing());
+/// ```
+pub struct IncrementalRingBuf<T> {
+
+    /// The front elements, which are still in the old buffer.
+    /// invariant: `old` is empty and unallocated unless a resize is in
+    /// progress.
+    old: RingBuf<T>,
+
+    /// The back elements, in the current buffer.
+    new: RingBuf<T>,
+}
+
+impl<T> IncrementalRingBuf<T> {
+
+    /// Creates an empty `IncrementalRingBuf`, which does not allocate.
+    #[inline]
+    pub const fn new() -> IncrementalRingBuf<T> {
+        IncrementalRingBuf { old: RingBuf::new(), new: RingBuf::new() }
+    }
+
+    /// Creates an empty `IncrementalRingBuf` which can hold exactly
+    /// `capacity` elements before it starts to resize.
+    pub fn with_capacity(capacity: usize) -> IncrementalRingBuf<T> {
+        IncrementalRingBuf::from_ringbuf(RingBuf::with_capacity(capacity))
+    }
+
+    /// Wraps a `RingBuf`, without copying its elements.
+    ///
+    /// The ring's growth policy and auto shrink setting carry over to each
+    /// buffer the `IncrementalRingBuf` grows into. The growth policy can only
+    /// make the new buffer larger: it is always at least one and a half
+    /// times the size of the old one, so that it never has to grow while a
+    /// resize is in progress.
+    pub fn from_ringbuf(ringbuf: RingBuf<T>) -> IncrementalRingBuf<T> {
+        IncrementalRingBuf { old: RingBuf::new(), new: ringbuf }
+    }
+
+    /// Completes any resize in progress, and returns the elements as a
+    /// `RingBuf`.
+    pub fn into_ringbuf(mut self) -> RingBuf<T> {
+        self.finish_resize();
+        mem::take(&mut self.new)
+    }
+
+    /// Returns the number of elements the ring can hold before it has to
+    /// start another resize.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.new.capacity()
+    }
+
+    /// Returns the number of elements in the ring.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.old.len() + self.new.len()
+    }
+
+    /// Returns `true` if the ring contains no elements.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns `true` if some elements are still in the old buffer of an
+    /// earlier resize.
+    #[inline]
+    pub fn is_resizing(&self) -> bool {
+        !self.old.is_empty()
+    }
+
+    /// Moves every remaining element out of the old buffer, and frees it.
+    pub fn finish_resize(&mut self) {
+        self.migrate(usize::MAX);
+    }
+
+    /// Returns a reference to the value at index `index`, or `None` if the
+    /// index is out of bounds.
+    #[inline]
+    pub fn get(&self, index: usize) -> Option<&T> {
+        match index.checked_sub(self.old.len()) {
+            None => self.old.get(index),
+            Some(index) => self.new.get(index),
+        }
+    }
+
+    /// Returns a mutable reference to the value at index `index`, or `None`
+    /// if the index is out of bounds.
+    #[inline]
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        match index.checked_sub(self.old.len()) {
+            None => self.old.get_mut(index),
+            Some(index) => self.new.get_mut(index),
+        }
+    }
+
+    /// Provides a reference to the front element, or `None` if the ring is
+    /// empty.
+    #[inline]
+    pub fn front(&self) -> Option<&T> {
+        self.old.front().or_else(|| self.new.front())
+    }
+
+    /// Provides a reference to the back element, or `None` if the ring is
+    /// empty.
+    #[inline]
+    pub fn back(&self) -> Option<&T> {
+        self.new.back().or_else(|| self.old.back())
+    }
+
+    /// Appends an element to the back of the ring, starting a resize if it
+    /// is full.
+    pub fn push_back(&mut self, value: T) {
+        self.grow_if_full();
+        self.migrate(MIGRATE_STEP);
+        self.new.push_back(value);
+    }
+
+    /// Prepends an element to the ring, starting a resize if it is full.
+    pub fn push_front(&mut self, value: T) {
+        self.grow_if_full();
+        self.migrate(MIGRATE_STEP);
+        if self.old.is_empty() {
+            self.new.push_front(value);
+        } else {
+            // The step above made room in the old buffer, so this does not
+            // reallocate.
+            self.old.push_front(value);
+        }
+    }
+
+    /// Removes the first element and returns it, or `None` if the ring is
+    /// empty.
+    pub fn pop_front(&mut self) -> Option<T> {
+        self.migrate(MIGRATE_STEP);
+        self.old.pop_front().or_else(|| self.new.pop_front())
+    }
+
+    /// Removes the last element and returns it, or `None` if the ring is
+    /// empty.
+    pub fn pop_back(&mut self) -> Option<T> {
+        self.migrate(MIGRATE_STEP);
+        self.new.pop_back().or_else(|| self.old.pop_back())
+    }
+
+    /// Clears the ring, removing all values and freeing the old buffer of a
+    /// resize in progress.
+    pub fn clear(&mut self) {
+        self.old = RingBuf::new();
+        self.new.clear();
+    }
+
+    /// Returns a front-to-back iterator.
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter { old: self.old.iter(), new: self.new.iter() }
+    }
+
+    /// Returns a front-to-back iterator which returns mutable references.
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        IterMut { old: self.old.iter_mut(), new: self.new.iter_mut() }
+    }
+
+    /// Starts a resize if the current buffer is full.
+    #[inline]
+    fn grow_if_full(&mut self) {
+        if self.new.len() < self.new.capacity() { return }
+
+        // Each operation moves more elements than it adds, so the previous
+        // resize has always finished by now. Finish it anyway, so that there
+        // are never more than two buffers.
+        self.finish_resize();
+        let cap = self.new.capacity();
+        let capacity = cmp::max(self.new.growth_policy().grow(cap, cap + 1),
+                                cap.saturating_add(cap / 2));
+        let mut ringbuf = RingBuf::with_capacity(capacity);
+        ringbuf.set_growth_policy(self.new.growth_policy());
+        ringbuf.set_auto_shrink(self.new.auto_shrink());
+        self.old = mem::replace(&mut self.new, ringbuf);
+
+        // Popping from the old buffer must not reallocate it.
+        self.old.set_auto_shrink(None);
+    }
+
+    /// Moves up to `count` elements from the back of the old buffer to the
+    /// front of the new one, and frees the old buffer once it is empty.
+    ///
+    /// The new buffer has at least one and a half times the capacity of the
+    /// old one, and each operation moves at least three more elements than
+    /// it adds, so the new buffer never needs to grow while it is being
+    /// filled.
+    fn migrate(&mut self, count: usize) {
+        if self.old.is_empty() { return }
+        for _ in 0..cmp::min(count, self.old.len()) {
+            let value = self.old.pop_back().unwrap();
+            self.new.push_front(value);
+        }
+        if self.old.is_empty() {
+            self.old = RingBuf::new();
+        }
+    }
+}
+
+deque_impls!(IncrementalRingBuf<T>);
+
+impl<T: Clone> Clone for IncrementalRingBuf<T> {
+    fn clone(&self) -> IncrementalRingBuf<T> {
+        IncrementalRingBuf { old: self.old.clone(), new: self.new.clone() }
+    }
+}
+
+impl<T> From<RingBuf<T>> for IncrementalRingBuf<T> {
+    fn from(ringbuf: RingBuf<T>) -> IncrementalRingBuf<T> {
+        IncrementalRingBuf::from_ringbuf(ringbuf)
+    }
+}
+
+impl<T> Extend<T> for IncrementalRingBuf<T> {
+    fn extend<I: IntoIterator<Item=T>>(&mut self, iterable: I) {
+        for value in iterable {
+            self.push_back(value);
+        }
+    }
+}
+
+impl<T> IntoIterator for IncrementalRingBuf<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> IntoIter<T> {
+        IntoIter { old: self.old.into_iter(), new: self.new.into_iter() }
+    }
+}
+
+/// Implements the iterator traits for an iterator which yields the items of
+/// its `old` iterator followed by those of its `new` iterator.
+macro_rules! chain_iterator {
+    ($name:ident<$($lt:lifetime,)? $t:ident>, $item:ty) => {
+        impl<$($lt,)? $t> Iterator for $name<$($lt,)? $t> {
+            type Item = $item;
+
+            #[inline]
+            fn next(&mut self) -> Option<$item> {
+                self.old.next().or_else(|| self.new.next())
+            }
+
+            #[inline]
+            fn size_hint(&self) -> (usize, Option<usize>) {
+                let len = self.old.len() + self.new.len();
+                (len, Some(len))
+            }
+        }
+
+        impl<$($lt,)? $t> DoubleEndedIterator for $name<$($lt,)? $t> {
+            #[inline]
+            fn next_back(&mut self) -> Option<$item> {
+                self.new.next_back().or_else(|| self.old.next_back())
+            }
+        }
+
+        impl<$($lt,)? $t> ExactSizeIterator for $name<$($lt,)? $t> {}
+
+        impl<$($lt,)? $t> FusedIterator for $name<$($lt,)? $t> {}
+    };
+}
+
+/// `IncrementalRingBuf` iterator.
+#[derive(Clone, Debug)]
+pub struct Iter<'a, T> {
+    old: super::Iter<'a, T>,
+    new: super::Iter<'a, T>,
+}
+
+chain_iterator!(Iter<'a, T>, &'a T);
+
+/// `IncrementalRingBuf` mutable iterator.
+#[derive(Debug)]
+pub struct IterMut<'a, T> {
+    old: super::IterMut<'a, T>,
+    new: super::IterMut<'a, T>,
+}
+
+chain_iterator!(IterMut<'a, T>, &'a mut T);
+
+/// An iterator that moves out of an `IncrementalRingBuf`.
+#[derive(Clone, Debug)]
+pub struct IntoIter<T> {
+    old: super::IntoIter<T>,
+    new: super::IntoIter<T>,
+}
+
+chain_iterator!(IntoIter<T>, T);
+
+#[cfg(test)]
+mod checks {
+    use std::collections::VecDeque;
+    use std::rc::Rc;
+
+    use super::IncrementalRingBuf;
+    use crate::model::{self, Model};
+    use crate::{GrowthPolicy, RingBuf};
+
+    impl Model for IncrementalRingBuf<i32> {
+        fn apply(&mut self, model: &mut VecDeque<i32>, op: u8, _: i32, _: usize) -> bool {
+            match op % 2 {
+                0 => {
+                    self.finish_resize();
+                    !self.is_resizing()
+                }
+                _ => *model == self.clone().into_ringbuf().into_vec(),
+            }
+        }
+    }
+
+    #[test]
+    fn check_model() {
+        model::check_model::<IncrementalRingBuf<i32>>();
+    }
+
+    #[test]
+    fn test_drop_items() {
+        model::check_drop_items::<IncrementalRingBuf<Rc<()>>>(100);
+    }
+
+    #[test]
+    fn test_zero_sized() {
+        model::check_zero_sized::<IncrementalRingBuf<()>>(100);
+    }
+
+    #[test]
+    fn test_bounded_moves() {
+        let mut ringbuf = IncrementalRingBuf::with_capacity(1024);
+        ringbuf.extend(0..1024);
+        assert!(!ringbuf.is_resizing());
+
+        // Each operation moves a few elements, and the new buffer never
+        // reallocates before the old one is empty.
+        ringbuf.push_back(1024);
+        assert_eq!(ringbuf.capacity(), 2048);
+        let mut ops = 1;
+        while ringbuf.is_resizing() {
+            assert!(ringbuf.old.len() >= 1024usize.saturating_sub(4 * ops));
+            if ops % 2 == 0 {
+                ringbuf.push_front(-(ops as i32));
+            } else {
+                ringbuf.push_back(1024 + ops as i32);
+            }
+            ops += 1;
+            assert_eq!(ringbuf.capacity(), 2048);
+        }
+        assert_eq!(ringbuf.old.capacity(), 0);
+        assert_eq!(ringbuf.len(), 1024 + ops);
+    }
+
+    #[test]
+    fn test_keeps_policies() {
+        let mut ringbuf = RingBuf::with_capacity(64);
+        ringbuf.set_growth_policy(GrowthPolicy::Increment(8));
+        ringbuf.set_auto_shrink(Some(4));
+        let mut ringbuf = IncrementalRingBuf::from_ringbuf(ringbuf);
+        ringbuf.extend(0..65);
+
+        // The increment is too small to finish the resize without growing,
+        // so the capacity grows by half instead.
+        assert!(ringbuf.is_resizing());
+        assert_eq!(ringbuf.capacity(), 96);
+        assert_eq!(ringbuf.old.auto_shrink(), None);
+        assert_eq!(ringbuf.new.growth_policy(), GrowthPolicy::Increment(8));
+        assert_eq!(ringbuf.new.auto_shrink(), Some(4));
+
+        // Popping from the old buffer does not shrink it.
+        let mut popped = 0;
+        while ringbuf.is_resizing() {
+            assert_eq!(ringbuf.pop_front(), Some(popped));
+            assert!(ringbuf.old.is_empty() || ringbuf.old.capacity() == 64);
+            popped += 1;
+        }
+        assert_eq!(ringbuf.len(), 65 - popped as usize);
+
+        let mut ringbuf = ringbuf.into_ringbuf();
+        assert_eq!(ringbuf.growth_policy(), GrowthPolicy::Increment(8));
+        assert_eq!(ringbuf.auto_shrink(), Some(4));
+        while ringbuf.len() > 24 {
+            ringbuf.pop_back();
+        }
+        assert_eq!(ringbuf.capacity(), 48);
+
+        // A large enough increment is used as is.
+        let mut ringbuf = RingBuf::with_capacity(4);
+        ringbuf.set_growth_policy(GrowthPolicy::Increment(100));
+        let mut ringbuf = IncrementalRingBuf::from_ringbuf(ringbuf);
+        ringbuf.extend(0..5);
+        assert_eq!(ringbuf.capacity(), 104);
+    }
+}
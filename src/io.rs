@@ -0,0 +1,373 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+//
+
+//! `std::io` implementations for byte ring buffers.
+
+use std::cmp;
+use std::io::{self, BufRead, IoSlice, IoSliceMut, Read, Write};
+use std::ptr;
+use std::slice;
+
+use super::{Allocator, RingBuf};
+
+impl<A: Allocator> RingBuf<u8, A> {
+
+    /// Writes bytes from the front of the ring to `writer` with a single
+    /// `write_vectored` call, and removes the bytes which were written.
+    ///
+    /// Both slices of the ring are handed to the writer, so a wrapped ring is
+    /// written without first being made contiguous. Returns the number of
+    /// bytes written.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use ringbuf::RingBuf;
+    /// let mut ringbuf: RingBuf<u8> = RingBuf::from(*b"hello");
+    /// let mut out = Vec::new();
+    /// assert_eq!(ringbuf.write_to(&mut out).unwrap(), 5);
+    /// assert_eq!(out, b"hello");
+    /// assert!(ringbuf.is_empty());
+    /// ```
+    pub fn write_to<W>(&mut self, writer: &mut W) -> io::Result<usize> where W: Write + ?Sized {
+        let (slice1, slice2) = self.as_slices();
+        let n = writer.write_vectored(&[IoSlice::new(slice1), IoSlice::new(slice2)])?;
+        assert!(n <= self.len, "writer reported more bytes than it was given");
+        unsafe { self.forget_front(n); }
+        Ok(n)
+    }
+
+    /// Reads bytes from `reader` into the free space at the back of the ring
+    /// with a single `read_vectored` call, and returns the number of bytes
+    /// read.
+    ///
+    /// The free space may wrap around the end of the buffer, in which case
+    /// both halves are handed to the reader. The ring does not grow, so this
+    /// returns `Ok(0)` when it is full as well as at end of file; call
+    /// `reserve` first to make room.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use ringbuf::RingBuf;
+    /// let mut ringbuf: RingBuf<u8> = RingBuf::with_capacity(4);
+    /// let mut input: &[u8] = b"hello";
+    /// assert_eq!(ringbuf.read_from(&mut input).unwrap(), 4);
+    /// assert_eq!(ringbuf.read_from(&mut input).unwrap(), 0);
+    /// assert!(ringbuf.iter().eq(b"hell"));
+    /// ```
+    pub fn read_from<R>(&mut self, reader: &mut R) -> io::Result<usize> where R: Read + ?Sized {
+        let offset = self.get_back_offset();
+        let free = self.cap - self.len;
+        let len1 = cmp::min(free, self.cap - offset);
+        let n = unsafe {
+            // `Read` requires initialized buffers, so zero the free space
+            // past the part of the buffer which has ever been written. After
+            // that the whole buffer is initialized.
+            self.zero_uninit(offset, len1);
+            self.zero_uninit(0, free - len1);
+            self.init = self.cap;
+            let slice1 = slice::from_raw_parts_mut(self.ptr.add(offset), len1);
+            let slice2 = slice::from_raw_parts_mut(self.ptr, free - len1);
+            reader.read_vectored(&mut [IoSliceMut::new(slice1), IoSliceMut::new(slice2)])?
+        };
+        assert!(n <= free, "reader reported more bytes than it was given");
+        self.len += n;
+        Ok(n)
+    }
+
+    /// Zeroes the part of the `len` free slots at `offset` which lies past
+    /// the initialized start of the buffer.
+    unsafe fn zero_uninit(&mut self, offset: usize, len: usize) {
+        let start = cmp::max(offset, self.init);
+        if start < offset + len {
+            ptr::write_bytes(self.ptr.add(start), 0, offset + len - start);
+        }
+    }
+}
+
+/// Reads bytes from the front of the ring, removing them.
+///
+/// # Example
+///
+/// ```rust
+/// # use ringbuf::RingBuf;
+/// use std::io::Read;
+///
+/// let mut ringbuf: RingBuf<u8> = RingBuf::from(*b"hello");
+/// let mut buf = [0; 4];
+/// assert_eq!(ringbuf.read(&mut buf).unwrap(), 4);
+/// assert_eq!(&buf, b"hell");
+/// assert!(ringbuf.iter().eq(b"o"));
+/// ```
+impl<A: Allocator> Read for RingBuf<u8, A> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let count = cmp::min(buf.len(), self.len);
+        let (ptr1, len1, ptr2, _) = self.get_slice_ptrs();
+        let len1 = cmp::min(count, len1);
+        unsafe {
+            ptr::copy_nonoverlapping(ptr1, buf.as_mut_ptr(), len1);
+            ptr::copy_nonoverlapping(ptr2, buf.as_mut_ptr().add(len1), count - len1);
+            self.forget_front(count);
+        }
+        Ok(count)
+    }
+
+    fn read_exact(&mut self, buf: &mut [u8]) -> io::Result<()> {
+        if buf.len() > self.len {
+            // Unlike `&[u8]` and `VecDeque<u8>`, which consume their input
+            // on this error, leave the ring untouched so the read can be
+            // retried once more bytes have been written.
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        self.read(buf).map(|_| ())
+    }
+}
+
+/// Writes bytes to the back of the ring, growing it as needed.
+///
+/// # Example
+///
+/// ```rust
+/// # use ringbuf::RingBuf;
+/// use std::io::Write;
+///
+/// let mut ringbuf = RingBuf::new();
+/// write!(ringbuf, "{}-{}", 1, 2).unwrap();
+/// assert!(ringbuf.iter().eq(b"1-2"));
+/// ```
+impl<A: Allocator> Write for RingBuf<u8, A> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.reserve(buf.len());
+        unsafe { self.copy_to_back(buf.as_ptr(), buf.len()); }
+        Ok(buf.len())
+    }
+
+    fn write_all(&mut self, buf: &[u8]) -> io::Result<()> {
+        self.write(buf).map(|_| ())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Exposes the first contiguous slice of the ring as the read buffer.
+///
+/// # Example
+///
+/// ```rust
+/// # use ringbuf::RingBuf;
+/// use std::io::BufRead;
+///
+/// let mut ringbuf: RingBuf<u8> = RingBuf::from(*b"one\ntwo\n");
+/// let lines: Vec<String> = ringbuf.lines().map(Result::unwrap).collect();
+/// assert_eq!(lines, ["one", "two"]);
+/// ```
+impl<A: Allocator> BufRead for RingBuf<u8, A> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        Ok(self.as_slices().0)
+    }
+
+    fn consume(&mut self, amt: usize) {
+        let amt = cmp::min(amt, self.len);
+        unsafe { self.forget_front(amt); }
+    }
+}
+
+#[cfg(test)]
+mod checks {
+    use std::collections::VecDeque;
+    use std::io::{self, BufRead, ErrorKind, Read, Write};
+    #[cfg(unix)]
+    use std::os::unix::net::UnixStream;
+
+    use quickcheck::quickcheck;
+
+    use crate::RingBuf;
+
+    #[test]
+    fn check_read_write() {
+        // Each op writes the bytes, or reads into a buffer of the given size.
+        fn prop(ops: Vec<Result<Vec<u8>, u8>>, capacity: u8) -> bool {
+            let mut ringbuf = RingBuf::with_capacity(capacity as usize);
+            let mut model = VecDeque::new();
+
+            for op in ops {
+                match op {
+                    Ok(bytes) => {
+                        if ringbuf.write(&bytes).unwrap() != bytes.len() { return false }
+                        model.extend(bytes);
+                    }
+                    Err(size) => {
+                        let mut buf = vec![0; size as usize];
+                        let n = ringbuf.read(&mut buf).unwrap();
+                        let expected: Vec<u8> = model.drain(..n).collect();
+                        if n != std::cmp::min(size as usize, n + model.len()) { return false }
+                        if buf[..n] != expected[..] { return false }
+                    }
+                }
+                if !ringbuf.iter().eq(model.iter()) { return false }
+            }
+            true
+        }
+
+        quickcheck(prop as fn(Vec<Result<Vec<u8>, u8>>, u8) -> bool);
+    }
+
+    #[test]
+    fn test_wrapped() {
+        let mut ringbuf: RingBuf<u8> = RingBuf::with_capacity(8);
+        ringbuf.write_all(b"abcdef").unwrap();
+        let mut buf = [0; 4];
+        ringbuf.read_exact(&mut buf).unwrap();
+        ringbuf.write_all(b"ghij").unwrap();
+        assert_eq!(ringbuf.capacity(), 8);
+        assert!(!ringbuf.as_slices().1.is_empty());
+
+        assert_eq!(ringbuf.fill_buf().unwrap(), b"efgh");
+        ringbuf.consume(3);
+        assert_eq!(ringbuf.fill_buf().unwrap(), b"h");
+        ringbuf.consume(1);
+        assert_eq!(ringbuf.fill_buf().unwrap(), b"ij");
+
+        let mut buf = [0; 3];
+        assert_eq!(ringbuf.read_exact(&mut buf).unwrap_err().kind(), ErrorKind::UnexpectedEof);
+        assert!(ringbuf.iter().eq(b"ij"));
+        let mut rest = Vec::new();
+        ringbuf.read_to_end(&mut rest).unwrap();
+        assert_eq!(rest, b"ij");
+        assert!(ringbuf.fill_buf().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_read_across_wrap() {
+        let mut ringbuf: RingBuf<u8> = RingBuf::with_capacity(4);
+        ringbuf.write_all(b"abc").unwrap();
+        ringbuf.consume(2);
+        ringbuf.write_all(b"def").unwrap();
+        assert_eq!(ringbuf.as_slices(), (&b"cd"[..], &b"ef"[..]));
+
+        let mut buf = [0; 8];
+        assert_eq!(ringbuf.read(&mut buf).unwrap(), 4);
+        assert_eq!(&buf[..4], b"cdef");
+        assert!(ringbuf.is_empty());
+    }
+
+    /// Wraps the free space around the end of the buffer: the ring holds
+    /// `held` bytes starting at offset 6 of 8.
+    fn wrapped(held: &[u8]) -> RingBuf<u8> {
+        let mut ringbuf: RingBuf<u8> = RingBuf::with_capacity(8);
+        ringbuf.write_all(&[0; 6]).unwrap();
+        ringbuf.consume(6);
+        ringbuf.write_all(held).unwrap();
+        assert_eq!(ringbuf.capacity(), 8);
+        ringbuf
+    }
+
+    #[test]
+    fn test_read_exact_short() {
+        let mut ringbuf: RingBuf<u8> = RingBuf::with_capacity(4);
+        ringbuf.write_all(b"abc").unwrap();
+        ringbuf.consume(2);
+        ringbuf.write_all(b"def").unwrap();
+
+        let mut buf = [0; 5];
+        let error = ringbuf.read_exact(&mut buf).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::UnexpectedEof);
+        assert_eq!(buf, [0; 5]);
+        assert!(ringbuf.iter().eq(b"cdef"));
+
+        ringbuf.read_exact(&mut buf[..4]).unwrap();
+        assert_eq!(&buf[..4], b"cdef");
+        assert!(ringbuf.is_empty());
+    }
+
+    #[test]
+    fn test_read_from_write_to() {
+        // The data wraps, and the free space is in one piece.
+        let mut ringbuf = wrapped(b"abcd");
+        let mut out = Vec::new();
+        assert_eq!(ringbuf.write_to(&mut out).unwrap(), 4);
+        assert_eq!(out, b"abcd");
+
+        // The free space wraps.
+        let mut ringbuf = wrapped(b"a");
+        let mut input: &[u8] = b"bcdefghijk";
+        assert_eq!(ringbuf.read_from(&mut input).unwrap(), 7);
+        assert!(ringbuf.iter().eq(b"abcdefgh"));
+        assert_eq!(ringbuf.read_from(&mut input).unwrap(), 0);
+        assert_eq!(input, b"ijk");
+    }
+
+    #[test]
+    fn test_read_from_zeroes_once() {
+        let mut ringbuf: RingBuf<u8> = RingBuf::with_capacity(8);
+        assert_eq!(ringbuf.init, 0);
+        let mut input: &[u8] = b"abc";
+        assert_eq!(ringbuf.read_from(&mut input).unwrap(), 3);
+        assert_eq!(ringbuf.init, 8);
+
+        // Growing keeps the initialized bytes, copying does not.
+        ringbuf.reserve_exact(13);
+        assert_eq!(ringbuf.init, 8);
+        let mut input: &[u8] = &[1; 20];
+        assert_eq!(ringbuf.read_from(&mut input).unwrap(), 13);
+        assert_eq!(ringbuf.init, 16);
+        ringbuf.shrink_to_fit();
+        assert_eq!(ringbuf.init, ringbuf.len());
+        assert!(ringbuf.iter().take(3).eq(b"abc"));
+        assert!(ringbuf.iter().skip(3).all(|&b| b == 1));
+    }
+
+    #[test]
+    fn test_pipe() {
+        let (mut reader, mut writer) = io::pipe().unwrap();
+        let mut ringbuf = wrapped(b"abcdef");
+        assert!(!ringbuf.as_slices().1.is_empty());
+
+        // Pipes may accept fewer bytes than offered, so loop.
+        while !ringbuf.is_empty() {
+            ringbuf.write_to(&mut writer).unwrap();
+        }
+        drop(writer);
+
+        let mut ringbuf = wrapped(b"");
+        let mut read = 0;
+        loop {
+            match ringbuf.read_from(&mut reader).unwrap() {
+                0 => break,
+                n => read += n,
+            }
+        }
+        assert_eq!(read, 6);
+        assert!(ringbuf.iter().eq(b"abcdef"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_unix_socket() {
+        let (mut left, mut right) = UnixStream::pair().unwrap();
+
+        // Echo a wrapped ring through the socket pair and back.
+        let mut ringbuf = wrapped(b"ping!");
+        while !ringbuf.is_empty() {
+            ringbuf.write_to(&mut left).unwrap();
+        }
+        let mut echo = wrapped(b"");
+        while echo.len() < 5 {
+            echo.read_from(&mut right).unwrap();
+        }
+        while !echo.is_empty() {
+            echo.write_to(&mut right).unwrap();
+        }
+        drop(right);
+
+        while ringbuf.read_from(&mut left).unwrap() != 0 {}
+        assert!(ringbuf.iter().eq(b"ping!"));
+    }
+}
@@ -0,0 +1,110 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+//
+
+//! Macros shared by the deque types.
+
+/// Implements the standard traits which every deque type provides in terms of
+/// its `new`, `len`, `get`, `get_mut`, `iter` and `iter_mut` methods and its
+/// `Extend<T>` implementation.
+///
+/// The deque's module must define `Iter` and `IterMut` iterator types.
+macro_rules! deque_impls {
+    ($deque:ident<T $(, const $n:ident: usize)?>) => {
+        impl<T $(, const $n: usize)?> Default for $deque<T $(, $n)?> {
+            #[inline]
+            fn default() -> $deque<T $(, $n)?> {
+                $deque::new()
+            }
+        }
+
+        impl<T $(, const $n: usize)?> core::iter::FromIterator<T> for $deque<T $(, $n)?> {
+            fn from_iter<I: IntoIterator<Item=T>>(iterable: I) -> $deque<T $(, $n)?> {
+                let mut deque = $deque::new();
+                deque.extend(iterable);
+                deque
+            }
+        }
+
+        impl<'a, T: Copy + 'a $(, const $n: usize)?> Extend<&'a T> for $deque<T $(, $n)?> {
+            fn extend<I: IntoIterator<Item=&'a T>>(&mut self, iterable: I) {
+                self.extend(iterable.into_iter().cloned());
+            }
+        }
+
+        impl<T $(, const $n: usize)?> core::ops::Index<usize> for $deque<T $(, $n)?> {
+            type Output = T;
+
+            #[inline]
+            fn index(&self, index: usize) -> &T {
+                self.get(index).expect("index out of bounds")
+            }
+        }
+
+        impl<T $(, const $n: usize)?> core::ops::IndexMut<usize> for $deque<T $(, $n)?> {
+            #[inline]
+            fn index_mut(&mut self, index: usize) -> &mut T {
+                self.get_mut(index).expect("index out of bounds")
+            }
+        }
+
+        impl<T: PartialEq $(, const $n: usize)?> PartialEq for $deque<T $(, $n)?> {
+            #[inline]
+            fn eq(&self, other: &$deque<T $(, $n)?>) -> bool {
+                self.len() == other.len() && self.iter().eq(other.iter())
+            }
+        }
+
+        impl<T: Eq $(, const $n: usize)?> Eq for $deque<T $(, $n)?> {}
+
+        impl<T: PartialOrd $(, const $n: usize)?> PartialOrd for $deque<T $(, $n)?> {
+            #[inline]
+            fn partial_cmp(&self, other: &$deque<T $(, $n)?>) -> Option<core::cmp::Ordering> {
+                self.iter().partial_cmp(other.iter())
+            }
+        }
+
+        impl<T: Ord $(, const $n: usize)?> Ord for $deque<T $(, $n)?> {
+            #[inline]
+            fn cmp(&self, other: &$deque<T $(, $n)?>) -> core::cmp::Ordering {
+                self.iter().cmp(other.iter())
+            }
+        }
+
+        impl<T: core::hash::Hash $(, const $n: usize)?> core::hash::Hash for $deque<T $(, $n)?> {
+            fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
+                core::hash::Hash::hash(&self.len(), state);
+                for elt in self.iter() {
+                    core::hash::Hash::hash(elt, state);
+                }
+            }
+        }
+
+        impl<T: core::fmt::Debug $(, const $n: usize)?> core::fmt::Debug for $deque<T $(, $n)?> {
+            fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+                f.debug_list().entries(self.iter()).finish()
+            }
+        }
+
+        impl<'a, T $(, const $n: usize)?> IntoIterator for &'a $deque<T $(, $n)?> {
+            type Item = &'a T;
+            type IntoIter = Iter<'a, T>;
+
+            fn into_iter(self) -> Iter<'a, T> {
+                self.iter()
+            }
+        }
+
+        impl<'a, T $(, const $n: usize)?> IntoIterator for &'a mut $deque<T $(, $n)?> {
+            type Item = &'a mut T;
+            type IntoIter = IterMut<'a, T>;
+
+            fn into_iter(self) -> IterMut<'a, T> {
+                self.iter_mut()
+            }
+        }
+    };
+}
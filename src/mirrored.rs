@@ -0,0 +1,337 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+//
+
+//! A byte ring buffer whose pages are mapped twice back to back, so that its
+//! contents are always contiguous in memory.
+
+use std::cmp;
+use std::fmt;
+use std::io::{self, BufRead, Read, Write};
+use std::ptr;
+use std::slice;
+
+/// A fixed-capacity byte ring buffer which exposes its contents as a single
+/// contiguous slice.
+///
+/// The buffer is a memory file mapped twice into adjacent virtual memory, so
+/// the byte after the last byte of the buffer is the first byte again. The
+/// readable bytes, and the free space, can then always be addressed as one
+/// slice even when they wrap around the end of the buffer, and parsers which
+/// need contiguous input can work on the ring directly.
+///
+/// The capacity is rounded up to a multiple of the page size.
+pub struct MirroredRingBuf {
+
+    /// The start of the first of the two mappings.
+    ptr: *mut u8,
+
+    /// The offset of the first byte.
+    lo: usize,
+
+    /// The number of bytes in the ring.
+    len: usize,
+
+    /// The size of each mapping.
+    cap: usize,
+}
+
+unsafe impl Send for MirroredRingBuf {}
+unsafe impl Sync for MirroredRingBuf {}
+
+/// Returns the error for the last failed system call, after running the
+/// cleanup.
+fn last_error(cleanup: impl FnOnce()) -> io::Error {
+    let error = io::Error::last_os_error();
+    cleanup();
+    error
+}
+
+impl MirroredRingBuf {
+
+    /// Creates an empty ring buffer which holds at least `capacity` bytes.
+    ///
+    /// The capacity is rounded up to a multiple of the page size, and is at
+    /// least one page. Returns an error if the memory file can not be created
+    /// or mapped.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use ringbuf::MirroredRingBuf;
+    /// let ringbuf = MirroredRingBuf::with_capacity(1000).unwrap();
+    /// assert!(ringbuf.capacity() >= 1000);
+    /// ```
+    pub fn with_capacity(capacity: usize) -> io::Result<MirroredRingBuf> {
+        let page_size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) } as usize;
+        let cap = cmp::max(capacity, 1).checked_next_multiple_of(page_size)
+                                       .filter(|&cap| cap <= isize::MAX as usize / 2)
+                                       .ok_or_else(|| {
+                                           io::Error::new(io::ErrorKind::InvalidInput,
+                                                          "capacity overflow")
+                                       })?;
+
+        unsafe {
+            let fd = libc::memfd_create(c"ringbuf".as_ptr(), libc::MFD_CLOEXEC);
+            if fd < 0 { return Err(io::Error::last_os_error()) }
+            let close = || { libc::close(fd); };
+
+            if libc::ftruncate(fd, cap as libc::off_t) < 0 { return Err(last_error(close)) }
+
+            // Reserve twice the capacity of address space, then map the file
+            // over each half.
+            let ptr = libc::mmap(ptr::null_mut(), 2 * cap, libc::PROT_NONE,
+                                 libc::MAP_PRIVATE | libc::MAP_ANONYMOUS, -1, 0);
+            if ptr == libc::MAP_FAILED { return Err(last_error(close)) }
+            let unmap = || { libc::munmap(ptr, 2 * cap); close(); };
+
+            for half in [ptr, ptr.add(cap)] {
+                let mapped = libc::mmap(half, cap, libc::PROT_READ | libc::PROT_WRITE,
+                                        libc::MAP_SHARED | libc::MAP_FIXED, fd, 0);
+                if mapped == libc::MAP_FAILED { return Err(last_error(unmap)) }
+            }
+
+            // The mappings keep the file alive.
+            close();
+            Ok(MirroredRingBuf { ptr: ptr as *mut u8, lo: 0, len: 0, cap })
+        }
+    }
+
+    /// Returns the number of bytes the ring buffer can hold.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.cap
+    }
+
+    /// Returns the number of bytes in the ring buffer.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the ring buffer is empty.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns `true` if the ring buffer is full.
+    #[inline]
+    pub fn is_full(&self) -> bool {
+        self.len == self.cap
+    }
+
+    /// Removes all bytes from the ring buffer.
+    pub fn clear(&mut self) {
+        self.lo = 0;
+        self.len = 0;
+    }
+
+    /// Returns the bytes in the ring buffer as a single slice.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use ringbuf::MirroredRingBuf;
+    /// let mut ringbuf = MirroredRingBuf::with_capacity(1).unwrap();
+    /// let cap = ringbuf.capacity();
+    ///
+    /// // Wrap the contents around the end of the buffer.
+    /// ringbuf.push_slice(&vec![0; cap - 2]);
+    /// ringbuf.consume(cap - 2);
+    /// ringbuf.push_slice(b"abcd");
+    /// assert_eq!(ringbuf.as_slice(), b"abcd");
+    /// ```
+    #[inline]
+    pub fn as_slice(&self) -> &[u8] {
+        unsafe { slice::from_raw_parts(self.ptr.add(self.lo), self.len) }
+    }
+
+    /// Returns the bytes in the ring buffer as a single mutable slice.
+    #[inline]
+    pub fn as_mut_slice(&mut self) -> &mut [u8] {
+        unsafe { slice::from_raw_parts_mut(self.ptr.add(self.lo), self.len) }
+    }
+
+    /// Returns the free space after the back of the ring buffer as a single
+    /// slice. After writing to the start of it, call `commit_back` to append
+    /// the bytes.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use ringbuf::MirroredRingBuf;
+    /// let mut ringbuf = MirroredRingBuf::with_capacity(1).unwrap();
+    /// ringbuf.spare_capacity_mut()[..2].copy_from_slice(b"hi");
+    /// ringbuf.commit_back(2);
+    /// assert_eq!(ringbuf.as_slice(), b"hi");
+    /// ```
+    #[inline]
+    pub fn spare_capacity_mut(&mut self) -> &mut [u8] {
+        unsafe { slice::from_raw_parts_mut(self.ptr.add(self.lo + self.len), self.cap - self.len) }
+    }
+
+    /// Appends the first `n` bytes of `spare_capacity_mut` to the back of the
+    /// ring buffer.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n` is greater than the free capacity.
+    pub fn commit_back(&mut self, n: usize) {
+        assert!(n <= self.cap - self.len, "commit exceeds the free capacity");
+        self.len += n;
+    }
+
+    /// Removes up to `n` bytes from the front of the ring buffer, and returns
+    /// the number removed.
+    pub fn consume(&mut self, n: usize) -> usize {
+        let n = cmp::min(n, self.len);
+        self.lo += n;
+        if self.lo >= self.cap {
+            self.lo -= self.cap;
+        }
+        self.len -= n;
+        n
+    }
+
+    /// Copies as many bytes from `bytes` as fit into the back of the ring
+    /// buffer, and returns the number copied.
+    pub fn push_slice(&mut self, bytes: &[u8]) -> usize {
+        let spare = self.spare_capacity_mut();
+        let n = cmp::min(bytes.len(), spare.len());
+        spare[..n].copy_from_slice(&bytes[..n]);
+        self.len += n;
+        n
+    }
+}
+
+impl Drop for MirroredRingBuf {
+    fn drop(&mut self) {
+        unsafe { libc::munmap(self.ptr as *mut libc::c_void, 2 * self.cap); }
+    }
+}
+
+impl fmt::Debug for MirroredRingBuf {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("MirroredRingBuf")
+         .field("len", &self.len)
+         .field("capacity", &self.cap)
+         .finish()
+    }
+}
+
+/// Reads bytes from the front of the ring buffer, removing them.
+impl Read for MirroredRingBuf {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = cmp::min(buf.len(), self.len);
+        buf[..n].copy_from_slice(&self.as_slice()[..n]);
+        self.consume(n);
+        Ok(n)
+    }
+}
+
+/// Exposes every byte in the ring buffer as the read buffer.
+impl BufRead for MirroredRingBuf {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        Ok(self.as_slice())
+    }
+
+    fn consume(&mut self, amt: usize) {
+        MirroredRingBuf::consume(self, amt);
+    }
+}
+
+/// Writes bytes to the back of the ring buffer. The ring buffer does not
+/// grow, so writes are short when it is nearly full, and return `Ok(0)` when
+/// it is full.
+impl Write for MirroredRingBuf {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        Ok(self.push_slice(buf))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod checks {
+    use std::collections::VecDeque;
+    use std::io::{BufRead, Read, Write};
+
+    use quickcheck::quickcheck;
+
+    use super::MirroredRingBuf;
+
+    #[test]
+    fn check_read_write() {
+        // Each op writes the bytes, or reads into a buffer of the given size.
+        fn prop(ops: Vec<Result<Vec<u8>, u16>>) -> bool {
+            let mut ringbuf = MirroredRingBuf::with_capacity(1).unwrap();
+            let mut model = VecDeque::new();
+
+            for op in ops {
+                match op {
+                    Ok(bytes) => {
+                        // Make the writes large enough to wrap around.
+                        let bytes = bytes.repeat(256);
+                        let n = ringbuf.write(&bytes).unwrap();
+                        if n != std::cmp::min(bytes.len(), ringbuf.capacity() - model.len()) {
+                            return false;
+                        }
+                        model.extend(&bytes[..n]);
+                    }
+                    Err(size) => {
+                        let mut buf = vec![0; size as usize];
+                        let n = ringbuf.read(&mut buf).unwrap();
+                        let expected: Vec<u8> = model.drain(..n).collect();
+                        if buf[..n] != expected[..] { return false }
+                    }
+                }
+                model.make_contiguous();
+                if ringbuf.as_slice() != model.as_slices().0 { return false }
+            }
+            true
+        }
+
+        quickcheck(prop as fn(Vec<Result<Vec<u8>, u16>>) -> bool);
+    }
+
+    #[test]
+    fn test_mirror() {
+        let mut ringbuf = MirroredRingBuf::with_capacity(1).unwrap();
+        let cap = ringbuf.capacity();
+        assert_eq!(cap % 4096, 0);
+
+        ringbuf.push_slice(&vec![7; cap - 1]);
+        ringbuf.consume(cap - 1);
+        assert_eq!(ringbuf.push_slice(b"wrapped"), 7);
+        assert_eq!(ringbuf.fill_buf().unwrap(), b"wrapped");
+
+        // Writes through the second mapping land at the start of the first.
+        ringbuf.as_mut_slice()[1..].copy_from_slice(b"RAPPED");
+        assert_eq!(ringbuf.as_slice(), b"wRAPPED");
+        assert_eq!(ringbuf.spare_capacity_mut().len(), cap - 7);
+
+        let mut line = String::new();
+        ringbuf.push_slice(b"\nrest");
+        ringbuf.read_line(&mut line).unwrap();
+        assert_eq!(line, "wRAPPED\n");
+        assert_eq!(ringbuf.as_slice(), b"rest");
+    }
+
+    #[test]
+    fn test_full() {
+        let mut ringbuf = MirroredRingBuf::with_capacity(1).unwrap();
+        let cap = ringbuf.capacity();
+        assert_eq!(ringbuf.write(&vec![1; cap + 1]).unwrap(), cap);
+        assert!(ringbuf.is_full());
+        assert_eq!(ringbuf.write(b"x").unwrap(), 0);
+        assert!(ringbuf.spare_capacity_mut().is_empty());
+        ringbuf.clear();
+        assert!(ringbuf.is_empty());
+    }
+}
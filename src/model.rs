@@ -0,0 +1,177 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+//
+
+//! Checks shared by the tests of the deque types, which run the same
+//! operations against each of them.
+
+use std::collections::VecDeque;
+use std::rc::Rc;
+
+use quickcheck::quickcheck;
+
+use crate::{ArrayRingBuf, ChunkedDeque, IncrementalRingBuf, Pow2RingBuf, SmallRingBuf};
+
+/// The operations common to the deque types.
+pub(crate) trait Deque<T: 'static>: Clone + IntoIterator<Item=T> {
+    fn new() -> Self;
+    fn len(&self) -> usize;
+    fn is_full(&self) -> bool;
+    fn get(&self, index: usize) -> Option<&T>;
+    fn front(&self) -> Option<&T>;
+    fn back(&self) -> Option<&T>;
+
+    /// Appends an element, or returns it if the deque is full.
+    fn push_back(&mut self, value: T) -> Result<(), T>;
+
+    /// Prepends an element, or returns it if the deque is full.
+    fn push_front(&mut self, value: T) -> Result<(), T>;
+
+    fn pop_front(&mut self) -> Option<T>;
+    fn pop_back(&mut self) -> Option<T>;
+    fn iter(&self) -> impl DoubleEndedIterator<Item=&T>;
+    fn iter_mut(&mut self) -> impl DoubleEndedIterator<Item=&mut T>;
+}
+
+/// Implements `Deque` for a type which grows as needed, by forwarding to its
+/// inherent methods.
+macro_rules! impl_deque {
+    ($deque:ident<T $(, const $n:ident: usize)?>) => {
+        impl<T: Clone + 'static $(, const $n: usize)?> Deque<T> for $deque<T $(, $n)?> {
+            fn new() -> Self { $deque::new() }
+            fn len(&self) -> usize { $deque::len(self) }
+            fn is_full(&self) -> bool { false }
+            fn get(&self, index: usize) -> Option<&T> { $deque::get(self, index) }
+            fn front(&self) -> Option<&T> { $deque::front(self) }
+            fn back(&self) -> Option<&T> { $deque::back(self) }
+            fn push_back(&mut self, value: T) -> Result<(), T> { $deque::push_back(self, value); Ok(()) }
+            fn push_front(&mut self, value: T) -> Result<(), T> { $deque::push_front(self, value); Ok(()) }
+            fn pop_front(&mut self) -> Option<T> { $deque::pop_front(self) }
+            fn pop_back(&mut self) -> Option<T> { $deque::pop_back(self) }
+            fn iter(&self) -> impl DoubleEndedIterator<Item=&T> { $deque::iter(self) }
+            fn iter_mut(&mut self) -> impl DoubleEndedIterator<Item=&mut T> { $deque::iter_mut(self) }
+        }
+    };
+}
+
+impl_deque!(IncrementalRingBuf<T>);
+impl_deque!(Pow2RingBuf<T>);
+impl_deque!(SmallRingBuf<T, const N: usize>);
+impl_deque!(ChunkedDeque<T>);
+
+impl<T: Clone + 'static, const N: usize> Deque<T> for ArrayRingBuf<T, N> {
+    fn new() -> Self { ArrayRingBuf::new() }
+    fn len(&self) -> usize { ArrayRingBuf::len(self) }
+    fn is_full(&self) -> bool { ArrayRingBuf::is_full(self) }
+    fn get(&self, index: usize) -> Option<&T> { ArrayRingBuf::get(self, index) }
+    fn front(&self) -> Option<&T> { ArrayRingBuf::front(self) }
+    fn back(&self) -> Option<&T> { ArrayRingBuf::back(self) }
+    fn push_back(&mut self, value: T) -> Result<(), T> {
+        self.try_push_back(value).map_err(|error| error.into_inner())
+    }
+    fn push_front(&mut self, value: T) -> Result<(), T> {
+        self.try_push_front(value).map_err(|error| error.into_inner())
+    }
+    fn pop_front(&mut self) -> Option<T> { ArrayRingBuf::pop_front(self) }
+    fn pop_back(&mut self) -> Option<T> { ArrayRingBuf::pop_back(self) }
+    fn iter(&self) -> impl DoubleEndedIterator<Item=&T> { ArrayRingBuf::iter(self) }
+    fn iter_mut(&mut self) -> impl DoubleEndedIterator<Item=&mut T> { ArrayRingBuf::iter_mut(self) }
+}
+
+/// The parts of the model check which are specific to a deque type.
+pub(crate) trait Model: Deque<i32> {
+
+    /// Applies operation `op` to both the deque and the model, and returns
+    /// `false` if the deque's result differs. `index` is at most the length.
+    fn apply(&mut self, model: &mut VecDeque<i32>, op: u8, value: i32, index: usize) -> bool;
+
+    /// Returns `false` if the deque breaks an invariant of its type.
+    fn check(&self, _model: &VecDeque<i32>) -> bool {
+        true
+    }
+}
+
+/// Checks a deque type against a `VecDeque` model. Each op is applied to
+/// both, and their contents are compared after every op.
+pub(crate) fn check_model<D: Model>() {
+    fn prop<D: Model>(ops: Vec<(u8, i32, u8)>) -> bool {
+        let mut deque = D::new();
+        let mut model = VecDeque::new();
+
+        for (op, value, index) in ops {
+            let index = index as usize % (model.len() + 1);
+            let full = deque.is_full();
+            match op % 8 {
+                0 | 1 => {
+                    if deque.push_back(value).is_ok() == full { return false }
+                    if !full { model.push_back(value); }
+                }
+                2 => {
+                    if deque.push_front(value).is_ok() == full { return false }
+                    if !full { model.push_front(value); }
+                }
+                3 => if deque.pop_front() != model.pop_front() { return false },
+                4 => if deque.pop_back() != model.pop_back() { return false },
+                5 => if deque.get(index) != model.get(index) { return false },
+                _ => if !deque.apply(&mut model, op / 8, value, index) { return false },
+            }
+
+            if deque.len() != model.len()
+                || deque.front() != model.front()
+                || deque.back() != model.back()
+                || !deque.iter().eq(model.iter())
+                || !deque.iter().rev().eq(model.iter().rev())
+                || !deque.iter_mut().rev().map(|x| *x).eq(model.iter().rev().cloned())
+                || deque.clone().into_iter().ne(model.iter().cloned())
+                || !deque.check(&model)
+            {
+                return false;
+            }
+        }
+        deque.into_iter().eq(model)
+    }
+
+    quickcheck(prop::<D> as fn(Vec<(u8, i32, u8)>) -> bool);
+}
+
+/// Checks that the `n` elements of a deque, its clone and its owning
+/// iterator are each dropped exactly once.
+pub(crate) fn check_drop_items<D: Deque<Rc<()>>>(n: usize) {
+    let item = Rc::new(());
+    let mut deque = D::new();
+    for i in 0..n {
+        let pushed = if i % 2 == 0 {
+            deque.push_back(item.clone())
+        } else {
+            deque.push_front(item.clone())
+        };
+        assert!(pushed.is_ok());
+    }
+    drop(deque.clone());
+    deque.pop_front();
+    assert_eq!(Rc::strong_count(&item), n);
+
+    let mut iter = deque.into_iter();
+    iter.next();
+    assert_eq!(Rc::strong_count(&item), n - 1);
+    drop(iter);
+    assert_eq!(Rc::strong_count(&item), 1);
+}
+
+/// Checks a deque of `n` zero-sized elements.
+pub(crate) fn check_zero_sized<D: Deque<()>>(n: usize) {
+    let mut deque = D::new();
+    for i in 0..n {
+        let pushed = if i % 2 == 0 { deque.push_back(()) } else { deque.push_front(()) };
+        assert!(pushed.is_ok());
+    }
+    assert_eq!(deque.len(), n);
+    assert_eq!(deque.iter().count(), n);
+    assert_eq!(deque.iter_mut().rev().count(), n);
+    assert_eq!(deque.get(n - 1), Some(&()));
+    assert_eq!(deque.pop_back(), Some(()));
+    assert_eq!(deque.into_iter().count(), n - 1);
+}
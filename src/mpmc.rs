@@ -0,0 +1,368 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+//
+
+//! A lock-free bounded multi-producer, multi-consumer queue, using Dmitry
+//! Vyukov's per-slot sequence number algorithm.
+
+use core::cell::UnsafeCell;
+use core::cmp;
+use core::fmt;
+use core::mem::MaybeUninit;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use alloc::boxed::Box;
+
+use super::spsc::CachePadded;
+use super::TryPushError;
+
+/// A slot in the queue.
+///
+/// For the slot at position `pos` (modulo the capacity), `seq == pos` means
+/// the slot is free for the push at `pos`, and `seq == pos + 1` means it
+/// holds the value for the pop at `pos`.
+struct Slot<T> {
+    seq: AtomicUsize,
+    value: UnsafeCell<MaybeUninit<T>>,
+}
+
+/// A bounded queue which can be pushed to and popped from by any number of
+/// threads concurrently.
+///
+/// The capacity is always a power of two, so that positions can be mapped to
+/// slots with a mask. Share the queue between threads with an `Arc`.
+pub struct MpmcQueue<T> {
+
+    /// The position of the next pop.
+    head: CachePadded<AtomicUsize>,
+
+    /// The position of the next push.
+    tail: CachePadded<AtomicUsize>,
+
+    buffer: Box<[Slot<T>]>,
+
+    /// The capacity minus one.
+    mask: usize,
+}
+
+unsafe impl<T: Send> Send for MpmcQueue<T> {}
+unsafe impl<T: Send> Sync for MpmcQueue<T> {}
+
+impl<T> MpmcQueue<T> {
+
+    /// Creates an empty queue which can hold at least `capacity` elements.
+    ///
+    /// The capacity is rounded up to the next power of two, and is at least
+    /// two.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the rounded capacity overflows `usize`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use ringbuf::MpmcQueue;
+    /// let queue: MpmcQueue<i32> = MpmcQueue::with_capacity(10);
+    /// assert_eq!(queue.capacity(), 16);
+    /// ```
+    pub fn with_capacity(capacity: usize) -> MpmcQueue<T> {
+        // A single slot can not tell a full lap from an empty one.
+        let capacity = cmp::max(capacity, 2).checked_next_power_of_two()
+                                            .expect("capacity overflow");
+        let buffer = (0..capacity).map(|seq| Slot {
+            seq: AtomicUsize::new(seq),
+            value: UnsafeCell::new(MaybeUninit::uninit()),
+        }).collect();
+
+        MpmcQueue {
+            head: CachePadded(AtomicUsize::new(0)),
+            tail: CachePadded(AtomicUsize::new(0)),
+            buffer,
+            mask: capacity - 1,
+        }
+    }
+
+    /// Returns the number of elements the queue can hold.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.mask + 1
+    }
+
+    /// Returns the number of elements in the queue.
+    ///
+    /// Other threads may concurrently push and pop elements, so the result
+    /// may be stale as soon as it is returned.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use ringbuf::MpmcQueue;
+    /// let queue = MpmcQueue::with_capacity(4);
+    /// queue.try_push(1).unwrap();
+    /// assert_eq!(queue.len(), 1);
+    /// ```
+    pub fn len(&self) -> usize {
+        loop {
+            // Retry until the tail is stable around the load of the head, so
+            // that the two loads form a consistent snapshot.
+            let tail = self.tail.load(Ordering::SeqCst);
+            let head = self.head.load(Ordering::SeqCst);
+            if self.tail.load(Ordering::SeqCst) == tail {
+                return cmp::min(tail.wrapping_sub(head), self.capacity());
+            }
+        }
+    }
+
+    /// Returns `true` if the queue is empty.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns `true` if the queue is full.
+    #[inline]
+    pub fn is_full(&self) -> bool {
+        self.len() == self.capacity()
+    }
+
+    /// Push an element onto the back of the queue, or return it in an error
+    /// if the queue is full.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use ringbuf::{MpmcQueue, TryPushError};
+    /// let queue = MpmcQueue::with_capacity(2);
+    /// assert!(queue.try_push(1).is_ok());
+    /// assert!(queue.try_push(2).is_ok());
+    /// assert_eq!(queue.try_push(3), Err(TryPushError::Full(3)));
+    /// ```
+    pub fn try_push(&self, value: T) -> Result<(), TryPushError<T>> {
+        let mut pos = self.tail.load(Ordering::Relaxed);
+        loop {
+            let slot = &self.buffer[pos & self.mask];
+            let seq = slot.seq.load(Ordering::Acquire);
+            let diff = seq.wrapping_sub(pos) as isize;
+
+            if diff == 0 {
+                // The slot is free; claim the position.
+                match self.tail.compare_exchange_weak(pos, pos.wrapping_add(1),
+                                                      Ordering::Relaxed,
+                                                      Ordering::Relaxed) {
+                    Ok(_) => {
+                        unsafe { (*slot.value.get()).write(value); }
+                        slot.seq.store(pos.wrapping_add(1), Ordering::Release);
+                        return Ok(());
+                    }
+                    Err(current) => pos = current,
+                }
+            } else if diff < 0 {
+                // The slot still holds the value from the previous lap.
+                return Err(TryPushError::Full(value));
+            } else {
+                // Another producer claimed the position.
+                pos = self.tail.load(Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// Remove the first element of the queue and return it, or `None` if
+    /// the queue is empty.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use ringbuf::MpmcQueue;
+    /// let queue = MpmcQueue::with_capacity(2);
+    /// queue.try_push(1).unwrap();
+    /// assert_eq!(queue.try_pop(), Some(1));
+    /// assert_eq!(queue.try_pop(), None);
+    /// ```
+    pub fn try_pop(&self) -> Option<T> {
+        let mut pos = self.head.load(Ordering::Relaxed);
+        loop {
+            let slot = &self.buffer[pos & self.mask];
+            let seq = slot.seq.load(Ordering::Acquire);
+            let diff = seq.wrapping_sub(pos.wrapping_add(1)) as isize;
+
+            if diff == 0 {
+                // The slot holds a value; claim the position.
+                match self.head.compare_exchange_weak(pos, pos.wrapping_add(1),
+                                                      Ordering::Relaxed,
+                                                      Ordering::Relaxed) {
+                    Ok(_) => {
+                        let value = unsafe { (*slot.value.get()).assume_init_read() };
+                        // Free the slot for the push one lap ahead.
+                        slot.seq.store(pos.wrapping_add(self.mask + 1), Ordering::Release);
+                        return Some(value);
+                    }
+                    Err(current) => pos = current,
+                }
+            } else if diff < 0 {
+                // The slot has not been pushed to in this lap.
+                return None;
+            } else {
+                // Another consumer claimed the position.
+                pos = self.head.load(Ordering::Relaxed);
+            }
+        }
+    }
+}
+
+impl<T> Drop for MpmcQueue<T> {
+    fn drop(&mut self) {
+        let head = *self.head.0.get_mut();
+        let tail = *self.tail.0.get_mut();
+        let mut pos = head;
+        while pos != tail {
+            unsafe { (*self.buffer[pos & self.mask].value.get()).assume_init_drop(); }
+            pos = pos.wrapping_add(1);
+        }
+    }
+}
+
+impl<T> fmt::Debug for MpmcQueue<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("MpmcQueue")
+         .field("len", &self.len())
+         .field("capacity", &self.capacity())
+         .finish()
+    }
+}
+
+#[cfg(test)]
+mod checks {
+    use std::collections::VecDeque;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+
+    use quickcheck::quickcheck;
+
+    use super::MpmcQueue;
+
+    #[test]
+    fn check_sequential_model() {
+        // Each op pushes `Some` values and pops on `None`.
+        fn prop(ops: Vec<Option<i32>>, capacity: u8) -> bool {
+            let queue = MpmcQueue::with_capacity(capacity as usize % 16);
+            let mut model = VecDeque::new();
+
+            for op in ops {
+                match op {
+                    Some(value) => {
+                        let pushed = queue.try_push(value).is_ok();
+                        if pushed != (model.len() < queue.capacity()) { return false }
+                        if pushed { model.push_back(value); }
+                    }
+                    None => {
+                        if queue.try_pop() != model.pop_front() { return false }
+                    }
+                }
+                if queue.len() != model.len() { return false }
+            }
+            true
+        }
+
+        quickcheck(prop as fn(Vec<Option<i32>>, u8) -> bool);
+    }
+
+    #[test]
+    fn check_drop_items() {
+        // This is testing memory safety
+        fn prop(items: Vec<i32>, pops: u8) -> bool {
+            let queue = MpmcQueue::with_capacity(items.len());
+            for item in items {
+                assert!(queue.try_push(Box::new(item)).is_ok());
+            }
+            for _ in 0..pops {
+                queue.try_pop();
+            }
+            true
+        }
+
+        quickcheck(prop as fn(Vec<i32>, u8) -> bool);
+    }
+
+    #[test]
+    fn test_capacity() {
+        assert_eq!(MpmcQueue::<i32>::with_capacity(0).capacity(), 2);
+        assert_eq!(MpmcQueue::<i32>::with_capacity(1).capacity(), 2);
+        assert_eq!(MpmcQueue::<i32>::with_capacity(8).capacity(), 8);
+        assert_eq!(MpmcQueue::<i32>::with_capacity(9).capacity(), 16);
+    }
+
+    /// Pushes `per_producer` unique values from each producer thread, pops
+    /// them from the consumer threads, and checks that every value was
+    /// delivered exactly once.
+    fn stress(producers: usize, consumers: usize, per_producer: usize, capacity: usize) {
+        let queue = Arc::new(MpmcQueue::with_capacity(capacity));
+        let total = producers * per_producer;
+        let popped = Arc::new(AtomicUsize::new(0));
+        let received = Arc::new(Mutex::new(Vec::with_capacity(total)));
+
+        let mut handles = Vec::new();
+        for p in 0..producers {
+            let queue = queue.clone();
+            handles.push(thread::spawn(move || {
+                for i in 0..per_producer {
+                    let mut value = p * per_producer + i;
+                    while let Err(error) = queue.try_push(value) {
+                        value = error.into_inner();
+                        thread::yield_now();
+                    }
+                }
+            }));
+        }
+        for _ in 0..consumers {
+            let queue = queue.clone();
+            let popped = popped.clone();
+            let received = received.clone();
+            handles.push(thread::spawn(move || {
+                let mut local = Vec::new();
+                while popped.load(Ordering::Relaxed) < total {
+                    match queue.try_pop() {
+                        Some(value) => {
+                            local.push(value);
+                            popped.fetch_add(1, Ordering::Relaxed);
+                        }
+                        None => thread::yield_now(),
+                    }
+                }
+                received.lock().unwrap().extend(local);
+            }));
+        }
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        let mut received = received.lock().unwrap();
+        received.sort_unstable();
+        assert_eq!(*received, (0..total).collect::<Vec<_>>());
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn test_stress_mpsc() {
+        stress(4, 1, 20_000, 64);
+    }
+
+    #[test]
+    fn test_stress_spmc() {
+        stress(1, 4, 80_000, 64);
+    }
+
+    #[test]
+    fn test_stress_mpmc() {
+        stress(4, 4, 20_000, 64);
+    }
+
+    #[test]
+    fn test_stress_mpmc_small() {
+        stress(4, 4, 20_000, 2);
+    }
+}
@@ -0,0 +1,214 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+//
+
+//! Policies for growing and shrinking the buffer of a `RingBuf`.
+
+use core::cmp;
+use core::num::NonZeroUsize;
+
+use super::{Allocator, RingBuf};
+
+/// How a `RingBuf` chooses its new capacity when a push or `reserve` needs
+/// more room.
+///
+/// The capacity always grows to at least the number of elements required;
+/// the policy decides how much extra room to leave for later pushes.
+/// `reserve_exact` ignores the policy.
+///
+/// # Example
+///
+/// ```rust
+/// # use ringbuf::{GrowthPolicy, RingBuf};
+/// let mut ringbuf = RingBuf::with_capacity(4);
+/// ringbuf.set_growth_policy(GrowthPolicy::Increment(10));
+/// ringbuf.extend(0..5);
+/// assert_eq!(ringbuf.capacity(), 14);
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum GrowthPolicy {
+
+    /// Double the capacity. Pushes take amortized constant time.
+    #[default]
+    Double,
+
+    /// Grow the capacity by half. Pushes take amortized constant time, and
+    /// less memory is left unused than with `Double`, at the cost of more
+    /// frequent copies.
+    OneAndHalf,
+
+    /// Grow the capacity by a fixed number of elements. Pushes take
+    /// amortized linear time.
+    Increment(usize),
+
+    /// Grow the capacity to exactly the number of elements required.
+    Exact,
+}
+
+impl GrowthPolicy {
+
+    /// Returns the capacity to grow a buffer of capacity `cap` to, so that it
+    /// can hold at least `required` elements.
+    pub(crate) fn grow(self, cap: usize, required: usize) -> usize {
+        let capacity = match self {
+            GrowthPolicy::Double => cmp::max(cap, 1).saturating_mul(2),
+            GrowthPolicy::OneAndHalf => cap.saturating_add(cap / 2),
+            GrowthPolicy::Increment(increment) => cap.saturating_add(increment),
+            GrowthPolicy::Exact => required,
+        };
+        cmp::max(capacity, required)
+    }
+}
+
+impl<T, A: Allocator> RingBuf<T, A> {
+
+    /// Returns the policy used to grow the ring buffer.
+    #[inline]
+    pub fn growth_policy(&self) -> GrowthPolicy {
+        self.growth
+    }
+
+    /// Sets the policy used to grow the ring buffer. The current capacity is
+    /// not changed.
+    #[inline]
+    pub fn set_growth_policy(&mut self, policy: GrowthPolicy) {
+        self.growth = policy;
+    }
+
+    /// Returns the divisor set by `set_auto_shrink`, or `None` if the ring
+    /// buffer does not shrink automatically.
+    #[inline]
+    pub fn auto_shrink(&self) -> Option<usize> {
+        self.shrink_divisor.map(NonZeroUsize::get)
+    }
+
+    /// Makes the ring buffer shrink automatically when a pop leaves it at
+    /// most `1 / divisor` full, or stops it from shrinking with `None`.
+    ///
+    /// The capacity shrinks to twice the length, so that the ring buffer has
+    /// room to grow again before it has to reallocate, and an empty ring
+    /// buffer frees its buffer. Only `pop_front` and `pop_back` shrink the
+    /// ring buffer.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `divisor` is less than 3, which would let a ring buffer
+    /// alternate between shrinking and growing.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use ringbuf::RingBuf;
+    /// let mut ringbuf: RingBuf<i32> = (0..100).collect();
+    /// ringbuf.set_auto_shrink(Some(4));
+    /// while ringbuf.len() > 25 {
+    ///     ringbuf.pop_front();
+    /// }
+    /// assert_eq!(ringbuf.capacity(), 50);
+    /// ```
+    pub fn set_auto_shrink(&mut self, divisor: Option<usize>) {
+        if let Some(divisor) = divisor {
+            assert!(divisor >= 3, "auto shrink divisor must be at least 3");
+        }
+        self.shrink_divisor = divisor.and_then(NonZeroUsize::new);
+    }
+
+    /// Shrinks the buffer if it is sparse enough for the auto shrink policy.
+    #[inline]
+    pub(crate) fn shrink_if_sparse(&mut self) {
+        if let Some(divisor) = self.shrink_divisor {
+            if self.len <= self.cap / divisor.get() {
+                self.shrink_to(self.len * 2);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod checks {
+    use std::collections::VecDeque;
+
+    use quickcheck::quickcheck;
+
+    use super::GrowthPolicy;
+    use crate::RingBuf;
+
+    #[test]
+    fn test_grow() {
+        assert_eq!(GrowthPolicy::Double.grow(0, 1), 2);
+        assert_eq!(GrowthPolicy::Double.grow(8, 9), 16);
+        assert_eq!(GrowthPolicy::Double.grow(8, 100), 100);
+        assert_eq!(GrowthPolicy::OneAndHalf.grow(0, 1), 1);
+        assert_eq!(GrowthPolicy::OneAndHalf.grow(1, 2), 2);
+        assert_eq!(GrowthPolicy::OneAndHalf.grow(8, 9), 12);
+        assert_eq!(GrowthPolicy::Increment(4).grow(8, 9), 12);
+        assert_eq!(GrowthPolicy::Increment(0).grow(8, 9), 9);
+        assert_eq!(GrowthPolicy::Exact.grow(8, 9), 9);
+        assert_eq!(GrowthPolicy::Double.grow(usize::MAX - 1, usize::MAX), usize::MAX);
+    }
+
+    #[test]
+    fn check_policies() {
+        // Each op pushes or pops a run of values, and the ring must match a
+        // `VecDeque` model under every policy.
+        fn prop(ops: Vec<(bool, u8)>, policy: u8, shrink: Option<u8>) -> bool {
+            let policy = match policy % 4 {
+                0 => GrowthPolicy::Double,
+                1 => GrowthPolicy::OneAndHalf,
+                2 => GrowthPolicy::Increment(policy as usize % 7),
+                _ => GrowthPolicy::Exact,
+            };
+            let shrink = shrink.map(|divisor| divisor as usize % 4 + 3);
+            let mut ringbuf = RingBuf::new();
+            ringbuf.set_growth_policy(policy);
+            ringbuf.set_auto_shrink(shrink);
+            let mut model = VecDeque::new();
+
+            for (push, count) in ops {
+                for i in 0..count {
+                    if push {
+                        ringbuf.push_back(i);
+                        model.push_back(i);
+                    } else if ringbuf.pop_front() != model.pop_front() {
+                        return false;
+                    }
+                }
+                if !ringbuf.iter().eq(model.iter()) { return false }
+                if let Some(divisor) = shrink {
+                    if !push && ringbuf.capacity() > 0 && ringbuf.len() <= ringbuf.capacity() / divisor {
+                        return false;
+                    }
+                }
+            }
+            true
+        }
+
+        quickcheck(prop as fn(Vec<(bool, u8)>, u8, Option<u8>) -> bool);
+    }
+
+    #[test]
+    fn test_auto_shrink() {
+        let mut ringbuf: RingBuf<i32> = (0..64).collect();
+        ringbuf.set_auto_shrink(Some(4));
+        for _ in 0..48 {
+            ringbuf.pop_back();
+        }
+        assert_eq!(ringbuf.capacity(), 32);
+        while ringbuf.pop_front().is_some() {}
+        assert_eq!(ringbuf.capacity(), 0);
+
+        // Without auto shrink, the capacity is kept.
+        let mut ringbuf: RingBuf<i32> = (0..64).collect();
+        ringbuf.clear();
+        assert_eq!(ringbuf.capacity(), 64);
+    }
+
+    #[test]
+    #[should_panic(expected = "auto shrink divisor must be at least 3")]
+    fn test_auto_shrink_divisor() {
+        RingBuf::<i32>::new().set_auto_shrink(Some(2));
+    }
+}
@@ -0,0 +1,549 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+//
+
+//! A growable ring buffer whose capacity is always a power of two.
+
+use core::cmp;
+use core::fmt;
+use core::iter::FusedIterator;
+use core::marker::PhantomData;
+use core::mem;
+use core::ptr;
+use core::slice;
+
+use super::{alloc_buffer, dealloc_buffer, Global, Iter, IterMut};
+
+/// A double-ended queue whose capacity is always a power of two, so that
+/// the position of an element in the buffer is found with a mask instead of
+/// the compare-and-subtract wraparound used by `RingBuf`.
+///
+/// The front and back of the queue are kept as counters which wrap around
+/// `usize` rather than the capacity, and the length is their difference, so
+/// every slot of the buffer can hold an element. The cost is memory: growing
+/// always doubles the capacity, and `with_capacity` and `reserve` round up
+/// to the next power of two.
+///
+/// # Example
+///
+/// ```rust
+/// # use ringbuf::Pow2RingBuf;
+/// let mut ringbuf = Pow2RingBuf::with_capacity(5);
+/// assert_eq!(ringbuf.capacity(), 8);
+///
+/// ringbuf.extend(0..8);
+/// assert_eq!(ringbuf.capacity(), 8);
+/// assert_eq!(ringbuf.pop_front(), Some(0));
+/// ringbuf.push_back(8);
+/// assert_eq!(ringbuf[7], 8);
+/// ```
+pub struct Pow2RingBuf<T> {
+
+    /// The position of the front element, before masking.
+    head: usize,
+
+    /// The position one past the back element, before masking.
+    /// invariant: `tail.wrapping_sub(head) <= cap`
+    tail: usize,
+
+    /// Capacity of the buffer; zero or a power of two.
+    cap: usize,
+
+    /// Pointer to the start of the buffer.
+    ptr: *mut T,
+
+    /// The ring buffer owns its elements.
+    marker: PhantomData<T>,
+}
+
+unsafe impl<T: Send> Send for Pow2RingBuf<T> {}
+unsafe impl<T: Sync> Sync for Pow2RingBuf<T> {}
+
+impl<T> Pow2RingBuf<T> {
+
+    /// Creates an empty `Pow2RingBuf`.
+    ///
+    /// The ring buffer will not allocate until elements are pushed onto it.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use ringbuf::Pow2RingBuf;
+    /// let ringbuf: Pow2RingBuf<i32> = Pow2RingBuf::new();
+    /// assert_eq!(ringbuf.capacity(), 0);
+    /// ```
+    pub const fn new() -> Pow2RingBuf<T> {
+        // Zero-sized types never need to allocate, so they get the largest
+        // power of two capacity up front.
+        let cap = if mem::size_of::<T>() == 0 { 1 << (usize::BITS - 1) } else { 0 };
+        Pow2RingBuf {
+            head: 0,
+            tail: 0,
+            cap,
+            ptr: ptr::NonNull::dangling().as_ptr(),
+            marker: PhantomData,
+        }
+    }
+
+    /// Creates an empty `Pow2RingBuf` which can hold at least `capacity`
+    /// elements without reallocating. The capacity is rounded up to the next
+    /// power of two.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the rounded capacity overflows `usize`.
+    pub fn with_capacity(capacity: usize) -> Pow2RingBuf<T> {
+        let mut ringbuf = Pow2RingBuf::new();
+        ringbuf.reserve(capacity);
+        ringbuf
+    }
+
+    /// Retrieves an element in the `Pow2RingBuf` by index.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use ringbuf::Pow2RingBuf;
+    /// let ringbuf: Pow2RingBuf<i32> = (3..6).collect();
+    /// assert_eq!(ringbuf.get(1), Some(&4));
+    /// assert_eq!(ringbuf.get(3), None);
+    /// ```
+    #[inline]
+    pub fn get(&self, index: usize) -> Option<&T> {
+        if index < self.len() {
+            unsafe { Some(&*self.ptr.add(self.get_offset(index))) }
+        } else {
+            None
+        }
+    }
+
+    /// Retrieves an element in the `Pow2RingBuf` mutably by index.
+    #[inline]
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        if index < self.len() {
+            unsafe { Some(&mut *self.ptr.add(self.get_offset(index))) }
+        } else {
+            None
+        }
+    }
+
+    /// Swaps elements at indices `i` and `j`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if either index is out of bounds.
+    pub fn swap(&mut self, i: usize, j: usize) {
+        assert!(i < self.len() && j < self.len(), "index out of bounds");
+        unsafe {
+            ptr::swap(self.ptr.add(self.get_offset(i)), self.ptr.add(self.get_offset(j)));
+        }
+    }
+
+    /// Returns the number of elements the `Pow2RingBuf` can hold without
+    /// reallocating. This is always zero or a power of two.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.cap
+    }
+
+    /// Reserves capacity for at least `additional` more elements, rounding
+    /// the new capacity up to the next power of two.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the new capacity overflows `usize`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use ringbuf::Pow2RingBuf;
+    /// let mut ringbuf: Pow2RingBuf<i32> = (0..3).collect();
+    /// ringbuf.reserve(10);
+    /// assert_eq!(ringbuf.capacity(), 16);
+    /// ```
+    pub fn reserve(&mut self, additional: usize) {
+        if self.cap - self.len() < additional {
+            let capacity = self.len().checked_add(additional)
+                                     .and_then(usize::checked_next_power_of_two)
+                                     .expect("capacity overflow");
+            self.resize_buffer(capacity);
+        }
+    }
+
+    /// Shrinks the capacity to the smallest power of two which holds the
+    /// elements, freeing the buffer if the `Pow2RingBuf` is empty.
+    pub fn shrink_to_fit(&mut self) {
+        let len = self.len();
+        self.resize_buffer(if len == 0 { 0 } else { len.next_power_of_two() });
+    }
+
+    /// Returns the number of elements in the `Pow2RingBuf`.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.tail.wrapping_sub(self.head)
+    }
+
+    /// Returns `true` if the `Pow2RingBuf` contains no elements.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.head == self.tail
+    }
+
+    /// Returns a front-to-back iterator.
+    pub fn iter(&self) -> Iter<'_, T> {
+        let (slice1, slice2) = self.as_slices();
+        Iter { iter1: slice1.iter(), iter2: slice2.iter() }
+    }
+
+    /// Returns a front-to-back iterator which returns mutable references.
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        let (slice1, slice2) = self.as_mut_slices();
+        IterMut { iter1: slice1.iter_mut(), iter2: slice2.iter_mut() }
+    }
+
+    /// Returns a pair of slices which contain the contents of the
+    /// `Pow2RingBuf`, in order.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use ringbuf::Pow2RingBuf;
+    /// let mut ringbuf = Pow2RingBuf::new();
+    /// ringbuf.push_back(2);
+    /// ringbuf.push_front(1);
+    /// assert_eq!(ringbuf.as_slices(), (&[1][..], &[2][..]));
+    /// ```
+    #[inline]
+    pub fn as_slices(&self) -> (&[T], &[T]) {
+        let (offset, len1, len2) = self.slice_ranges();
+        unsafe {
+            (slice::from_raw_parts(self.ptr.add(offset), len1),
+             slice::from_raw_parts(self.ptr, len2))
+        }
+    }
+
+    /// Returns a pair of mutable slices which contain the contents of the
+    /// `Pow2RingBuf`, in order.
+    #[inline]
+    pub fn as_mut_slices(&mut self) -> (&mut [T], &mut [T]) {
+        let (offset, len1, len2) = self.slice_ranges();
+        unsafe {
+            (slice::from_raw_parts_mut(self.ptr.add(offset), len1),
+             slice::from_raw_parts_mut(self.ptr, len2))
+        }
+    }
+
+    /// Returns `true` if the `Pow2RingBuf` contains an element equal to the
+    /// given value.
+    pub fn contains(&self, x: &T) -> bool where T: PartialEq {
+        let (slice1, slice2) = self.as_slices();
+        slice1.contains(x) || slice2.contains(x)
+    }
+
+    /// Provides a reference to the front element, or `None` if the
+    /// `Pow2RingBuf` is empty.
+    #[inline]
+    pub fn front(&self) -> Option<&T> {
+        self.get(0)
+    }
+
+    /// Provides a mutable reference to the front element, or `None` if the
+    /// `Pow2RingBuf` is empty.
+    #[inline]
+    pub fn front_mut(&mut self) -> Option<&mut T> {
+        self.get_mut(0)
+    }
+
+    /// Provides a reference to the back element, or `None` if the
+    /// `Pow2RingBuf` is empty.
+    #[inline]
+    pub fn back(&self) -> Option<&T> {
+        self.len().checked_sub(1).and_then(|index| self.get(index))
+    }
+
+    /// Provides a mutable reference to the back element, or `None` if the
+    /// `Pow2RingBuf` is empty.
+    #[inline]
+    pub fn back_mut(&mut self) -> Option<&mut T> {
+        self.len().checked_sub(1).and_then(move |index| self.get_mut(index))
+    }
+
+    /// Prepends an element to the `Pow2RingBuf`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the new capacity overflows `usize`.
+    #[inline]
+    pub fn push_front(&mut self, value: T) {
+        self.grow_if_full();
+        self.head = self.head.wrapping_sub(1);
+        unsafe { ptr::write(self.ptr.add(self.get_offset(0)), value) }
+    }
+
+    /// Removes the first element and returns it, or `None` if the
+    /// `Pow2RingBuf` is empty.
+    #[inline]
+    pub fn pop_front(&mut self) -> Option<T> {
+        if self.is_empty() { return None }
+        let offset = self.get_offset(0);
+        self.head = self.head.wrapping_add(1);
+        unsafe { Some(ptr::read(self.ptr.add(offset))) }
+    }
+
+    /// Appends an element to the back of the `Pow2RingBuf`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the new capacity overflows `usize`.
+    #[inline]
+    pub fn push_back(&mut self, value: T) {
+        self.grow_if_full();
+        unsafe { ptr::write(self.ptr.add(self.tail & self.mask()), value) }
+        self.tail = self.tail.wrapping_add(1);
+    }
+
+    /// Removes the last element and returns it, or `None` if the
+    /// `Pow2RingBuf` is empty.
+    #[inline]
+    pub fn pop_back(&mut self) -> Option<T> {
+        if self.is_empty() { return None }
+        self.tail = self.tail.wrapping_sub(1);
+        unsafe { Some(ptr::read(self.ptr.add(self.tail & self.mask()))) }
+    }
+
+    /// Shortens the `Pow2RingBuf`, dropping excess elements from the back.
+    pub fn truncate(&mut self, len: usize) {
+        while self.len() > len {
+            self.pop_back();
+        }
+    }
+
+    /// Clears the `Pow2RingBuf`, removing all values.
+    pub fn clear(&mut self) {
+        self.truncate(0);
+    }
+
+    /// Retains only the elements specified by the predicate.
+    pub fn retain<F>(&mut self, mut f: F) where F: FnMut(&T) -> bool {
+        let len = self.len();
+        let mut deleted = 0;
+        for i in 0..len {
+            if !f(&self[i]) {
+                deleted += 1;
+            } else if deleted > 0 {
+                self.swap(i - deleted, i);
+            }
+        }
+        if deleted > 0 {
+            self.truncate(len - deleted);
+        }
+    }
+}
+
+impl<T> Pow2RingBuf<T> {
+
+    /// Returns the mask which maps a position to an offset in the buffer.
+    #[inline]
+    fn mask(&self) -> usize {
+        self.cap.wrapping_sub(1)
+    }
+
+    /// Return the offset of the given index in the buffer.
+    #[inline]
+    fn get_offset(&self, index: usize) -> usize {
+        self.head.wrapping_add(index) & self.mask()
+    }
+
+    /// Returns the offset and length of the first slice, and the length of
+    /// the second slice, which starts at offset 0.
+    #[inline]
+    fn slice_ranges(&self) -> (usize, usize, usize) {
+        let len = self.len();
+        if len == 0 { return (0, 0, 0) }
+        let offset = self.get_offset(0);
+        if offset > self.cap - len {
+            (offset, self.cap - offset, len - (self.cap - offset))
+        } else {
+            (offset, len, 0)
+        }
+    }
+
+    /// Double the capacity of the buffer if it is full.
+    #[inline]
+    fn grow_if_full(&mut self) {
+        if self.len() == self.cap {
+            let capacity = cmp::max(self.cap, 1).checked_mul(2).expect("capacity overflow");
+            self.resize_buffer(capacity);
+        }
+    }
+
+    /// Moves the elements to a new buffer of the given capacity, which must
+    /// be zero or a power of two, and hold all of the elements.
+    fn resize_buffer(&mut self, capacity: usize) {
+        debug_assert!(capacity == 0 || capacity.is_power_of_two());
+        assert!(capacity >= self.len(), "capacity underflow");
+
+        if capacity == self.cap || mem::size_of::<T>() == 0 { return }
+
+        let len = self.len();
+        unsafe {
+            let ptr: *mut T = alloc_buffer(&Global, capacity);
+            let (slice1, slice2) = self.as_slices();
+            ptr::copy_nonoverlapping(slice1.as_ptr(), ptr, slice1.len());
+            ptr::copy_nonoverlapping(slice2.as_ptr(), ptr.add(slice1.len()), slice2.len());
+            dealloc_buffer(&Global, self.ptr, self.cap);
+            self.ptr = ptr;
+        }
+        self.cap = capacity;
+        self.head = 0;
+        self.tail = len;
+    }
+}
+
+deque_impls!(Pow2RingBuf<T>);
+
+impl<T: Clone> Clone for Pow2RingBuf<T> {
+    fn clone(&self) -> Pow2RingBuf<T> {
+        let mut ringbuf: Pow2RingBuf<T> = Pow2RingBuf::with_capacity(self.len());
+        // Write the clones straight into the buffer one slice at a time, so
+        // that this can be optimised to a memcpy when T is Copy.
+        let (slice1, slice2) = self.as_slices();
+        for slice in [slice1, slice2] {
+            for element in slice {
+                unsafe { ptr::write(ringbuf.ptr.add(ringbuf.tail), element.clone()) }
+                ringbuf.tail += 1;
+            }
+        }
+        ringbuf
+    }
+}
+
+impl<T> Drop for Pow2RingBuf<T> {
+    fn drop(&mut self) {
+        unsafe {
+            let (slice1, slice2) = self.as_mut_slices();
+            let (slice1, slice2) = (slice1 as *mut [T], slice2 as *mut [T]);
+            ptr::drop_in_place(slice1);
+            ptr::drop_in_place(slice2);
+            dealloc_buffer(&Global, self.ptr, self.cap);
+        }
+    }
+}
+
+impl<T> Extend<T> for Pow2RingBuf<T> {
+    fn extend<I: IntoIterator<Item=T>>(&mut self, iterable: I) {
+        let iter = iterable.into_iter();
+        self.reserve(iter.size_hint().0);
+        for value in iter {
+            self.push_back(value);
+        }
+    }
+}
+
+impl<T> IntoIterator for Pow2RingBuf<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> IntoIter<T> {
+        IntoIter { ringbuf: self }
+    }
+}
+
+/// An iterator that moves out of a `Pow2RingBuf`.
+#[derive(Clone)]
+pub struct IntoIter<T> {
+    ringbuf: Pow2RingBuf<T>,
+}
+
+impl<T> Iterator for IntoIter<T> {
+    type Item = T;
+
+    #[inline]
+    fn next(&mut self) -> Option<T> {
+        self.ringbuf.pop_front()
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.ringbuf.len(), Some(self.ringbuf.len()))
+    }
+}
+
+impl<T> DoubleEndedIterator for IntoIter<T> {
+    #[inline]
+    fn next_back(&mut self) -> Option<T> {
+        self.ringbuf.pop_back()
+    }
+}
+
+impl<T> ExactSizeIterator for IntoIter<T> {}
+
+impl<T> FusedIterator for IntoIter<T> {}
+
+impl<T: fmt::Debug> fmt::Debug for IntoIter<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("IntoIter").field(&self.ringbuf).finish()
+    }
+}
+
+#[cfg(test)]
+mod checks {
+    use std::collections::VecDeque;
+    use std::rc::Rc;
+
+    use super::Pow2RingBuf;
+    use crate::model::{self, Model};
+
+    impl Model for Pow2RingBuf<i32> {
+        fn apply(&mut self, model: &mut VecDeque<i32>, op: u8, _: i32, _: usize) -> bool {
+            match op % 2 {
+                0 => self.shrink_to_fit(),
+                _ => {
+                    self.retain(|&x| x % 3 != 0);
+                    model.retain(|&x| x % 3 != 0);
+                }
+            }
+            true
+        }
+
+        fn check(&self, model: &VecDeque<i32>) -> bool {
+            let (slice1, slice2) = self.as_slices();
+            slice1.iter().chain(slice2).eq(model.iter())
+                && (self.capacity() == 0 || self.capacity().is_power_of_two())
+        }
+    }
+
+    #[test]
+    fn check_model() {
+        model::check_model::<Pow2RingBuf<i32>>();
+    }
+
+    #[test]
+    fn test_full_capacity() {
+        // Every slot is used, and the counters may wrap around `usize`.
+        let mut ringbuf = Pow2RingBuf::with_capacity(4);
+        ringbuf.head = usize::MAX - 1;
+        ringbuf.tail = usize::MAX - 1;
+        ringbuf.extend(0..4);
+        assert_eq!(ringbuf.capacity(), 4);
+        assert_eq!(ringbuf.len(), 4);
+        assert_eq!(ringbuf.as_slices(), (&[0, 1][..], &[2, 3][..]));
+
+        ringbuf.push_back(4);
+        assert_eq!(ringbuf.capacity(), 8);
+        assert!(ringbuf.iter().eq(&[0, 1, 2, 3, 4]));
+    }
+
+    #[test]
+    fn test_drop_items() {
+        model::check_drop_items::<Pow2RingBuf<Rc<()>>>(5);
+    }
+
+    #[test]
+    fn test_zero_sized() {
+        model::check_zero_sized::<Pow2RingBuf<()>>(5);
+    }
+}
@@ -4,70 +4,177 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 //
-#![feature(unsafe_destructor)]
-#![feature(default_type_params)]
-
-extern crate quickcheck;
-
-///! A double-ended queue implemented as a circular buffer
-///!
-///! RingBuf implements the trait Deque. It should be imported with
-///! `use collections::Deque`.
-
-use std::cmp;
-use std::collections::Deque;
-use std::default::Default;
-use std::fmt;
-use std::hash::{Writer, Hash};
-use std::iter::Chain;
-use std::iter::FromIterator;
-use std::mem;
-use std::num;
-use std::ptr;
-use std::raw::Slice;
-use std::slice;
-
-/// RingBuf is a circular buffer that implements Deque.
+
+//! A double-ended queue implemented as a circular buffer
+//!
+//! `RingBuf` implements the inherent API of `std::collections::VecDeque`, so
+//! it can be used as a drop-in replacement. In addition it provides
+//! `from_vec` and `into_vec` conversions which do not copy the elements, and
+//! `RingBuf<u8>` implements `Read`, `BufRead` and `Write`. On Linux,
+//! `MirroredRingBuf` is a byte ring buffer whose contents are always one
+//! contiguous slice.
+//!
+//! `BoundedRingBuf` is a fixed-capacity variant which overwrites its oldest
+//! elements instead of growing, and `ArrayRingBuf` stores a fixed number of
+//! elements inline, without allocating. `SmallRingBuf` stores a few elements
+//! inline and moves them to the heap when it outgrows them. `ChunkedDeque`
+//! stores its elements in fixed-size blocks, so that growing a very large
+//! queue never moves them. `IncrementalRingBuf` grows without copying all of
+//! its elements at once, by moving a few of them on each later operation.
+//! `Pow2RingBuf` keeps its capacity at a power of two, so that indexing is a
+//! single mask.
+//! `SeqRingBuf` numbers its elements with sequence numbers which stay valid
+//! as elements are popped from the front, for logs read by position.
+//!
+//! A `BoundedRingBuf` can be `split` into a `Producer` and a `Consumer` to
+//! share it between two threads as a wait-free queue. `MpmcQueue` is a
+//! lock-free bounded queue for any number of producer and consumer threads.
+//! Both need atomic compare-and-swap, so they are left out on targets without
+//! it, such as `thumbv6m-none-eabi`.
+//! `channel` creates a blocking bounded channel backed by a `RingBuf`, whose
+//! capacity can be changed while it is in use, and with the `async` feature
+//! `async_channel` creates its `Stream` and `Sink` counterpart.
+//!
+//! How a `RingBuf` grows is set with a `GrowthPolicy`, and it can be made to
+//! shrink automatically with `set_auto_shrink` as elements are popped.
+//!
+//! `RingBuf` can allocate its buffer from any `Allocator`, with `new_in` and
+//! `with_capacity_in`. The trait is re-exported from the `allocator-api2`
+//! crate, since the standard library's is not yet stable.
+//!
+//! The crate only needs an allocator for its core types. Without the default
+//! `std` feature it is `no_std`, and the pieces which need the standard
+//! library (the `std::io` implementations, `MirroredRingBuf` and the channels)
+//! are left out.
+
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
+
+use alloc::alloc::Layout;
+use core::cmp;
+use core::cmp::Ordering;
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::iter::FromIterator;
+use core::iter::FusedIterator;
+use core::marker::PhantomData;
+use core::mem;
+use core::mem::MaybeUninit;
+use core::num::NonZeroUsize;
+use core::ops::{Bound, Index, IndexMut, Range, RangeBounds};
+use core::ptr;
+use core::slice;
+
+use alloc::vec::Vec;
+
+pub use allocator_api2::alloc::{AllocError, Allocator, Global};
+pub use array::{ArrayRingBuf, IntoIter as ArrayIntoIter};
+#[cfg(feature = "async")]
+pub use async_channel::{async_channel, AsyncReceiver, AsyncSender, RecvFuture, SendFuture};
+pub use bounded::BoundedRingBuf;
+pub use chunked::{ChunkedDeque, IntoIter as ChunkedIntoIter, Iter as ChunkedIter,
+                  IterMut as ChunkedIterMut};
+#[cfg(feature = "std")]
+pub use channel::{channel, Iter as ChannelIter, Receiver, Sender};
+pub use incremental::{IncrementalRingBuf, IntoIter as IncrementalIntoIter,
+                      Iter as IncrementalIter, IterMut as IncrementalIterMut};
+pub use error::{RecvError, RecvTimeoutError, SendError, SendTimeoutError, SeqError,
+                TryPushError, TryRecvError, TryReserveError, TrySendError};
+#[cfg(all(feature = "std", target_os = "linux"))]
+pub use mirrored::MirroredRingBuf;
+#[cfg(target_has_atomic = "ptr")]
+pub use mpmc::MpmcQueue;
+pub use policy::GrowthPolicy;
+pub use pow2::{IntoIter as Pow2IntoIter, Pow2RingBuf};
+pub use seq::SeqRingBuf;
+pub use small::{IntoIter as SmallIntoIter, SmallRingBuf};
+#[cfg(target_has_atomic = "ptr")]
+pub use spsc::{Consumer, Producer};
+
+#[macro_use]
+mod macros;
+
+mod array;
+#[cfg(feature = "async")]
+mod async_channel;
+mod bounded;
+#[cfg(feature = "std")]
+mod channel;
+mod chunked;
+mod error;
+mod incremental;
+#[cfg(feature = "std")]
+mod io;
+#[cfg(all(feature = "std", target_os = "linux"))]
+mod mirrored;
+#[cfg(test)]
+mod model;
+#[cfg(target_has_atomic = "ptr")]
+mod mpmc;
+mod policy;
+mod pow2;
+mod seq;
+mod small;
+#[cfg(target_has_atomic = "ptr")]
+mod spsc;
+
+/// RingBuf is a circular buffer with the same interface as `VecDeque`.
 ///
 /// # Examples
 ///
 /// ```rust
-/// # use std::collections::{RingBuf, Deque};
+/// # use ringbuf::RingBuf;
 /// let mut ringbuf = RingBuf::new();
-/// ringbuf.push_front(1i);
+/// ringbuf.push_front(1);
 /// ringbuf.push_back(2);
 ///
 /// assert_eq!(ringbuf.len(), 2);
-/// assert_eq!(ringbuf.get(0), &1);
+/// assert_eq!(ringbuf[0], 1);
 /// assert_eq!(ringbuf.front(), Some(&1));
 /// assert_eq!(ringbuf.back(), Some(&2));
 ///
 /// assert_eq!(ringbuf.pop_back(), Some(2));
 /// assert_eq!(ringbuf.len(), 1);
 /// ```
-#[unsafe_no_drop_flag]
-pub struct RingBuf<T> {
+pub struct RingBuf<T, A: Allocator = Global> {
 
     /// The index of the 0th element
     /// invariant: `0 <= lo < cap`
-    lo: uint,
+    lo: usize,
 
     /// The number of elements currently in the ring.
     /// invariant: `0 <= len <= cap`
-    len: uint,
+    len: usize,
 
     /// Capacity of the buffer.
-    cap: uint,
+    cap: usize,
 
     /// Pointer to the start of the buffer
-    ptr: *mut T
-}
+    ptr: *mut T,
 
-/// RingBuf iterator.
-pub type Items<'a, T> = Chain<slice::Items<'a, T>, slice::Items<'a, T>>;
+    /// The allocator which owns the buffer.
+    alloc: A,
 
-/// RingBuf mutable iterator.
-pub type MutItems<'a, T> = Chain<slice::MutItems<'a, T>, slice::MutItems<'a, T>>;
+    /// How the buffer grows when it is full.
+    growth: GrowthPolicy,
+
+    /// Shrink the buffer when a pop leaves at most `cap / shrink_divisor`
+    /// elements.
+    shrink_divisor: Option<NonZeroUsize>,
+
+    /// The number of slots at the start of the buffer which are known to
+    /// have been written, so that `read_from` on a byte ring only zeroes
+    /// memory which has never been initialized.
+    /// invariant: `init <= cap`
+    init: usize,
+
+    /// The ring buffer owns its elements.
+    marker: PhantomData<T>,
+}
+
+unsafe impl<T: Send, A: Allocator + Send> Send for RingBuf<T, A> {}
+unsafe impl<T: Sync, A: Allocator + Sync> Sync for RingBuf<T, A> {}
 
 impl<T> RingBuf<T> {
 
@@ -78,11 +185,11 @@ impl<T> RingBuf<T> {
     /// # Example
     ///
     /// ```rust
-    /// # use std::collections::RingBuf;
-    /// let mut ringbuf: RingBuf<int> = RingBuf::new();
+    /// # use ringbuf::RingBuf;
+    /// let mut ringbuf: RingBuf<i32> = RingBuf::new();
     /// ```
-    pub fn new() -> RingBuf<T> {
-        RingBuf::with_capacity(0)
+    pub const fn new() -> RingBuf<T> {
+        RingBuf::new_in(Global)
     }
 
     /// Constructs a new, empty `RingBuf` with the specified capacity.
@@ -93,12 +200,11 @@ impl<T> RingBuf<T> {
     /// # Example
     ///
     /// ```rust
-    /// # use std::collections::RingBuf;
-    /// let ring: RingBuf<int> = RingBuf::with_capacity(10);
+    /// # use ringbuf::RingBuf;
+    /// let ring: RingBuf<i32> = RingBuf::with_capacity(10);
     /// ```
-    pub fn with_capacity(capacity: uint) -> RingBuf<T> {
-        let ptr: *mut T = unsafe { alloc(capacity) };
-        RingBuf { lo: 0, len: 0, cap: capacity, ptr: ptr }
+    pub fn with_capacity(capacity: usize) -> RingBuf<T> {
+        RingBuf::with_capacity_in(capacity, Global)
     }
 
     /// Constructs a new `RingBuf` from the elements in a `Vec`.
@@ -109,93 +215,180 @@ impl<T> RingBuf<T> {
     /// # Example
     ///
     /// ```rust
-    /// # use std::collections::RingBuf;
-    /// let mut vec = vec![1i, 2, 3];
+    /// # use ringbuf::RingBuf;
+    /// let vec = vec![1, 2, 3];
     /// let ringbuf = RingBuf::from_vec(vec);
     /// ```
-    pub fn from_vec(mut vec: Vec<T>) -> RingBuf<T> {
+    pub fn from_vec(vec: Vec<T>) -> RingBuf<T> {
+        let mut vec = mem::ManuallyDrop::new(vec);
         let len = vec.len();
         let cap = vec.capacity();
         let ptr = vec.as_mut_ptr();
-        let ringbuf = RingBuf { lo: 0, len: len, cap: cap, ptr: ptr };
-        unsafe { mem::forget(vec); }
-        ringbuf
+        RingBuf {
+            lo: 0,
+            len,
+            cap,
+            ptr,
+            alloc: Global,
+            growth: GrowthPolicy::Double,
+            shrink_divisor: None,
+            init: len,
+            marker: PhantomData,
+        }
     }
 
     /// Constructs a new `Vec` from the elements in a `RingBuf`.
     ///
-    /// May require copying and temporary allocation.
+    /// May require moving the elements within the buffer, but never
+    /// allocates.
     ///
     /// # Example
     ///
     /// ```rust
-    /// # use std::collections::{RingBuf, Deque};
+    /// # use ringbuf::RingBuf;
     /// let mut ringbuf = RingBuf::new();
-    /// ringbuf.push_front(1i);
+    /// ringbuf.push_front(1);
     /// ringbuf.push_back(2);
     /// let vec = ringbuf.into_vec();
-    /// assert_eq!(&[1, 2], vec.as_slice());
+    /// assert_eq!(&[1, 2], &vec[..]);
     /// ```
     pub fn into_vec(mut self) -> Vec<T> {
         self.reset();
 
-        let vec;
-        unsafe {
-            vec = Vec::from_raw_parts(self.len, self.cap, self.ptr);
-            mem::forget(self);
+        let ringbuf = mem::ManuallyDrop::new(self);
+        unsafe { Vec::from_raw_parts(ringbuf.ptr, ringbuf.len, ringbuf.cap) }
+    }
+}
+
+impl<T, A: Allocator> RingBuf<T, A> {
+
+    /// Construct a new, empty `RingBuf` which allocates from `alloc`.
+    ///
+    /// The ring buffer will not allocate until elements are pushed onto it.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use ringbuf::{Global, RingBuf};
+    /// let mut ringbuf: RingBuf<i32> = RingBuf::new_in(Global);
+    /// ```
+    pub const fn new_in(alloc: A) -> RingBuf<T, A> {
+        // Zero-sized types never need to allocate, so they get the maximum
+        // capacity up front.
+        let cap = if mem::size_of::<T>() == 0 { usize::MAX } else { 0 };
+        RingBuf {
+            lo: 0,
+            len: 0,
+            cap,
+            ptr: ptr::NonNull::dangling().as_ptr(),
+            alloc,
+            growth: GrowthPolicy::Double,
+            shrink_divisor: None,
+            init: 0,
+            marker: PhantomData,
         }
-        vec
     }
 
-    /// Returns a reference to the value at index `index`.
+    /// Constructs a new, empty `RingBuf` with the specified capacity, which
+    /// allocates from `alloc`.
     ///
-    /// # Failure
+    /// The ring will be able to hold exactly `capacity` elements without
+    /// reallocating. If `capacity` is 0, the ringbuf will not allocate.
     ///
-    /// Fails if `index` is out of bounds.
+    /// # Example
     ///
     /// ```rust
-    /// # use std::collections::RingBuf;
-    /// let ringbuf = RingBuf::from_vec(vec![1i, 2, 3]);
-    /// assert!(ringbuf.get(1) == &2);
+    /// # use ringbuf::{Global, RingBuf};
+    /// let ring: RingBuf<i32> = RingBuf::with_capacity_in(10, Global);
+    /// assert_eq!(ring.capacity(), 10);
     /// ```
-    pub fn get<'a>(&'a self, index: uint) -> &'a T {
-        assert!(index < self.len);
-        let offset = self.get_offset(index) as int;
-        unsafe { &*self.ptr.offset(offset) }
+    pub fn with_capacity_in(capacity: usize, alloc: A) -> RingBuf<T, A> {
+        if mem::size_of::<T>() == 0 { return RingBuf::new_in(alloc) }
+        let ptr: *mut T = unsafe { alloc_buffer(&alloc, capacity) };
+        RingBuf {
+            lo: 0,
+            len: 0,
+            cap: capacity,
+            ptr,
+            alloc,
+            growth: GrowthPolicy::Double,
+            shrink_divisor: None,
+            init: 0,
+            marker: PhantomData,
+        }
     }
 
-    /// Returns a mutable reference to the value at index `index`.
+    /// Returns a reference to the allocator of the `RingBuf`.
+    #[inline]
+    pub fn allocator(&self) -> &A {
+        &self.alloc
+    }
+
+    /// Returns a reference to the value at index `index`, or `None` if the
+    /// index is out of bounds.
+    ///
+    /// # Example
     ///
-    /// # Failure
+    /// ```rust
+    /// # use ringbuf::RingBuf;
+    /// let ringbuf = RingBuf::from_vec(vec![1, 2, 3]);
+    /// assert_eq!(ringbuf.get(1), Some(&2));
+    /// assert_eq!(ringbuf.get(3), None);
+    /// ```
+    pub fn get(&self, index: usize) -> Option<&T> {
+        if index < self.len {
+            let offset = self.get_offset(index);
+            unsafe { Some(&*self.ptr.add(offset)) }
+        } else {
+            None
+        }
+    }
+
+    /// Returns a mutable reference to the value at index `index`, or `None`
+    /// if the index is out of bounds.
     ///
-    /// Fails if `index` is out of bounds
+    /// # Example
     ///
     /// ```rust
-    /// # use std::collections::RingBuf;
-    /// let mut ringbuf = RingBuf::from_vec(vec![1i, 2, 3]);
-    /// *ringbuf.get_mut(1) = 4;
-    /// assert_eq!(ringbuf.get(1), &4);
+    /// # use ringbuf::RingBuf;
+    /// let mut ringbuf = RingBuf::from_vec(vec![1, 2, 3]);
+    /// if let Some(elem) = ringbuf.get_mut(1) {
+    ///     *elem = 4;
+    /// }
+    /// assert_eq!(ringbuf.get(1), Some(&4));
     /// ```
-    pub fn get_mut<'a>(&'a mut self, index: uint) -> &'a mut T {
-        assert!(index < self.len);
-        let offset = self.get_offset(index) as int;
-        unsafe { &mut *self.ptr.offset(offset) }
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        if index < self.len {
+            let offset = self.get_offset(index);
+            unsafe { Some(&mut *self.ptr.add(offset)) }
+        } else {
+            None
+        }
     }
 
     /// Swap elements at indices `i` and `j`
     ///
     /// `i` and `j` may be equal.
     ///
-    /// # Failure
+    /// # Panics
+    ///
+    /// Panics if there is no element with the given index
+    ///
+    /// # Example
     ///
-    /// Fails if there is no element with the given index
-    pub fn swap(&mut self, i: uint, j: uint) {
+    /// ```rust
+    /// # use ringbuf::RingBuf;
+    /// let mut ringbuf = RingBuf::from_vec(vec![1, 2, 3]);
+    /// ringbuf.swap(0, 2);
+    /// assert_eq!(ringbuf.into_vec(), vec![3, 2, 1]);
+    /// ```
+    pub fn swap(&mut self, i: usize, j: usize) {
         assert!(i < self.len());
         assert!(j < self.len());
-        let i_offset = self.get_offset(i) as int;
-        let j_offset = self.get_offset(j) as int;
+        let i_offset = self.get_offset(i);
+        let j_offset = self.get_offset(j);
         unsafe {
-            ptr::swap(self.ptr.offset(i_offset), self.ptr.offset(j_offset));
+            ptr::swap(self.ptr.add(i_offset), self.ptr.add(j_offset));
         }
     }
 
@@ -207,13 +400,13 @@ impl<T> RingBuf<T> {
     /// # Example
     ///
     /// ```rust
-    /// # use std::collections::RingBuf;
-    /// let mut ringbuf = RingBuf::from_vec(vec![1i, 2, 3, 4]);
+    /// # use ringbuf::RingBuf;
+    /// let mut ringbuf = RingBuf::from_vec(vec![1, 2, 3, 4]);
     /// ringbuf.truncate(2);
-    /// assert_eq!(ringbuf.into_vec(), vec![1i, 2]);
+    /// assert_eq!(ringbuf.into_vec(), vec![1, 2]);
     /// ```
-    pub fn truncate(&mut self, len: uint) {
-        for _ in range(len, self.len) { self.pop_back(); }
+    pub fn truncate(&mut self, len: usize) {
+        for _ in len..self.len { self.pop_back(); }
     }
 
     /// Work with `self` as a pair of slices.
@@ -223,20 +416,20 @@ impl<T> RingBuf<T> {
     /// # Example
     ///
     /// ```rust
-    /// # use std::collections::{RingBuf, Deque};
+    /// # use ringbuf::RingBuf;
     /// let mut rb = RingBuf::new();
-    /// rb.push_back(1i);
+    /// rb.push_back(1);
     /// rb.push_front(0);
     /// let (slice1, slice2) = rb.as_slices();
     /// assert_eq!(slice1, &[0]);
     /// assert_eq!(slice2, &[1]);
     /// ```
     #[inline]
-    pub fn as_slices<'a>(&'a self) -> (&'a [T], &'a [T]) {
+    pub fn as_slices(&self) -> (&[T], &[T]) {
         let (ptr1, len1, ptr2, len2) = self.get_slice_ptrs();
         unsafe {
-            (mem::transmute(Slice { data: ptr1, len: len1 }),
-             mem::transmute(Slice { data: ptr2, len: len2 }))
+            (slice::from_raw_parts(ptr1, len1),
+             slice::from_raw_parts(ptr2, len2))
         }
     }
 
@@ -247,20 +440,20 @@ impl<T> RingBuf<T> {
     /// # Example
     ///
     /// ```rust
-    /// # use std::collections::{RingBuf, Deque};
+    /// # use ringbuf::RingBuf;
     /// let mut rb = RingBuf::new();
-    /// rb.push_front(1i);
+    /// rb.push_front(1);
     /// rb.push_back(2);
     /// let (slice1, slice2) = rb.as_mut_slices();
     /// assert_eq!(slice1, &[1]);
     /// assert_eq!(slice2, &[2]);
     /// ```
     #[inline]
-    pub fn as_mut_slices<'a>(&'a mut self) -> (&'a mut [T], &'a mut [T]) {
+    pub fn as_mut_slices(&mut self) -> (&mut [T], &mut [T]) {
         let (ptr1, len1, ptr2, len2) = self.get_slice_ptrs();
         unsafe {
-            (mem::transmute(Slice { data: ptr1, len: len1 }),
-             mem::transmute(Slice { data: ptr2, len: len2 }))
+            (slice::from_raw_parts_mut(ptr1, len1),
+             slice::from_raw_parts_mut(ptr2, len2))
         }
     }
 
@@ -270,16 +463,16 @@ impl<T> RingBuf<T> {
     /// # Example
     ///
     /// ```rust
-    /// # use std::collections::RingBuf;
-    /// let ringbuf = RingBuf::from_vec(vec![1i, 2, 3]);
+    /// # use ringbuf::RingBuf;
+    /// let ringbuf = RingBuf::from_vec(vec![1, 2, 3]);
     /// for &num in ringbuf.iter() {
     ///     println!("{}", num);
     /// }
     /// ```
     #[inline]
-    pub fn iter<'a>(&'a self) -> Items<'a, T> {
+    pub fn iter(&self) -> Iter<'_, T> {
         let (slice1, slice2) = self.as_slices();
-        slice1.iter().chain(slice2.iter())
+        Iter { iter1: slice1.iter(), iter2: slice2.iter() }
     }
 
     /// Returns an iterator over mutable references to the elements of the
@@ -288,37 +481,105 @@ impl<T> RingBuf<T> {
     /// # Example
     ///
     /// ```rust
-    /// # use std::collections::RingBuf;
-    /// let mut ringbuf = RingBuf::from_vec(vec![1i, 2, 3]);
-    /// for num in ringbuf.mut_iter() {
+    /// # use ringbuf::RingBuf;
+    /// let mut ringbuf = RingBuf::from_vec(vec![1, 2, 3]);
+    /// for num in ringbuf.iter_mut() {
     ///     *num = 0;
     /// }
     /// ```
     #[inline]
-    pub fn mut_iter<'a>(&'a mut self) -> MutItems<'a,T> {
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
         let (slice1, slice2) = self.as_mut_slices();
-        slice1.mut_iter().chain(slice2.mut_iter())
+        IterMut { iter1: slice1.iter_mut(), iter2: slice2.iter_mut() }
     }
 
+    /// Returns an iterator over references to the elements in the given range
+    /// of the ring buffer.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the starting point is greater than the end point or if the
+    /// end point is greater than the length of the ring buffer.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use ringbuf::RingBuf;
+    /// let ringbuf = RingBuf::from_vec(vec![1, 2, 3, 4]);
+    /// let range: Vec<_> = ringbuf.range(1..3).cloned().collect();
+    /// assert_eq!(range, vec![2, 3]);
+    /// ```
+    pub fn range<R>(&self, range: R) -> Iter<'_, T> where R: RangeBounds<usize> {
+        let (range1, range2) = self.slice_ranges(range);
+        let (slice1, slice2) = self.as_slices();
+        Iter { iter1: slice1[range1].iter(), iter2: slice2[range2].iter() }
+    }
 
-    /// Creates a consuming iterator, that is, one that moves each
-    /// value out of the ringbuf (from front to back).
+    /// Returns an iterator over mutable references to the elements in the
+    /// given range of the ring buffer.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the starting point is greater than the end point or if the
+    /// end point is greater than the length of the ring buffer.
     ///
     /// # Example
     ///
     /// ```rust
-    /// # use std::collections::{RingBuf, Deque};
-    /// let mut rb = RingBuf::new();
-    /// rb.push_back("a".to_string());
-    /// rb.push_back("b".to_string());
-    /// for s in rb.move_iter() {
-    ///     // s has type String, not &String
-    ///     println!("{}", s);
+    /// # use ringbuf::RingBuf;
+    /// let mut ringbuf = RingBuf::from_vec(vec![1, 2, 3, 4]);
+    /// for num in ringbuf.range_mut(2..) {
+    ///     *num = 0;
     /// }
+    /// assert_eq!(ringbuf.into_vec(), vec![1, 2, 0, 0]);
     /// ```
-    #[inline]
-    pub fn move_iter(self) -> MoveItems<T> {
-        MoveItems { ringbuf: self }
+    pub fn range_mut<R>(&mut self, range: R) -> IterMut<'_, T> where R: RangeBounds<usize> {
+        let (range1, range2) = self.slice_ranges(range);
+        let (slice1, slice2) = self.as_mut_slices();
+        IterMut { iter1: slice1[range1].iter_mut(), iter2: slice2[range2].iter_mut() }
+    }
+
+    /// Removes the elements in the given range from the ring buffer and
+    /// returns them as an iterator.
+    ///
+    /// When the iterator is dropped, any elements in the range which have not
+    /// been yielded are dropped, and the gap is closed by moving whichever of
+    /// the elements before or after the range is shorter. If the iterator is
+    /// leaked (for instance with `mem::forget`), the ring buffer may lose the
+    /// elements after the range.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the starting point is greater than the end point or if the
+    /// end point is greater than the length of the ring buffer.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use ringbuf::RingBuf;
+    /// let mut ringbuf = RingBuf::from_vec(vec![1, 2, 3, 4, 5]);
+    /// let drained: Vec<_> = ringbuf.drain(1..3).collect();
+    /// assert_eq!(drained, vec![2, 3]);
+    /// assert_eq!(ringbuf.into_vec(), vec![1, 4, 5]);
+    /// ```
+    pub fn drain<R>(&mut self, range: R) -> Drain<'_, T, A> where R: RangeBounds<usize> {
+        let Range { start, end } = slice_range(range, self.len);
+        let len = self.len;
+
+        // Hide the drained range and the elements after it until the drain
+        // is dropped, so that leaking the drain can not expose moved-out
+        // elements.
+        self.len = start;
+
+        Drain {
+            ringbuf: self as *mut RingBuf<T, A>,
+            drain_start: start,
+            drain_len: end - start,
+            orig_len: len,
+            idx: 0,
+            remaining: end - start,
+            marker: PhantomData,
+        }
     }
 
     /// Returns the number of elements the ringbuf can hold without
@@ -327,76 +588,119 @@ impl<T> RingBuf<T> {
     /// # Example
     ///
     /// ```rust
-    /// # use std::collections::RingBuf;
-    /// let ringbuf: RingBuf<int> = RingBuf::with_capacity(10);
+    /// # use ringbuf::RingBuf;
+    /// let ringbuf: RingBuf<i32> = RingBuf::with_capacity(10);
     /// assert_eq!(ringbuf.capacity(), 10);
     /// ```
     #[inline]
-    pub fn capacity(&self) -> uint {
+    pub fn capacity(&self) -> usize {
         self.cap
     }
 
-    /// Reserves capacity for at least `n` additional elements in the given
+    /// Reserves capacity for at least `additional` more elements in the given
     /// ring buffer.
     ///
-    /// # Failure
+    /// This function will over-allocate in order to amortize the allocation
+    /// costs in scenarios where the caller may need to repeatedly reserve
+    /// additional space.
+    ///
+    /// If the capacity for `self` is already sufficient, then no action is
+    /// taken.
     ///
-    /// Fails if the new capacity overflows `uint`.
+    /// # Panics
+    ///
+    /// Panics if the new capacity overflows `usize`.
     ///
     /// # Example
     ///
     /// ```rust
-    /// # use std::collections::RingBuf;
-    /// let mut ringbuf: RingBuf<int> = RingBuf::with_capacity(1);
-    /// ringbuf.reserve_additional(10);
+    /// # use ringbuf::RingBuf;
+    /// let mut ringbuf: RingBuf<i32> = RingBuf::with_capacity(1);
+    /// ringbuf.push_back(1);
+    /// ringbuf.reserve(10);
     /// assert!(ringbuf.capacity() >= 11);
     /// ```
-    pub fn reserve_additional(&mut self, extra: uint) {
-        if self.cap - self.len < extra {
-            let size = self.len.checked_add(&extra).expect("length overflow");
-            self.reserve(size);
+    pub fn reserve(&mut self, additional: usize) {
+        if self.cap - self.len < additional {
+            let required = self.len.checked_add(additional).expect("capacity overflow");
+            let capacity = self.growth.grow(self.cap, required);
+            self.resize_buffer(capacity);
         }
     }
 
-    /// Reserves capacity for at least `n` elements in the given ring buffer.
+    /// Reserves capacity for exactly `additional` more elements in the given
+    /// ring buffer.
     ///
-    /// This function will over-allocate in order to amortize the allocation
-    /// costs in scenarios where the caller may need to repeatedly reserve
-    /// additional space.
+    /// If the capacity for `self` is already sufficient, then no action is
+    /// taken.
     ///
-    /// If the capacity for `self` is already equal to or greater than the
-    /// requested capacity, then no action is taken.
+    /// # Panics
+    ///
+    /// Panics if the new capacity overflows `usize`.
     ///
     /// # Example
     ///
     /// ```rust
-    /// # use std::collections::RingBuf;
-    /// let mut ringbuf = RingBuf::<int>::new();
-    /// ringbuf.reserve(10);
+    /// # use ringbuf::RingBuf;
+    /// let mut ringbuf: RingBuf<i32> = RingBuf::with_capacity(10);
+    /// ringbuf.reserve_exact(11);
+    /// assert_eq!(ringbuf.capacity(), 11);
+    /// ```
+    pub fn reserve_exact(&mut self, additional: usize) {
+        if self.cap - self.len < additional {
+            let capacity = self.len.checked_add(additional).expect("capacity overflow");
+            self.resize_buffer(capacity);
+        }
+    }
+
+    /// Tries to reserve capacity for at least `additional` more elements in
+    /// the given ring buffer, over-allocating like `reserve`.
+    ///
+    /// Unlike `reserve`, this returns an error instead of panicking or
+    /// aborting if the capacity overflows or the allocation fails. The ring
+    /// buffer is left unchanged on error.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use ringbuf::{RingBuf, TryReserveError};
+    /// let mut ringbuf: RingBuf<i32> = RingBuf::new();
+    /// assert_eq!(ringbuf.try_reserve(10), Ok(()));
     /// assert!(ringbuf.capacity() >= 10);
+    /// assert_eq!(ringbuf.try_reserve(usize::MAX), Err(TryReserveError::CapacityOverflow));
     /// ```
-    pub fn reserve(&mut self, capacity: uint) {
-        self.reserve_exact(num::next_power_of_two(capacity))
+    pub fn try_reserve(&mut self, additional: usize) -> Result<(), TryReserveError> {
+        if self.cap - self.len < additional {
+            let required = self.len.checked_add(additional)
+                                   .ok_or(TryReserveError::CapacityOverflow)?;
+            let capacity = self.growth.grow(self.cap, required);
+            self.try_resize_buffer(capacity)?;
+        }
+        Ok(())
     }
 
-    /// Reserves capacity for exactly `capacity` elements in the given ring
-    /// buffer.
+    /// Tries to reserve capacity for exactly `additional` more elements in
+    /// the given ring buffer.
     ///
-    /// If the capacity for `self` is already equal to or greater than the
-    /// requested capacity, then no action is taken.
+    /// Unlike `reserve_exact`, this returns an error instead of panicking or
+    /// aborting if the capacity overflows or the allocation fails. The ring
+    /// buffer is left unchanged on error.
     ///
     /// # Example
     ///
     /// ```rust
-    /// # use std::collections::RingBuf;
-    /// let mut ringbuf: RingBuf<int> = RingBuf::with_capacity(10);
-    /// ringbuf.reserve_exact(11);
+    /// # use ringbuf::RingBuf;
+    /// let mut ringbuf: RingBuf<i32> = RingBuf::with_capacity(10);
+    /// assert!(ringbuf.try_reserve_exact(11).is_ok());
     /// assert_eq!(ringbuf.capacity(), 11);
     /// ```
-    pub fn reserve_exact(&mut self, capacity: uint) {
-        if capacity > self.cap {
-            self.resize(capacity);
+    pub fn try_reserve_exact(&mut self, additional: usize) -> Result<(), TryReserveError> {
+        if self.cap - self.len < additional {
+            let capacity = self.len.checked_add(additional)
+                                   .ok_or(TryReserveError::CapacityOverflow)?;
+            self.try_resize_buffer(capacity)?;
         }
+        Ok(())
     }
 
     /// Shrink the capacity of the ring buffer as much as possible
@@ -404,570 +708,1748 @@ impl<T> RingBuf<T> {
     /// # Example
     ///
     /// ```rust
-    /// # use std::collections::{RingBuf, Deque};
-    /// let mut ringbuf = RingBuf::new();
-    /// ringbuf.push_back(1i);
+    /// # use ringbuf::RingBuf;
+    /// let mut ringbuf = RingBuf::with_capacity(10);
+    /// ringbuf.push_back(1);
     /// ringbuf.shrink_to_fit();
+    /// assert_eq!(ringbuf.capacity(), 1);
     /// ```
     pub fn shrink_to_fit(&mut self) {
         let len = self.len;
-        self.resize(len);
+        self.resize_buffer(len);
     }
 
-    /// Sets the length of a ring buffer.
+    /// Shrink the capacity of the ring buffer with a lower bound.
     ///
-    /// This will explicitly set the size of the vector, without actually
-    /// modifying its buffers, so it is up to the caller to ensure that the
-    /// vector is actually the specified size.
-    pub unsafe fn set_len(&mut self, len: uint) {
-        self.len = len;
+    /// The capacity will remain at least as large as both the length and
+    /// the supplied value. If the current capacity is less than the lower
+    /// bound, this is a no-op.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use ringbuf::RingBuf;
+    /// let mut ringbuf = RingBuf::with_capacity(10);
+    /// ringbuf.push_back(1);
+    /// ringbuf.shrink_to(4);
+    /// assert_eq!(ringbuf.capacity(), 4);
+    /// ```
+    pub fn shrink_to(&mut self, min_capacity: usize) {
+        let capacity = cmp::max(self.len, min_capacity);
+        if capacity < self.cap {
+            self.resize_buffer(capacity);
+        }
     }
-}
 
-impl<T> Collection for RingBuf<T> {
+    /// Returns the number of elements in the ring buffer.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use ringbuf::RingBuf;
+    /// let ringbuf = RingBuf::from_vec(vec![1, 2, 3]);
+    /// assert_eq!(ringbuf.len(), 3);
+    /// ```
     #[inline]
-    fn len(&self) -> uint {
+    pub fn len(&self) -> usize {
         self.len
     }
-}
 
-impl<T> Mutable for RingBuf<T> {
+    /// Returns `true` if the ring buffer contains no elements.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use ringbuf::RingBuf;
+    /// let mut ringbuf = RingBuf::new();
+    /// assert!(ringbuf.is_empty());
+    /// ringbuf.push_back(1);
+    /// assert!(!ringbuf.is_empty());
+    /// ```
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Remove all elements from the ring buffer.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use ringbuf::RingBuf;
+    /// let mut ringbuf = RingBuf::from_vec(vec![1, 2, 3]);
+    /// ringbuf.clear();
+    /// assert!(ringbuf.is_empty());
+    /// ```
     #[inline]
-    fn clear(&mut self) {
+    pub fn clear(&mut self) {
         self.truncate(0)
     }
-}
 
-impl<T> Deque<T> for RingBuf<T> {
+    /// Returns `true` if the ring buffer contains an element equal to the
+    /// given value.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use ringbuf::RingBuf;
+    /// let ringbuf = RingBuf::from_vec(vec![1, 2, 3]);
+    /// assert!(ringbuf.contains(&2));
+    /// assert!(!ringbuf.contains(&4));
+    /// ```
+    pub fn contains(&self, x: &T) -> bool where T: PartialEq {
+        let (slice1, slice2) = self.as_slices();
+        slice1.contains(x) || slice2.contains(x)
+    }
 
     /// Return a reference to the first element in the `RingBuf`.
-    fn front<'a>(&'a self) -> Option<&'a T> {
-        if self.len > 0 { Some(self.get(0)) } else { None }
+    pub fn front(&self) -> Option<&T> {
+        self.get(0)
     }
 
     /// Return a mutable reference to the first element in the `RingBuf`.
-    fn front_mut<'a>(&'a mut self) -> Option<&'a mut T> {
-        if self.len > 0 { Some(self.get_mut(0)) } else { None }
+    pub fn front_mut(&mut self) -> Option<&mut T> {
+        self.get_mut(0)
     }
 
     /// Return a reference to the last element in the `RingBuf`.
-    fn back<'a>(&'a self) -> Option<&'a T> {
-        if self.len > 0 { Some(self.get(self.len - 1)) } else { None }
+    pub fn back(&self) -> Option<&T> {
+        if self.len > 0 { self.get(self.len - 1) } else { None }
     }
 
     /// Return a mutable reference to the last element in the `RingBuf`.
-    fn back_mut<'a>(&'a mut self) -> Option<&'a mut T> {
+    pub fn back_mut(&mut self) -> Option<&mut T> {
         let len = self.len;
-        if len > 0 { Some(self.get_mut(len - 1)) } else { None }
+        if len > 0 { self.get_mut(len - 1) } else { None }
     }
 
     /// Prepend an element to a ring buffer.
     ///
-    /// # Failure
+    /// # Panics
     ///
-    /// Fails if the number of elements in the ring buffer overflows a `uint`.
+    /// Panics if the number of elements in the ring buffer overflows a `usize`.
     ///
     /// # Example
     ///
     /// ```rust
-    /// # use std::collections::{RingBuf, Deque};
+    /// # use ringbuf::RingBuf;
     /// let mut ringbuf = RingBuf::new();
-    /// ringbuf.push_back(1i);
+    /// ringbuf.push_front(1);
     /// assert_eq!(Some(&1), ringbuf.front());
     /// ```
     #[inline]
-    fn push_front(&mut self, value: T) {
-        if mem::size_of::<T>() == 0 {
-            // zero-size types consume no memory,
-            // so we can't rely on the address space running out
-            self.len = self.len.checked_add(&1).expect("length overflow");
-            unsafe { mem::forget(value); }
-            return;
-        }
-        if self.len == self.cap {
-            let capacity = cmp::max(self.len, 1) * 2;
-            self.resize(capacity);
-        }
+    pub fn push_front(&mut self, value: T) {
+        self.grow_if_full();
 
         unsafe {
             let offset = self.get_front_offset();
-            let slot = self.ptr.offset(offset as int);
-            ptr::write(slot, value);
+            ptr::write(self.ptr.add(offset), value);
             self.len += 1;
             self.lo = offset;
         }
     }
 
+    /// Prepend an element to a ring buffer, returning an error which holds
+    /// the element if the ring buffer is full and can not grow.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use ringbuf::RingBuf;
+    /// let mut ringbuf = RingBuf::new();
+    /// assert!(ringbuf.try_push_front(1).is_ok());
+    /// assert_eq!(Some(&1), ringbuf.front());
+    /// ```
+    pub fn try_push_front(&mut self, value: T) -> Result<(), TryPushError<T>> {
+        if self.len == self.cap {
+            if let Err(error) = self.try_grow() {
+                return Err(TryPushError::Alloc(value, error));
+            }
+        }
+        self.push_front(value);
+        Ok(())
+    }
+
     /// Remove the first element from a ring buffer and return it, or `None` if
     /// it is empty.
     ///
     /// # Example
     ///
     /// ```rust
-    /// # use std::collections::{RingBuf, Deque};
+    /// # use ringbuf::RingBuf;
     /// let mut ringbuf = RingBuf::new();
-    /// ringbuf.push_back(1i);
+    /// ringbuf.push_back(1);
     /// assert_eq!(Some(1), ringbuf.pop_front());
     /// assert_eq!(None, ringbuf.pop_front());
     /// ```
     #[inline]
-    fn pop_front(&mut self) -> Option<T> {
+    pub fn pop_front(&mut self) -> Option<T> {
         if self.len == 0 {
             None
         } else {
             unsafe {
-                let offset = self.get_offset(0) as int;
+                let offset = self.get_offset(0);
                 self.lo = self.get_offset(1);
                 self.len -= 1;
-                Some(ptr::read(self.ptr.offset(offset) as *const T))
+                let value = ptr::read(self.ptr.add(offset));
+                self.shrink_if_sparse();
+                Some(value)
             }
         }
     }
-}
 
-impl<T> MutableSeq<T> for RingBuf<T> {
     /// Append an element to a ring buffer.
     ///
-    /// # Failure
+    /// # Panics
     ///
-    /// Fails if the number of elements in the ring buffer overflows a `uint`.
+    /// Panics if the number of elements in the ring buffer overflows a `usize`.
     ///
     /// # Example
     ///
     /// ```rust
-    /// # use std::collections::{RingBuf, Deque};
+    /// # use ringbuf::RingBuf;
     /// let mut ringbuf = RingBuf::new();
-    /// ringbuf.push_back(1i);
+    /// ringbuf.push_back(1);
     /// assert_eq!(Some(&1), ringbuf.back());
     /// ```
     #[inline]
-    fn push(&mut self, value: T) {
-        if mem::size_of::<T>() == 0 {
-            // zero-size types consume no memory, so we can't rely on the
-            // address space running out
-            self.len = self.len.checked_add(&1).expect("length overflow");
-            unsafe { mem::forget(value); }
-            return
-        }
-        if self.len == self.cap {
-            let capacity = cmp::max(self.len, 1) * 2;
-            self.resize(capacity);
-        }
+    pub fn push_back(&mut self, value: T) {
+        self.grow_if_full();
 
         unsafe {
-            let offset = self.get_back_offset() as int;
-            let slot = self.ptr.offset(offset);
-            ptr::write(slot, value);
+            let offset = self.get_back_offset();
+            ptr::write(self.ptr.add(offset), value);
             self.len += 1;
         }
     }
 
+    /// Append an element to a ring buffer, returning an error which holds the
+    /// element if the ring buffer is full and can not grow.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use ringbuf::RingBuf;
+    /// let mut ringbuf = RingBuf::new();
+    /// assert!(ringbuf.try_push_back(1).is_ok());
+    /// assert_eq!(Some(&1), ringbuf.back());
+    /// ```
+    pub fn try_push_back(&mut self, value: T) -> Result<(), TryPushError<T>> {
+        if self.len == self.cap {
+            if let Err(error) = self.try_grow() {
+                return Err(TryPushError::Alloc(value, error));
+            }
+        }
+        self.push_back(value);
+        Ok(())
+    }
+
     /// Remove the last element from a ring buffer and return it, or `None` if
     /// it is empty.
     ///
     /// # Example
     ///
     /// ```rust
-    /// # use std::collections::{RingBuf, Deque};
+    /// # use ringbuf::RingBuf;
     /// let mut ringbuf = RingBuf::new();
-    /// ringbuf.push_back(1i);
+    /// ringbuf.push_back(1);
     /// assert_eq!(Some(1), ringbuf.pop_back());
     /// assert_eq!(None, ringbuf.pop_back());
     /// ```
     #[inline]
-    fn pop(&mut self) -> Option<T> {
+    pub fn pop_back(&mut self) -> Option<T> {
         if self.len == 0 {
             None
         } else {
             unsafe {
-                let offset = self.get_offset(self.len - 1) as int;
+                let offset = self.get_offset(self.len - 1);
                 self.len -= 1;
-                Some(ptr::read(self.ptr.offset(offset) as *const T))
+                let value = ptr::read(self.ptr.add(offset));
+                self.shrink_if_sparse();
+                Some(value)
             }
         }
     }
-}
 
+    /// Remove the element at `index` and return it, replacing it with the
+    /// first element. Returns `None` if `index` is out of bounds.
+    ///
+    /// This does not preserve ordering, but is O(1).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use ringbuf::RingBuf;
+    /// let mut ringbuf = RingBuf::from_vec(vec![1, 2, 3]);
+    /// assert_eq!(ringbuf.swap_remove_front(2), Some(3));
+    /// assert_eq!(ringbuf.into_vec(), vec![2, 1]);
+    /// ```
+    pub fn swap_remove_front(&mut self, index: usize) -> Option<T> {
+        if index >= self.len { return None }
+        if index != 0 { self.swap(index, 0); }
+        self.pop_front()
+    }
 
-impl<T> Default for RingBuf<T> {
-    #[inline]
-    fn default() -> RingBuf<T> { RingBuf::new() }
-}
+    /// Remove the element at `index` and return it, replacing it with the
+    /// last element. Returns `None` if `index` is out of bounds.
+    ///
+    /// This does not preserve ordering, but is O(1).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use ringbuf::RingBuf;
+    /// let mut ringbuf = RingBuf::from_vec(vec![1, 2, 3]);
+    /// assert_eq!(ringbuf.swap_remove_back(0), Some(1));
+    /// assert_eq!(ringbuf.into_vec(), vec![3, 2]);
+    /// ```
+    pub fn swap_remove_back(&mut self, index: usize) -> Option<T> {
+        if index >= self.len { return None }
+        let last = self.len - 1;
+        if index != last { self.swap(index, last); }
+        self.pop_back()
+    }
 
-impl<T:Clone> Clone for RingBuf<T> {
-    fn clone(&self) -> RingBuf<T> {
-        let mut ringbuf = RingBuf::with_capacity(self.len);
-        // Unsafe code so this can be optimised to a memcpy (or something
-        // similarly fast) when T is Copy. LLVM is easily confused, so any
-        // extra operations during the loop can prevent this optimisation
-        {
-            let (slice1, slice2) = self.as_slices();
-            while ringbuf.len < slice1.len() {
-                unsafe {
-                    let len = ringbuf.len;
-                    ptr::write(
-                        ringbuf.ptr.offset(len as int),
-                        slice1.unsafe_ref(len).clone());
-                }
-                ringbuf.len += 1;
-            }
-            while ringbuf.len < slice1.len() + slice2.len() {
-                unsafe {
-                    let len = ringbuf.len;
-                    ptr::write(
-                        ringbuf.ptr.offset(len as int),
-                        slice2.unsafe_ref(len - slice1.len()).clone());
-                }
-                ringbuf.len += 1;
+    /// Insert an element at `index` within the ring buffer, shifting all
+    /// elements with indices greater than or equal to `index` towards the
+    /// back.
+    ///
+    /// Whichever side of `index` holds fewer elements is moved, so inserting
+    /// near either end of the ring is cheap.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is greater than the ring buffer's length, or if the
+    /// number of elements in the ring buffer overflows a `usize`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use ringbuf::RingBuf;
+    /// let mut ringbuf = RingBuf::from_vec(vec![1, 2, 4]);
+    /// ringbuf.insert(2, 3);
+    /// assert_eq!(ringbuf.into_vec(), vec![1, 2, 3, 4]);
+    /// ```
+    pub fn insert(&mut self, index: usize, value: T) {
+        assert!(index <= self.len, "index out of bounds");
+        self.grow_if_full();
+
+        unsafe {
+            if index < self.len - index {
+                // Move the front of the ring back by one slot.
+                //
+                //      lo      index
+                //       V        V
+                // +-+-+-+-+-+-+-+-+-+-+
+                // | | |x|A|B|C|x|x|x| |
+                // +-+-+-+-+-+-+-+-+-+-+
+                self.lo = self.get_front_offset();
+                self.copy_elements(1, 0, index);
+            } else {
+                // Move the back of the ring forward by one slot.
+                let count = self.len - index;
+                self.copy_elements(index, index + 1, count);
             }
+            let offset = self.get_offset(index);
+            ptr::write(self.ptr.add(offset), value);
         }
-        ringbuf
+        self.len += 1;
     }
 
-    fn clone_from(&mut self, source: &RingBuf<T>) {
-        // drop anything in self that will not be overwritten
-        if self.len() > source.len() {
-            self.truncate(source.len())
-        }
+    /// Remove the element at `index` and return it, or `None` if `index` is
+    /// out of bounds.
+    ///
+    /// The elements on whichever side of `index` is shorter are shifted to
+    /// close the gap.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use ringbuf::RingBuf;
+    /// let mut ringbuf = RingBuf::from_vec(vec![1, 2, 3, 4]);
+    /// assert_eq!(ringbuf.remove(1), Some(2));
+    /// assert_eq!(ringbuf.remove(3), None);
+    /// assert_eq!(ringbuf.into_vec(), vec![1, 3, 4]);
+    /// ```
+    pub fn remove(&mut self, index: usize) -> Option<T> {
+        if index >= self.len { return None }
 
-        // reuse the contained values' allocations/resources.
-        for (place, thing) in self.mut_iter().zip(source.iter()) {
-            place.clone_from(thing)
+        unsafe {
+            let offset = self.get_offset(index);
+            let value = ptr::read(self.ptr.add(offset));
+            if index < self.len - index - 1 {
+                // Close the gap by moving the front of the ring forward.
+                self.copy_elements(0, 1, index);
+                self.lo = self.get_offset(1);
+            } else {
+                // Close the gap by moving the back of the ring back.
+                let count = self.len - index - 1;
+                self.copy_elements(index + 1, index, count);
+            }
+            self.len -= 1;
+            Some(value)
         }
-
-        // self.len <= source.len due to the truncate above, so the
-        // slice here is always in-bounds.
-        let len = self.len();
-        self.extend(source.iter().skip(len).map(|x| x.clone()));
-    }
-}
-
-impl<T> FromIterator<T> for RingBuf<T> {
-    fn from_iter<I:Iterator<T>>(mut iterator: I) -> RingBuf<T> {
-        RingBuf::from_vec(iterator.collect())
     }
-}
 
-impl<T> Extendable<T> for RingBuf<T> {
-    fn extend<I: Iterator<T>>(&mut self, mut iterator: I) {
+    /// Splits the ring buffer into two at the given index.
+    ///
+    /// Returns a newly allocated ring buffer containing the elements in the
+    /// range `[at, len)`. After the call, `self` will be left containing the
+    /// elements `[0, at)` with its previous capacity unchanged.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `at` is greater than the ring buffer's length.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use ringbuf::RingBuf;
+    /// let mut ringbuf = RingBuf::from_vec(vec![1, 2, 3, 4]);
+    /// let other = ringbuf.split_off(1);
+    /// assert_eq!(ringbuf.into_vec(), vec![1]);
+    /// assert_eq!(other.into_vec(), vec![2, 3, 4]);
+    /// ```
+    pub fn split_off(&mut self, at: usize) -> RingBuf<T, A> where A: Clone {
+        assert!(at <= self.len, "`at` out of bounds");
+
+        let mut other = RingBuf::with_capacity_in(self.len - at, self.alloc.clone());
+        other.growth = self.growth;
+        other.shrink_divisor = self.shrink_divisor;
+        let (range1, range2) = self.slice_ranges(at..);
+        let (slice1, slice2) = self.as_slices();
+        let (slice1, slice2) = (&slice1[range1], &slice2[range2]);
+        unsafe {
+            other.copy_to_back(slice1.as_ptr(), slice1.len());
+            other.copy_to_back(slice2.as_ptr(), slice2.len());
+        }
+        self.len = at;
+        other
+    }
+
+    /// Moves all the elements of `other` into the back of `self`, leaving
+    /// `other` empty.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of elements in the ring buffer overflows a `usize`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use ringbuf::RingBuf;
+    /// let mut ringbuf = RingBuf::from_vec(vec![1, 2]);
+    /// let mut other = RingBuf::from_vec(vec![3, 4]);
+    /// ringbuf.append(&mut other);
+    /// assert!(other.is_empty());
+    /// assert_eq!(ringbuf.into_vec(), vec![1, 2, 3, 4]);
+    /// ```
+    pub fn append(&mut self, other: &mut RingBuf<T, A>) {
+        self.reserve(other.len);
+
+        let (slice1, slice2) = other.as_slices();
+        unsafe {
+            self.copy_to_back(slice1.as_ptr(), slice1.len());
+            self.copy_to_back(slice2.as_ptr(), slice2.len());
+        }
+        other.len = 0;
+        other.lo = 0;
+    }
+
+    /// Retain only the elements specified by the predicate.
+    ///
+    /// Removes all elements `e` for which `f(&e)` returns `false`. The order
+    /// of the retained elements is preserved.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use ringbuf::RingBuf;
+    /// let mut ringbuf = RingBuf::from_vec(vec![1, 2, 3, 4]);
+    /// ringbuf.retain(|&x| x % 2 == 0);
+    /// assert_eq!(ringbuf.into_vec(), vec![2, 4]);
+    /// ```
+    pub fn retain<F>(&mut self, mut f: F) where F: FnMut(&T) -> bool {
+        self.retain_mut(|elem| f(elem));
+    }
+
+    /// Retain only the elements specified by the predicate, passing a mutable
+    /// reference to each element.
+    ///
+    /// Removes all elements `e` for which `f(&mut e)` returns `false`. The
+    /// order of the retained elements is preserved.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use ringbuf::RingBuf;
+    /// let mut ringbuf = RingBuf::from_vec(vec![1, 2, 3, 4]);
+    /// ringbuf.retain_mut(|x| { *x += 1; *x % 2 == 0 });
+    /// assert_eq!(ringbuf.into_vec(), vec![2, 4]);
+    /// ```
+    pub fn retain_mut<F>(&mut self, mut f: F) where F: FnMut(&mut T) -> bool {
+        let len = self.len;
+        let mut deleted = 0;
+        for i in 0..len {
+            if !f(&mut self[i]) {
+                deleted += 1;
+            } else if deleted > 0 {
+                self.swap(i - deleted, i);
+            }
+        }
+        if deleted > 0 {
+            self.truncate(len - deleted);
+        }
+    }
+
+    /// Modifies the ring buffer in-place so that `len()` is equal to
+    /// `new_len`, either by removing excess elements from the back or by
+    /// appending elements generated by calling `generator` to the back.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use ringbuf::RingBuf;
+    /// let mut ringbuf = RingBuf::from_vec(vec![1, 2]);
+    /// let mut next = 3;
+    /// ringbuf.resize_with(4, || { next += 1; next - 1 });
+    /// assert_eq!(ringbuf.into_vec(), vec![1, 2, 3, 4]);
+    /// ```
+    pub fn resize_with<F>(&mut self, new_len: usize, mut generator: F) where F: FnMut() -> T {
+        if new_len > self.len {
+            self.reserve(new_len - self.len);
+            while self.len < new_len {
+                self.push_back(generator());
+            }
+        } else {
+            self.truncate(new_len);
+        }
+    }
+
+    /// Modifies the ring buffer in-place so that `len()` is equal to
+    /// `new_len`, either by removing excess elements from the back or by
+    /// appending clones of `value` to the back.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use ringbuf::RingBuf;
+    /// let mut ringbuf = RingBuf::from_vec(vec![1, 2]);
+    /// ringbuf.resize(4, 0);
+    /// assert_eq!(ringbuf.into_vec(), vec![1, 2, 0, 0]);
+    /// ```
+    pub fn resize(&mut self, new_len: usize, value: T) where T: Clone {
+        self.resize_with(new_len, || value.clone());
+    }
+
+    /// Rearrange the elements of the ring buffer so that they are stored
+    /// contiguously, and return a mutable slice of them.
+    ///
+    /// This does not change the order of the elements, and it never
+    /// allocates. Afterwards the first slice returned by `as_slices` holds
+    /// every element.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use ringbuf::RingBuf;
+    /// let mut ringbuf = RingBuf::with_capacity(4);
+    /// ringbuf.push_back(2);
+    /// ringbuf.push_back(3);
+    /// ringbuf.push_front(1);
+    /// ringbuf.make_contiguous().sort();
+    /// assert_eq!(ringbuf.as_slices(), (&[1, 2, 3][..], &[][..]));
+    /// ```
+    pub fn make_contiguous(&mut self) -> &mut [T] {
+        let (_, len1, _, len2) = self.get_slice_ptrs();
+        if len2 != 0 {
+            // `slice1` begins at the `lo` index and ends at the end of the
+            // buffer. `slice2` begins at the `0` index.
+            let free = self.cap - self.len;
+            unsafe {
+                if free >= len1 {
+                    // There is sufficient space to move slice2 without
+                    // overwriting slice1.
+                    //
+                    //           lo
+                    //            V
+                    // +-+-+-+-+-+-+-+
+                    // |x|x|x| | |x|x|
+                    // +-+-+-+-+-+-+-+
+                    ptr::copy(self.ptr, self.ptr.add(len1), len2);
+                    ptr::copy_nonoverlapping(self.ptr.add(self.lo), self.ptr, len1);
+                    self.lo = 0;
+                } else if free >= len2 {
+                    // There is sufficient space to move slice2 after slice1
+                    // once slice1 has been moved to the front of the gap.
+                    //
+                    //       lo
+                    //        V
+                    // +-+-+-+-+-+-+-+
+                    // |x| | |x|x|x|x|
+                    // +-+-+-+-+-+-+-+
+                    ptr::copy(self.ptr.add(self.lo), self.ptr.add(len2), len1);
+                    ptr::copy_nonoverlapping(self.ptr, self.ptr.add(self.len), len2);
+                    self.lo = len2;
+                } else if len1 > len2 {
+                    // Close the gap by moving slice2 up against slice1, then
+                    // rotate the slices into order.
+                    //
+                    //         lo
+                    //          V
+                    // +-+-+-+-+-+-+-+
+                    // |x|x| | |x|x|x|
+                    // +-+-+-+-+-+-+-+
+                    ptr::copy(self.ptr, self.ptr.add(free), len2);
+                    slice::from_raw_parts_mut(self.ptr.add(free), self.len).rotate_left(len2);
+                    self.lo = free;
+                } else {
+                    // Close the gap by moving slice1 down against slice2,
+                    // then rotate the slices into order.
+                    //
+                    //           lo
+                    //            V
+                    // +-+-+-+-+-+-+-+
+                    // |x|x|x| | |x|x|
+                    // +-+-+-+-+-+-+-+
+                    ptr::copy(self.ptr.add(self.lo), self.ptr.add(len2), len1);
+                    slice::from_raw_parts_mut(self.ptr, self.len).rotate_right(len1);
+                    self.lo = 0;
+                }
+            }
+        }
+
+        let (ptr1, len1, _, _) = self.get_slice_ptrs();
+        unsafe { slice::from_raw_parts_mut(ptr1, len1) }
+    }
+
+    /// Rotate the ring buffer `n` places to the left, so that the element at
+    /// index `n` becomes the first element.
+    ///
+    /// This runs in place in `O(min(n, len - n))` time, and never allocates.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n` is greater than the ring buffer's length.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use ringbuf::RingBuf;
+    /// let mut ringbuf = RingBuf::from_vec(vec![1, 2, 3, 4, 5]);
+    /// ringbuf.rotate_left(2);
+    /// assert_eq!(ringbuf.into_vec(), vec![3, 4, 5, 1, 2]);
+    /// ```
+    pub fn rotate_left(&mut self, n: usize) {
+        assert!(n <= self.len, "rotation out of bounds");
+        let k = self.len - n;
+        unsafe {
+            if n <= k {
+                self.rotate_left_inner(n);
+            } else {
+                self.rotate_right_inner(k);
+            }
+        }
+    }
+
+    /// Rotate the ring buffer `n` places to the right, so that the first
+    /// element moves to index `n`.
+    ///
+    /// This runs in place in `O(min(n, len - n))` time, and never allocates.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n` is greater than the ring buffer's length.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use ringbuf::RingBuf;
+    /// let mut ringbuf = RingBuf::from_vec(vec![1, 2, 3, 4, 5]);
+    /// ringbuf.rotate_right(2);
+    /// assert_eq!(ringbuf.into_vec(), vec![4, 5, 1, 2, 3]);
+    /// ```
+    pub fn rotate_right(&mut self, n: usize) {
+        assert!(n <= self.len, "rotation out of bounds");
+        let k = self.len - n;
+        unsafe {
+            if n <= k {
+                self.rotate_right_inner(n);
+            } else {
+                self.rotate_left_inner(k);
+            }
+        }
+    }
+
+    /// Binary searches this sorted ring buffer for a given element.
+    ///
+    /// If the value is found then `Ok` is returned, containing the index of
+    /// the matching element. If the value is not found then `Err` is
+    /// returned, containing the index where a matching element could be
+    /// inserted while maintaining sorted order.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use ringbuf::RingBuf;
+    /// let ringbuf = RingBuf::from_vec(vec![1, 3, 5, 7]);
+    /// assert_eq!(ringbuf.binary_search(&5), Ok(2));
+    /// assert_eq!(ringbuf.binary_search(&4), Err(2));
+    /// ```
+    pub fn binary_search(&self, x: &T) -> Result<usize, usize> where T: Ord {
+        self.binary_search_by(|elem| elem.cmp(x))
+    }
+
+    /// Binary searches this sorted ring buffer with a comparator function.
+    ///
+    /// The comparator function should return the ordering of its argument
+    /// relative to the desired target.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use ringbuf::RingBuf;
+    /// let ringbuf = RingBuf::from_vec(vec![1, 3, 5, 7]);
+    /// assert_eq!(ringbuf.binary_search_by(|x| x.cmp(&7)), Ok(3));
+    /// ```
+    pub fn binary_search_by<'a, F>(&'a self, f: F) -> Result<usize, usize>
+            where F: FnMut(&'a T) -> Ordering {
+        let (slice1, slice2) = self.as_slices();
+        binary_search_slices(slice1, slice2, f)
+    }
+
+    /// Binary searches this sorted ring buffer with a key extraction
+    /// function.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use ringbuf::RingBuf;
+    /// let ringbuf = RingBuf::from_vec(vec![(0, 'a'), (2, 'b'), (4, 'c')]);
+    /// assert_eq!(ringbuf.binary_search_by_key(&2, |&(k, _)| k), Ok(1));
+    /// ```
+    pub fn binary_search_by_key<'a, B, F>(&'a self, b: &B, mut f: F) -> Result<usize, usize>
+            where F: FnMut(&'a T) -> B, B: Ord {
+        self.binary_search_by(|elem| f(elem).cmp(b))
+    }
+
+    /// Returns the index of the partition point of a ring buffer partitioned
+    /// according to the given predicate.
+    ///
+    /// The ring buffer is assumed to be partitioned such that all elements
+    /// for which the predicate returns `true` precede all elements for which
+    /// it returns `false`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use ringbuf::RingBuf;
+    /// let ringbuf = RingBuf::from_vec(vec![1, 2, 3, 3, 5, 6, 7]);
+    /// assert_eq!(ringbuf.partition_point(|&x| x < 5), 4);
+    /// ```
+    pub fn partition_point<P>(&self, pred: P) -> usize where P: FnMut(&T) -> bool {
+        let (slice1, slice2) = self.as_slices();
+        partition_point_slices(slice1, slice2, pred)
+    }
+
+    /// Sets the length of a ring buffer.
+    ///
+    /// This will explicitly set the size of the vector, without actually
+    /// modifying its buffers, so it is up to the caller to ensure that the
+    /// vector is actually the specified size.
+    ///
+    /// # Safety
+    ///
+    /// `len` must not exceed the capacity, and the elements between the old
+    /// and new length must be initialized.
+    pub unsafe fn set_len(&mut self, len: usize) {
+        self.len = len;
+    }
+
+    /// Returns the free space after the back of the ring buffer as a pair of
+    /// slices of uninitialized slots, in the order they would be pushed with
+    /// `push_back`.
+    ///
+    /// The free space wraps around the end of the buffer when the elements
+    /// do not, so the second slice may be non-empty. After initializing the
+    /// first `n` slots, call `commit_back(n)` to append them.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use ringbuf::RingBuf;
+    /// let mut ringbuf: RingBuf<i32> = RingBuf::with_capacity(4);
+    /// ringbuf.push_back(1);
+    /// let (slice1, slice2) = ringbuf.spare_capacity_mut();
+    /// assert_eq!(slice1.len() + slice2.len(), 3);
+    /// slice1[0].write(2);
+    /// unsafe { ringbuf.commit_back(1); }
+    /// assert_eq!(ringbuf, RingBuf::from([1, 2]));
+    /// ```
+    pub fn spare_capacity_mut(&mut self) -> (&mut [MaybeUninit<T>], &mut [MaybeUninit<T>]) {
+        let offset = self.get_back_offset();
+        let free = self.cap - self.len;
+        let len1 = cmp::min(free, self.cap - offset);
+        unsafe {
+            let ptr = self.ptr as *mut MaybeUninit<T>;
+            (slice::from_raw_parts_mut(ptr.add(offset), len1),
+             slice::from_raw_parts_mut(ptr, free - len1))
+        }
+    }
+
+    /// Appends the first `n` slots returned by `spare_capacity_mut` to the
+    /// back of the ring buffer.
+    ///
+    /// # Safety
+    ///
+    /// `n` must not exceed the free capacity, and the first `n` slots of the
+    /// spare capacity must be initialized.
+    pub unsafe fn commit_back(&mut self, n: usize) {
+        debug_assert!(n <= self.cap - self.len);
+        self.len += n;
+    }
+
+    /// Returns the free space before the front of the ring buffer as a pair
+    /// of slices of uninitialized slots, in index order.
+    ///
+    /// The slots nearest the front are at the end of the second slice, and
+    /// are prepended first. After initializing the last `n` slots, call
+    /// `commit_front(n)` to prepend them.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use ringbuf::RingBuf;
+    /// let mut ringbuf: RingBuf<i32> = RingBuf::with_capacity(4);
+    /// ringbuf.push_back(3);
+    /// let (slice1, slice2) = ringbuf.spare_front_mut();
+    /// assert_eq!(slice1.len() + slice2.len(), 3);
+    /// let mut slots = slice1.iter_mut().chain(slice2).rev();
+    /// slots.next().unwrap().write(2);
+    /// slots.next().unwrap().write(1);
+    /// unsafe { ringbuf.commit_front(2); }
+    /// assert_eq!(ringbuf, RingBuf::from([1, 2, 3]));
+    /// ```
+    pub fn spare_front_mut(&mut self) -> (&mut [MaybeUninit<T>], &mut [MaybeUninit<T>]) {
+        let free = self.cap - self.len;
+        let len2 = cmp::min(free, self.lo);
+        unsafe {
+            let ptr = self.ptr as *mut MaybeUninit<T>;
+            (slice::from_raw_parts_mut(ptr.add(self.cap - (free - len2)), free - len2),
+             slice::from_raw_parts_mut(ptr.add(self.lo - len2), len2))
+        }
+    }
+
+    /// Prepends the last `n` slots returned by `spare_front_mut` to the front
+    /// of the ring buffer.
+    ///
+    /// # Safety
+    ///
+    /// `n` must not exceed the free capacity, and the last `n` slots of the
+    /// spare front capacity must be initialized.
+    pub unsafe fn commit_front(&mut self, n: usize) {
+        debug_assert!(n <= self.cap - self.len);
+        self.lo = if n <= self.lo { self.lo - n } else { self.cap - (n - self.lo) };
+        self.len += n;
+    }
+}
+
+impl<T> Default for RingBuf<T> {
+    #[inline]
+    fn default() -> RingBuf<T> { RingBuf::new() }
+}
+
+impl<T: Clone, A: Allocator + Clone> Clone for RingBuf<T, A> {
+    fn clone(&self) -> RingBuf<T, A> {
+        let mut ringbuf: RingBuf<T, A> = RingBuf::with_capacity_in(self.len, self.alloc.clone());
+        ringbuf.growth = self.growth;
+        ringbuf.shrink_divisor = self.shrink_divisor;
+        // Unsafe code so this can be optimised to a memcpy (or something
+        // similarly fast) when T is Copy. LLVM is easily confused, so any
+        // extra operations during the loop can prevent this optimisation
+        {
+            let (slice1, slice2) = self.as_slices();
+            while ringbuf.len < slice1.len() {
+                unsafe {
+                    let len = ringbuf.len;
+                    ptr::write(
+                        ringbuf.ptr.add(len),
+                        slice1.get_unchecked(len).clone());
+                }
+                ringbuf.len += 1;
+            }
+            while ringbuf.len < slice1.len() + slice2.len() {
+                unsafe {
+                    let len = ringbuf.len;
+                    ptr::write(
+                        ringbuf.ptr.add(len),
+                        slice2.get_unchecked(len - slice1.len()).clone());
+                }
+                ringbuf.len += 1;
+            }
+        }
+        ringbuf
+    }
+
+    fn clone_from(&mut self, source: &RingBuf<T, A>) {
+        // drop anything in self that will not be overwritten
+        if self.len() > source.len() {
+            self.truncate(source.len())
+        }
+
+        // reuse the contained values' allocations/resources.
+        for (place, thing) in self.iter_mut().zip(source.iter()) {
+            place.clone_from(thing)
+        }
+
+        // self.len <= source.len due to the truncate above, so the
+        // slice here is always in-bounds.
+        let len = self.len();
+        self.extend(source.iter().skip(len).cloned());
+    }
+}
+
+impl<T> FromIterator<T> for RingBuf<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iterator: I) -> RingBuf<T> {
+        RingBuf::from_vec(iterator.into_iter().collect())
+    }
+}
+
+impl<T, A: Allocator> Extend<T> for RingBuf<T, A> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iterator: I) {
+        let iterator = iterator.into_iter();
         let (lower, _) = iterator.size_hint();
-        self.reserve_additional(lower);
+        self.reserve(lower);
         for element in iterator {
             self.push_back(element)
         }
     }
 }
 
-/// Allocate a buffer with the provided capacity.
-// FIXME: #13996: need a way to mark the return value as `noalias`
+impl<'a, T: Copy + 'a, A: Allocator> Extend<&'a T> for RingBuf<T, A> {
+    fn extend<I: IntoIterator<Item = &'a T>>(&mut self, iterator: I) {
+        self.extend(iterator.into_iter().cloned());
+    }
+}
+
+impl<T> From<Vec<T>> for RingBuf<T> {
+    fn from(vec: Vec<T>) -> RingBuf<T> {
+        RingBuf::from_vec(vec)
+    }
+}
+
+impl<T> From<RingBuf<T>> for Vec<T> {
+    fn from(ringbuf: RingBuf<T>) -> Vec<T> {
+        ringbuf.into_vec()
+    }
+}
+
+impl<T, const N: usize> From<[T; N]> for RingBuf<T> {
+    fn from(array: [T; N]) -> RingBuf<T> {
+        RingBuf::from_vec(Vec::from(array))
+    }
+}
+
+impl<T, A: Allocator> Index<usize> for RingBuf<T, A> {
+    type Output = T;
+
+    #[inline]
+    fn index(&self, index: usize) -> &T {
+        self.get(index).expect("index out of bounds")
+    }
+}
+
+impl<T, A: Allocator> IndexMut<usize> for RingBuf<T, A> {
+    #[inline]
+    fn index_mut(&mut self, index: usize) -> &mut T {
+        self.get_mut(index).expect("index out of bounds")
+    }
+}
+
+/// Allocate a buffer with the provided capacity from `alloc`.
+///
+/// The layout of the buffer matches that of a `Vec<T>` with the same
+/// capacity, so buffers from the global allocator can be exchanged with
+/// `Vec`.
 #[inline(never)]
-unsafe fn alloc<T>(capacity: uint) -> *mut T {
-    let mut vec = Vec::<T>::with_capacity(capacity);
-    let ptr = vec.as_mut_ptr();
-    mem::forget(vec);
-    ptr
+unsafe fn alloc_buffer<T, A: Allocator>(alloc: &A, capacity: usize) -> *mut T {
+    match try_alloc_buffer(alloc, capacity) {
+        Ok(ptr) => ptr,
+        Err(error) => error.handle(),
+    }
 }
 
-/// Deallocate a buffer of the provided capacity.
+/// Allocate a buffer with the provided capacity from `alloc`, returning an
+/// error instead of aborting if the allocation fails.
+#[inline(never)]
+unsafe fn try_alloc_buffer<T, A: Allocator>(alloc: &A,
+                                            capacity: usize)
+                                            -> Result<*mut T, TryReserveError> {
+    let layout = Layout::array::<T>(capacity).map_err(|_| TryReserveError::CapacityOverflow)?;
+    if layout.size() == 0 { return Ok(ptr::NonNull::dangling().as_ptr()) }
+    match alloc.allocate(layout) {
+        Ok(ptr) => Ok(ptr.as_ptr() as *mut T),
+        Err(_) => Err(TryReserveError::AllocError { layout }),
+    }
+}
+
+/// Deallocate a buffer of the provided capacity from `alloc`.
 #[inline]
-unsafe fn dealloc<T>(ptr: *mut T, capacity: uint) {
-    Vec::from_raw_parts(0, capacity, ptr);
+unsafe fn dealloc_buffer<T, A: Allocator>(alloc: &A, ptr: *mut T, capacity: usize) {
+    if mem::size_of::<T>() == 0 || capacity == 0 { return }
+    let layout = Layout::array::<T>(capacity).expect("capacity overflow");
+    alloc.deallocate(ptr::NonNull::new_unchecked(ptr as *mut u8), layout);
 }
 
-impl<T> RingBuf<T> {
+/// Return the offset of the given index in a buffer of capacity `cap` holding
+/// a ring which starts at offset `lo`.
+#[inline]
+fn wrap_offset(lo: usize, cap: usize, index: usize) -> usize {
+    // The order of these operations preserves numerical stability
+    if lo >= cap - index {
+        index - (cap - lo)
+    } else {
+        lo + index
+    }
+}
+
+/// Move `count` elements of a ring from index `src` to index `dst`. The ring
+/// starts at offset `lo` of the buffer of capacity `cap` at `ptr`. The source
+/// and destination may overlap, and either may wrap around the end of the
+/// buffer.
+///
+/// `src + count` and `dst + count` must not exceed the capacity. The elements
+/// in the source range which are not overwritten are left logically
+/// uninitialized.
+unsafe fn copy_wrapped<T>(ptr: *mut T,
+                          cap: usize,
+                          lo: usize,
+                          src: usize,
+                          dst: usize,
+                          count: usize) {
+    if src == dst || count == 0 { return }
+
+    if dst > src && dst < src + count {
+        // The destination overlaps the end of the source, so copy from back
+        // to front in runs that do not wrap.
+        let mut remaining = count;
+        while remaining > 0 {
+            let src_end = wrap_offset(lo, cap, src + remaining - 1) + 1;
+            let dst_end = wrap_offset(lo, cap, dst + remaining - 1) + 1;
+            let n = cmp::min(remaining, cmp::min(src_end, dst_end));
+            ptr::copy(ptr.add(src_end - n), ptr.add(dst_end - n), n);
+            remaining -= n;
+        }
+    } else {
+        // Copy from front to back in runs that do not wrap.
+        let mut copied = 0;
+        while copied < count {
+            let src_offset = wrap_offset(lo, cap, src + copied);
+            let dst_offset = wrap_offset(lo, cap, dst + copied);
+            let n = cmp::min(count - copied, cmp::min(cap - src_offset, cap - dst_offset));
+            ptr::copy(ptr.add(src_offset), ptr.add(dst_offset), n);
+            copied += n;
+        }
+    }
+}
+
+/// Converts a range over the elements of a sequence of length `len` into a
+/// half-open `Range`.
+///
+/// # Panics
+///
+/// Panics if the start of the range is greater than the end, or if the end is
+/// greater than `len`.
+fn slice_range<R>(range: R, len: usize) -> Range<usize> where R: RangeBounds<usize> {
+    let start = match range.start_bound() {
+        Bound::Included(&start) => start,
+        Bound::Excluded(&start) => start.checked_add(1).expect("range start overflow"),
+        Bound::Unbounded => 0,
+    };
+    let end = match range.end_bound() {
+        Bound::Included(&end) => end.checked_add(1).expect("range end overflow"),
+        Bound::Excluded(&end) => end,
+        Bound::Unbounded => len,
+    };
+    assert!(start <= end, "range start is greater than range end");
+    assert!(end <= len, "range end out of bounds");
+    start..end
+}
+
+/// Splits a range over the elements of a ring into a range over its first
+/// slice, of length `len1`, and a range over its second slice.
+fn split_range(range: Range<usize>, len1: usize) -> (Range<usize>, Range<usize>) {
+    let Range { start, end } = range;
+    if end <= len1 {
+        (start..end, 0..0)
+    } else if start >= len1 {
+        (len1..len1, start - len1..end - len1)
+    } else {
+        (start..len1, 0..end - len1)
+    }
+}
+
+/// Binary searches a sorted ring, given as its pair of slices, with a
+/// comparator function.
+fn binary_search_slices<'a, T, F>(slice1: &'a [T],
+                                  slice2: &'a [T],
+                                  mut f: F) -> Result<usize, usize>
+        where F: FnMut(&'a T) -> Ordering {
+    match slice2.first().map(&mut f) {
+        Some(Ordering::Equal) => Ok(slice1.len()),
+        Some(Ordering::Less) => {
+            let offset = slice1.len() + 1;
+            slice2[1..].binary_search_by(f)
+                       .map(|index| index + offset)
+                       .map_err(|index| index + offset)
+        }
+        _ => slice1.binary_search_by(f),
+    }
+}
+
+/// Returns the partition point of a ring, given as its pair of slices.
+fn partition_point_slices<T, P>(slice1: &[T], slice2: &[T], mut pred: P) -> usize
+        where P: FnMut(&T) -> bool {
+    match slice2.first() {
+        Some(first) if pred(first) => slice1.len() + slice2.partition_point(pred),
+        _ => slice1.partition_point(pred),
+    }
+}
+
+impl<T, A: Allocator> RingBuf<T, A> {
 
     /// Calculates the start and length of the slices in this ringbuf.
     #[inline]
-    fn get_slice_ptrs(&self) -> (*const T, uint, *const T, uint) {
+    fn get_slice_ptrs(&self) -> (*mut T, usize, *mut T, usize) {
         let ptr1;
         let ptr2;
         let len1;
         let len2;
         unsafe {
             if self.lo > self.cap - self.len {
-                ptr1 = self.ptr.offset(self.lo as int);
+                ptr1 = self.ptr.add(self.lo);
                 ptr2 = self.ptr;
                 len1 = self.cap - self.lo;
                 len2 = self.len - len1;
             } else {
-                ptr1 = self.ptr.offset(self.lo as int);
+                ptr1 = self.ptr.add(self.lo);
                 ptr2 = self.ptr;
                 len1 = self.len;
                 len2 = 0;
             }
         }
-        (ptr1 as *const T, len1, ptr2 as *const T, len2)
+        (ptr1, len1, ptr2, len2)
+    }
+
+    /// Splits a range over the elements of the ring into ranges over the
+    /// first and second slices returned by `as_slices`.
+    fn slice_ranges<R>(&self, range: R) -> (Range<usize>, Range<usize>)
+            where R: RangeBounds<usize> {
+        let (_, len1, _, _) = self.get_slice_ptrs();
+        split_range(slice_range(range, self.len), len1)
+    }
+
+    /// Resize the buffer of the `RingBuf` to the specified capacity.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of elements in the ring buffer is greater than
+    /// the requested capacity, or if the capacity overflows.
+    fn resize_buffer(&mut self, capacity: usize) {
+        if let Err(error) = self.try_resize_buffer(capacity) {
+            error.handle()
+        }
+    }
+
+    /// Resize the buffer of the `RingBuf` to the specified capacity,
+    /// returning an error if the new buffer can not be allocated.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of elements in the ring buffer is greater than
+    /// the requested capacity.
+    fn try_resize_buffer(&mut self, capacity: usize) -> Result<(), TryReserveError> {
+        assert!(capacity >= self.len, "capacity underflow");
+
+        if capacity == self.cap { return Ok(()) }
+        if mem::size_of::<T>() == 0 { return Ok(()) }
+        if capacity > self.cap && self.cap > 0 { return self.try_grow_buffer(capacity) }
+
+        let ptr;
+        unsafe {
+            ptr = try_alloc_buffer::<T, A>(&self.alloc, capacity)?;
+            let (slice1, slice2) = self.as_slices();
+            let len1 = slice1.len();
+            ptr::copy_nonoverlapping(slice1.as_ptr(), ptr, len1);
+            ptr::copy_nonoverlapping(slice2.as_ptr(),
+                                     ptr.add(len1),
+                                     slice2.len());
+            dealloc_buffer(&self.alloc, self.ptr, self.cap);
+        }
+
+        self.ptr = ptr;
+        self.cap = capacity;
+        self.lo = 0;
+        self.init = self.len;
+        Ok(())
+    }
+
+    /// Grow an allocated buffer to the specified capacity with the
+    /// allocator's `grow`, which can often extend the buffer in place instead
+    /// of copying it.
+    ///
+    /// The elements keep their offsets, so a ring which does not wrap needs
+    /// no further moves. A wrapped ring moves the shorter of its two parts:
+    /// the part at the start of the buffer to just after the old end, if it
+    /// fits, or otherwise the part at the end of the buffer to the new end.
+    fn try_grow_buffer(&mut self, capacity: usize) -> Result<(), TryReserveError> {
+        debug_assert!(capacity > self.cap && self.cap > 0);
+
+        let old_layout = Layout::array::<T>(self.cap).expect("capacity overflow");
+        let layout = Layout::array::<T>(capacity).map_err(|_| TryReserveError::CapacityOverflow)?;
+        let ptr = unsafe {
+            self.alloc.grow(ptr::NonNull::new_unchecked(self.ptr as *mut u8), old_layout, layout)
+        }.map_err(|_| TryReserveError::AllocError { layout })?;
+
+        let old_cap = self.cap;
+        self.ptr = ptr.cast::<T>().as_ptr();
+        self.cap = capacity;
+
+        let head_len = old_cap - self.lo;
+        if self.len > head_len {
+            let tail_len = self.len - head_len;
+            unsafe {
+                if tail_len < head_len && tail_len <= capacity - old_cap {
+                    ptr::copy_nonoverlapping(self.ptr, self.ptr.add(old_cap), tail_len);
+                } else {
+                    let lo = capacity - head_len;
+                    ptr::copy(self.ptr.add(self.lo), self.ptr.add(lo), head_len);
+                    self.lo = lo;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Double the capacity of the buffer if it is full.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the new capacity overflows a `usize`.
+    #[inline]
+    fn grow_if_full(&mut self) {
+        if self.len == self.cap {
+            if let Err(error) = self.try_grow() {
+                error.handle()
+            }
+        }
+    }
+
+    /// Grow the buffer according to the growth policy.
+    fn try_grow(&mut self) -> Result<(), TryReserveError> {
+        let required = self.len.checked_add(1).ok_or(TryReserveError::CapacityOverflow)?;
+        self.try_resize_buffer(self.growth.grow(self.cap, required))
+    }
+
+    /// Move `count` elements starting at index `src` to index `dst`. The
+    /// source and destination may overlap, and either may wrap around the end
+    /// of the buffer.
+    ///
+    /// The indices are relative to `lo`, and `src + count` and `dst + count`
+    /// must not exceed the capacity. The elements in the source range which
+    /// are not overwritten are left logically uninitialized.
+    unsafe fn copy_elements(&mut self, src: usize, dst: usize, count: usize) {
+        copy_wrapped(self.ptr, self.cap, self.lo, src, dst, count);
+    }
+
+    /// Copy `count` elements from `src` into the free space at the back of
+    /// the ring, which may wrap around the end of the buffer.
+    ///
+    /// There must be room for at least `count` more elements, and `src` must
+    /// not point into the ring.
+    unsafe fn copy_to_back(&mut self, src: *const T, count: usize) {
+        let offset = self.get_back_offset();
+        let len1 = cmp::min(count, self.cap - offset);
+        ptr::copy_nonoverlapping(src, self.ptr.add(offset), len1);
+        ptr::copy_nonoverlapping(src.add(len1), self.ptr, count - len1);
+        self.len += count;
+    }
+
+    /// Remove the first `count` elements from the ring without dropping
+    /// them, after they have been moved out.
+    ///
+    /// `count` must not be greater than the length of the ring.
+    #[cfg_attr(not(feature = "std"), allow(dead_code))]
+    unsafe fn forget_front(&mut self, count: usize) {
+        self.lo = self.get_offset(count);
+        self.len -= count;
+    }
+
+    /// Return the offset of the next back slot
+    #[inline]
+    fn get_back_offset(&self) -> usize {
+        self.get_offset(self.len)
+    }
+
+    /// Return the offset of the next front slot
+    #[inline]
+    fn get_front_offset(&self) -> usize {
+        if self.lo == 0 {
+            self.cap - 1
+        } else {
+            self.lo - 1
+        }
+    }
+
+    /// Return the offset of the given index in the underlying buffer.
+    #[inline]
+    fn get_offset(&self, index: usize) -> usize {
+        wrap_offset(self.lo, self.cap, index)
+    }
+
+    /// Reset the `lo` index to 0. This may require moving elements, but
+    /// never allocates.
+    fn reset(&mut self) {
+        if self.lo == 0 { return }
+
+        self.make_contiguous();
+        if self.lo != 0 {
+            // The buffer does not wrap. Move the elements to the start.
+            //
+            //   lo
+            //    V
+            // +-+-+-+-+-+-+-+
+            // | |x|x|x|x|x| |
+            // +-+-+-+-+-+-+-+
+            unsafe {
+                ptr::copy(self.ptr.add(self.lo),
+                          self.ptr,
+                          self.len);
+            }
+            self.lo = 0;
+        }
+    }
+
+    /// Rotate the ring `n` places to the left by moving the first `n`
+    /// elements to the back.
+    ///
+    /// `n` must not be greater than the length of the ring.
+    unsafe fn rotate_left_inner(&mut self, n: usize) {
+        // Advance `lo` past the first `n` elements, which leaves them at the
+        // (wrapped) indices just before the new `lo`. Then move them into
+        // place after the last element. If the buffer is full they are
+        // already in place.
+        self.lo = self.get_offset(n);
+        self.copy_elements(self.cap - n, self.len - n, n);
+    }
+
+    /// Rotate the ring `n` places to the right by moving the last `n`
+    /// elements to the front.
+    ///
+    /// `n` must not be greater than the length of the ring.
+    unsafe fn rotate_right_inner(&mut self, n: usize) {
+        // Move the last `n` elements into the (wrapped) slots just before
+        // `lo`, then make the first of them the new `lo`. If the buffer is
+        // full they are already in place.
+        self.copy_elements(self.len - n, self.cap - n, n);
+        self.lo = self.get_offset(self.cap - n);
+    }
+}
+
+impl<T: PartialEq, A: Allocator> PartialEq for RingBuf<T, A> {
+    #[inline]
+    fn eq(&self, other: &RingBuf<T, A>) -> bool {
+        self.len == other.len
+            && self.iter().zip(other.iter()).all(|(a, b)| a == b)
+    }
+}
+
+impl<T: PartialOrd, A: Allocator> PartialOrd for RingBuf<T, A> {
+    #[inline]
+    fn partial_cmp(&self, other: &RingBuf<T, A>) -> Option<Ordering> {
+        for (a, b) in self.iter().zip(other.iter()) {
+            let cmp = a.partial_cmp(b);
+            if cmp != Some(Ordering::Equal) {
+                return cmp;
+            }
+        }
+        Some(self.len.cmp(&other.len))
+    }
+}
+
+impl<T: Eq, A: Allocator> Eq for RingBuf<T, A> {}
+
+impl<T: Ord, A: Allocator> Ord for RingBuf<T, A> {
+    #[inline]
+    fn cmp(&self, other: &RingBuf<T, A>) -> Ordering {
+        for (a, b) in self.iter().zip(other.iter()) {
+            let cmp = a.cmp(b);
+            if cmp != Ordering::Equal {
+                return cmp;
+            }
+        }
+        self.len.cmp(&other.len)
+    }
+}
+
+impl<T: Hash, A: Allocator> Hash for RingBuf<T, A> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.len().hash(state);
+        for elt in self.iter() {
+            elt.hash(state);
+        }
+    }
+}
+
+impl<T: fmt::Debug, A: Allocator> fmt::Debug for RingBuf<T, A> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T, A: Allocator> Drop for RingBuf<T, A> {
+    fn drop(&mut self) {
+        unsafe {
+            let (slice1, slice2) = self.as_mut_slices();
+            let (slice1, slice2) = (slice1 as *mut [T], slice2 as *mut [T]);
+            ptr::drop_in_place(slice1);
+            ptr::drop_in_place(slice2);
+            dealloc_buffer(&self.alloc, self.ptr, self.cap);
+        }
+    }
+}
+
+impl<T, A: Allocator> IntoIterator for RingBuf<T, A> {
+    type Item = T;
+    type IntoIter = IntoIter<T, A>;
+
+    /// Creates a consuming iterator, that is, one that moves each
+    /// value out of the ringbuf (from front to back).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use ringbuf::RingBuf;
+    /// let mut rb = RingBuf::new();
+    /// rb.push_back("a".to_string());
+    /// rb.push_back("b".to_string());
+    /// for s in rb.into_iter() {
+    ///     // s has type String, not &String
+    ///     println!("{}", s);
+    /// }
+    /// ```
+    #[inline]
+    fn into_iter(self) -> IntoIter<T, A> {
+        IntoIter { ringbuf: self }
+    }
+}
+
+impl<'a, T, A: Allocator> IntoIterator for &'a RingBuf<T, A> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    #[inline]
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+impl<'a, T, A: Allocator> IntoIterator for &'a mut RingBuf<T, A> {
+    type Item = &'a mut T;
+    type IntoIter = IterMut<'a, T>;
+
+    #[inline]
+    fn into_iter(self) -> IterMut<'a, T> {
+        self.iter_mut()
     }
+}
 
-    /// Resize the `RingBuf` to the specified capacity.
-    ///
-    /// # Failure
-    ///
-    /// Fails if the number of elements in the ring buffer is greater than
-    /// the requested capacity.
-    fn resize(&mut self, capacity: uint) {
-        assert!(capacity >= self.len, "capacity underflow");
-
-        if capacity == self.cap { return }
-        if mem::size_of::<T>() == 0 { return }
+/// RingBuf iterator.
+pub struct Iter<'a, T> {
+    iter1: slice::Iter<'a, T>,
+    iter2: slice::Iter<'a, T>,
+}
 
-        let ptr;
-        unsafe {
-            if capacity == 0 {
-                ptr = 0 as *mut T;
-            } else {
-                let (slice1, slice2) = self.as_slices();
-                ptr = alloc::<T>(capacity) as *mut T;
-                let len1 = slice1.len();
-                ptr::copy_nonoverlapping_memory(ptr, slice1.as_ptr(), len1);
-                ptr::copy_nonoverlapping_memory(ptr.offset(len1 as int),
-                slice2.as_ptr(),
-                slice2.len());
-            }
-            if self.cap != 0 {
-                dealloc(self.ptr, self.cap);
-            }
-        }
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
 
-        self.ptr = ptr;
-        self.cap = capacity;
-        self.lo = 0;
+    #[inline]
+    fn next(&mut self) -> Option<&'a T> {
+        self.iter1.next().or_else(|| self.iter2.next())
     }
 
-    /// Return the offset of the next back slot
     #[inline]
-    fn get_back_offset(&self) -> uint {
-        self.get_offset(self.len)
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.len();
+        (len, Some(len))
     }
+}
 
-    /// Return the offset of the next front slot
+impl<'a, T> DoubleEndedIterator for Iter<'a, T> {
     #[inline]
-    fn get_front_offset(&self) -> uint {
-        if self.lo == 0 {
-            self.cap - 1
-        } else {
-            self.lo - 1
-        }
+    fn next_back(&mut self) -> Option<&'a T> {
+        self.iter2.next_back().or_else(|| self.iter1.next_back())
     }
+}
 
-    /// Return the offset of the given index in the underlying buffer.
+impl<'a, T> ExactSizeIterator for Iter<'a, T> {
     #[inline]
-    fn get_offset(&self, index: uint) -> uint {
-        // The order of these operations preserves numerical stability
-        if self.lo >= self.cap - index {
-            index - (self.cap - self.lo)
-        } else {
-            self.lo + index
-        }
+    fn len(&self) -> usize {
+        self.iter1.len() + self.iter2.len()
     }
+}
 
-    /// Reset the `lo` index to 0. This may require copying and temporary
-    /// allocation.
-    fn reset(&mut self) {
-        if self.lo == 0 { return }
+impl<'a, T> FusedIterator for Iter<'a, T> {}
 
-        // Shift elements to start of buffer
-        {
-            // `slice1` begins at the `lo` index.
-            // `slice2` begins at the `0` index.
-            let (slice1, slice2) = self.as_slices();
-            let len1 = slice1.len();
-            let len2 = slice2.len();
+impl<'a, T> Clone for Iter<'a, T> {
+    fn clone(&self) -> Iter<'a, T> {
+        Iter { iter1: self.iter1.clone(), iter2: self.iter2.clone() }
+    }
+}
 
-            if len1 == 0 {
-                // Nothing to do
-            } if len2 == 0 {
-                // The buffer does not wrap. Move slice1.
-                //
-                //   lo
-                //    V
-                // +-+-+-+-+-+-+-+
-                // | |x|x|x|x|x| |
-                // +-+-+-+-+-+-+-+
-                unsafe {
-                    ptr::copy_memory(self.ptr,
-                                     self.ptr.offset(self.lo as int) as *const T,
-                                     self.len);
-                }
+impl<'a, T: fmt::Debug> fmt::Debug for Iter<'a, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("Iter")
+         .field(&self.iter1.as_slice())
+         .field(&self.iter2.as_slice())
+         .finish()
+    }
+}
 
-            } if len1 <= (self.cap - len1) - len2 {
-                // There is sufficient space to move slice2 without overwriting
-                // slice1.
-                //
-                //           lo
-                //            V
-                // +-+-+-+-+-+-+-+
-                // |x|x|x| | |x|x|
-                // +-+-+-+-+-+-+-+
-                unsafe {
-                    ptr::copy_memory(self.ptr.offset(slice1.len() as int),
-                                     slice2.as_ptr(),
-                                     slice2.len());
-                    ptr::copy_memory(self.ptr,
-                                     slice1.as_ptr(),
-                                     slice1.len());
-                }
-            } else if len1 < len2 {
-                // Copy slice1 and move slice2.
-                //
-                //           lo
-                //            V
-                // +-+-+-+-+-+-+-+
-                // |x|x|x|x| |x|x|
-                // +-+-+-+-+-+-+-+
-                unsafe {
-                    let tmp = alloc(len1);
-                    ptr::copy_nonoverlapping_memory(tmp,
-                                                    slice1.as_ptr(),
-                                                    len1);
-                    ptr::copy_memory(self.ptr.offset(len1 as int),
-                                     slice2.as_ptr(),
-                                     len2);
-                    ptr::copy_nonoverlapping_memory(self.ptr,
-                                                    tmp as *const T,
-                                                    len1);
-                    dealloc(tmp, len1);
-                }
-            } else {
-                // Copy slice2 and move slice1.
-                //
-                //         lo
-                //          V
-                // +-+-+-+-+-+-+-+
-                // |x|x| | |x|x|x|
-                // +-+-+-+-+-+-+-+
-                unsafe {
-                    let tmp = alloc(len2);
-                    ptr::copy_nonoverlapping_memory(tmp,
-                                                    slice2.as_ptr(),
-                                                    len2);
-                    ptr::copy_memory(self.ptr,
-                                     slice1.as_ptr(),
-                                     len1);
-                    ptr::copy_nonoverlapping_memory(self.ptr.offset(len1 as int),
-                    tmp as *const T,
-                    len2);
-                    dealloc(tmp, len2);
-                }
-            }
-        }
-        self.lo = 0;
+/// RingBuf mutable iterator.
+pub struct IterMut<'a, T> {
+    iter1: slice::IterMut<'a, T>,
+    iter2: slice::IterMut<'a, T>,
+}
+
+impl<'a, T> Iterator for IterMut<'a, T> {
+    type Item = &'a mut T;
+
+    #[inline]
+    fn next(&mut self) -> Option<&'a mut T> {
+        self.iter1.next().or_else(|| self.iter2.next())
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.len();
+        (len, Some(len))
     }
 }
 
-impl<T: PartialEq> PartialEq for RingBuf<T> {
+impl<'a, T> DoubleEndedIterator for IterMut<'a, T> {
     #[inline]
-    fn eq(&self, other: &RingBuf<T>) -> bool {
-        self.len == other.len
-            && self.iter().zip(other.iter()).all(|(a, b)| a == b)
+    fn next_back(&mut self) -> Option<&'a mut T> {
+        self.iter2.next_back().or_else(|| self.iter1.next_back())
     }
 }
 
-impl<T: PartialOrd> PartialOrd for RingBuf<T> {
+impl<'a, T> ExactSizeIterator for IterMut<'a, T> {
     #[inline]
-    fn partial_cmp(&self, other: &RingBuf<T>) -> Option<Ordering> {
-        for (a, b) in self.iter().zip(other.iter()) {
-            let cmp = a.partial_cmp(b);
-            if cmp != Some(Equal) {
-                return cmp;
-            }
-        }
-        Some(self.len.cmp(&other.len))
+    fn len(&self) -> usize {
+        self.iter1.len() + self.iter2.len()
     }
 }
 
-impl<T: Eq> Eq for RingBuf<T> {}
+impl<'a, T> FusedIterator for IterMut<'a, T> {}
 
-impl<T: Ord> Ord for RingBuf<T> {
-    #[inline]
-    fn cmp(&self, other: &RingBuf<T>) -> Ordering {
-        for (a, b) in self.iter().zip(other.iter()) {
-            let cmp = a.cmp(b);
-            if cmp != Equal {
-                return cmp;
-            }
-        }
-        self.len.cmp(&other.len)
+impl<'a, T: fmt::Debug> fmt::Debug for IterMut<'a, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("IterMut")
+         .field(&self.iter1.as_slice())
+         .field(&self.iter2.as_slice())
+         .finish()
     }
 }
 
-impl<S: Writer, A: Hash<S>> Hash<S> for RingBuf<A> {
-    fn hash(&self, state: &mut S) {
-        self.len().hash(state);
-        for elt in self.iter() {
-            elt.hash(state);
+/// A draining iterator over a range of a RingBuf.
+///
+/// Created by `RingBuf::drain`.
+pub struct Drain<'a, T, A: Allocator = Global> {
+    /// The ring buffer being drained.
+    ringbuf: *mut RingBuf<T, A>,
+
+    /// The index of the first drained element.
+    drain_start: usize,
+
+    /// The number of drained elements.
+    drain_len: usize,
+
+    /// The length of the ring buffer before the drain.
+    orig_len: usize,
+
+    /// The index of the next element to yield, relative to `drain_start`.
+    idx: usize,
+
+    /// The number of elements which have not been yielded yet.
+    remaining: usize,
+
+    marker: PhantomData<&'a mut RingBuf<T, A>>,
+}
+
+unsafe impl<'a, T: Send, A: Allocator + Send> Send for Drain<'a, T, A> {}
+unsafe impl<'a, T: Sync, A: Allocator + Sync> Sync for Drain<'a, T, A> {}
+
+impl<'a, T, A: Allocator> Drain<'a, T, A> {
+
+    /// Moves the element at index `index` out of the ring buffer.
+    #[inline]
+    unsafe fn read(&self, index: usize) -> T {
+        let ringbuf = &*self.ringbuf;
+        let offset = ringbuf.get_offset(self.drain_start + index);
+        ptr::read(ringbuf.ptr.add(offset))
+    }
+
+    /// Closes the gap left by the drained elements by moving the shorter of
+    /// the head and tail of the ring buffer.
+    unsafe fn close_gap(&mut self) {
+        let ringbuf = &mut *self.ringbuf;
+        let head_len = self.drain_start;
+        let tail_len = self.orig_len - self.drain_start - self.drain_len;
+
+        if head_len < tail_len {
+            // Move the head back to the start of the tail.
+            //
+            //   lo  drain_start
+            //    V   V
+            // +-+-+-+-+-+-+-+-+-+-+
+            // | |h|h| | |t|t|t|t| |
+            // +-+-+-+-+-+-+-+-+-+-+
+            ringbuf.copy_elements(0, self.drain_len, head_len);
+            ringbuf.lo = ringbuf.get_offset(self.drain_len);
+        } else {
+            // Move the tail forward to the end of the head.
+            //
+            //   lo      drain_start
+            //    V       V
+            // +-+-+-+-+-+-+-+-+-+-+
+            // | |h|h|h|h| | |t|t| |
+            // +-+-+-+-+-+-+-+-+-+-+
+            ringbuf.copy_elements(self.drain_start + self.drain_len,
+                                  self.drain_start,
+                                  tail_len);
         }
+        ringbuf.len = self.orig_len - self.drain_len;
     }
 }
 
-impl<T: fmt::Show> fmt::Show for RingBuf<T> {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        try!(write!(f, "["));
+impl<'a, T, A: Allocator> Iterator for Drain<'a, T, A> {
+    type Item = T;
 
-        for (i, e) in self.iter().enumerate() {
-            if i != 0 { try!(write!(f, ", ")); }
-            try!(write!(f, "{}", *e));
-        }
+    #[inline]
+    fn next(&mut self) -> Option<T> {
+        if self.remaining == 0 { return None }
+        let value = unsafe { self.read(self.idx) };
+        self.idx += 1;
+        self.remaining -= 1;
+        Some(value)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
 
-        write!(f, "]")
+impl<'a, T, A: Allocator> DoubleEndedIterator for Drain<'a, T, A> {
+    #[inline]
+    fn next_back(&mut self) -> Option<T> {
+        if self.remaining == 0 { return None }
+        self.remaining -= 1;
+        Some(unsafe { self.read(self.idx + self.remaining) })
     }
 }
 
-#[unsafe_destructor]
-impl<T> Drop for RingBuf<T> {
+impl<'a, T, A: Allocator> ExactSizeIterator for Drain<'a, T, A> {}
+
+impl<'a, T, A: Allocator> FusedIterator for Drain<'a, T, A> {}
+
+impl<'a, T, A: Allocator> Drop for Drain<'a, T, A> {
     fn drop(&mut self) {
-        unsafe {
-            for x in self.iter() {
-                ptr::read(x);
+        /// Finishes the drain even if an element's destructor panics.
+        struct DropGuard<'r, 'a, T, A: Allocator>(&'r mut Drain<'a, T, A>);
+
+        impl<'r, 'a, T, A: Allocator> Drop for DropGuard<'r, 'a, T, A> {
+            fn drop(&mut self) {
+                // Continue dropping the remaining elements if one of their
+                // destructors panicked.
+                for _ in &mut *self.0 {}
+                unsafe { self.0.close_gap(); }
             }
-            dealloc(self.ptr, self.cap);
         }
+
+        let guard = DropGuard(self);
+        for _ in &mut *guard.0 {}
+    }
+}
+
+impl<'a, T, A: Allocator> fmt::Debug for Drain<'a, T, A> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("Drain")
+         .field(&self.drain_len)
+         .field(&self.idx)
+         .field(&self.remaining)
+         .finish()
     }
 }
 
 /// An iterator that moves out of a RingBuf.
-pub struct MoveItems<T> {
-    ringbuf: RingBuf<T>
+#[derive(Clone)]
+pub struct IntoIter<T, A: Allocator = Global> {
+    ringbuf: RingBuf<T, A>
 }
 
-impl<T> Iterator<T> for MoveItems<T> {
+impl<T, A: Allocator> Iterator for IntoIter<T, A> {
+    type Item = T;
+
     #[inline]
     fn next(&mut self) -> Option<T> {
         self.ringbuf.pop_front()
     }
 
     #[inline]
-    fn size_hint(&self) -> (uint, Option<uint>) {
+    fn size_hint(&self) -> (usize, Option<usize>) {
         (self.ringbuf.len(), Some(self.ringbuf.len()))
     }
 }
 
-impl<T> DoubleEndedIterator<T> for MoveItems<T> {
+impl<T, A: Allocator> DoubleEndedIterator for IntoIter<T, A> {
     #[inline]
     fn next_back(&mut self) -> Option<T> {
         self.ringbuf.pop_back()
     }
 }
 
+impl<T, A: Allocator> ExactSizeIterator for IntoIter<T, A> {}
+
+impl<T, A: Allocator> FusedIterator for IntoIter<T, A> {}
+
+impl<T: fmt::Debug, A: Allocator> fmt::Debug for IntoIter<T, A> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("IntoIter").field(&self.ringbuf).finish()
+    }
+}
+
 #[cfg(test)]
 mod checks {
-    use std::collections::Deque;
+    use std::alloc::Layout;
+    use std::cell::Cell;
     use std::iter::FromIterator;
-    use std::rand::Rand;
+    use std::ptr::NonNull;
+    use std::rc::Rc;
 
     use quickcheck::Arbitrary;
     use quickcheck::Gen;
-    use quickcheck::Shrinker;
     use quickcheck::quickcheck;
 
-    use super::RingBuf;
+    use super::{AllocError, Allocator, Global, RingBuf, TryReserveError};
 
     /// Creates a new ringbuf with a provided initial capacity and offset, and
     /// copied elements from the provided slice. This is a convenience for
     /// creating a ringbuf with a `lo` offset other than the default buffer start.
     fn create_ringbuf_with_offset<T: Copy>(items: &[T],
-                                           capacity: uint,
-                                           lo: uint)
+                                           capacity: usize,
+                                           lo: usize)
                                            -> RingBuf<T> {
         let mut ringbuf = RingBuf::with_capacity(capacity);
         ringbuf.lo = if capacity == 0 { 0 } else { lo % capacity };
@@ -978,16 +2460,19 @@ mod checks {
     }
 
     impl<A: Copy + Arbitrary> Arbitrary for RingBuf<A> {
-        fn arbitrary<G: Gen>(g: &mut G) -> RingBuf<A> {
+        fn arbitrary(g: &mut Gen) -> RingBuf<A> {
             let vec: Vec<A> = Arbitrary::arbitrary(g);
-            let cap = vec.capacity();
-            let lo = if cap == 0 { 0 } else { g.gen_range(0, cap) };
+            // Leave a random amount of free space so that the elements don't
+            // always fill the whole buffer.
+            let cap = vec.len() + usize::arbitrary(g) % (vec.len() + 1);
+            let lo = if cap == 0 { 0 } else { usize::arbitrary(g) % cap };
 
-            create_ringbuf_with_offset(vec.as_slice(), cap, lo)
+            create_ringbuf_with_offset(&vec, cap, lo)
         }
 
-        fn shrink(&self) -> Box<Shrinker<RingBuf<A>>> {
+        fn shrink(&self) -> Box<dyn Iterator<Item = RingBuf<A>>> {
             let mut xs: Vec<RingBuf<A>> = vec![];
+            let cap = self.cap;
 
             // Add versions with varying offsets
             let mut lo = self.lo;
@@ -995,66 +2480,62 @@ mod checks {
                 let shrinks = self.clone()
                                   .into_vec()
                                   .shrink()
-                                  .map(|x| create_ringbuf_with_offset(x.as_slice(),
-                                                                      self.cap,
-                                                                      lo))
-                                  .collect();
-                xs.push_all_move(shrinks);
-                lo = lo / 2;
+                                  .map(move |x| create_ringbuf_with_offset(&x, cap, lo));
+                xs.extend(shrinks);
+                lo /= 2;
             }
 
             // Add version with 0 offset
             let shrinks = self.clone()
                               .into_vec()
                               .shrink()
-                              .map(|x| RingBuf::from_vec(x))
-                              .collect();
-            xs.push_all_move(shrinks);
-            box xs.move_iter() as Box<Shrinker<RingBuf<A>>>
+                              .map(RingBuf::from_vec);
+            xs.extend(shrinks);
+            Box::new(xs.into_iter())
         }
     }
 
     #[test]
     fn check_vec_bijection() {
-        fn prop(rb: RingBuf<int>) -> bool {
+        fn prop(rb: RingBuf<i32>) -> bool {
             rb == RingBuf::from_vec(rb.clone().into_vec())
         }
 
-        quickcheck(prop);
+        quickcheck(prop as fn(RingBuf<i32>) -> bool);
     }
 
     #[test]
     fn check_iter_bijection() {
-        fn prop(rb: RingBuf<int>) -> bool {
-            rb == FromIterator::from_iter(rb.clone().move_iter())
+        fn prop(rb: RingBuf<i32>) -> bool {
+            rb == FromIterator::from_iter(rb.clone())
         }
 
-        quickcheck(prop);
+        quickcheck(prop as fn(RingBuf<i32>) -> bool);
     }
 
     #[test]
     fn check_clone_equivalence() {
-        fn prop(rb: RingBuf<int>) -> bool {
+        fn prop(rb: RingBuf<i32>) -> bool {
             rb == rb.clone()
         }
 
-        quickcheck(prop);
+        quickcheck(prop as fn(RingBuf<i32>) -> bool);
     }
 
     #[test]
     fn test_shrink_to_fit_equivalence() {
-        fn prop(rb: RingBuf<int>) -> bool {
+        fn prop(rb: RingBuf<i32>) -> bool {
             let mut stf = rb.clone();
             stf.shrink_to_fit();
             rb == stf
         }
 
-        quickcheck(prop);
+        quickcheck(prop as fn(RingBuf<i32>) -> bool);
     }
 
     #[test]
     fn check_back_push_pop_get() {
-        fn prop(rb: RingBuf<int>, item: int) -> bool {
+        fn prop(rb: RingBuf<i32>, item: i32) -> bool {
             let mut copy = rb.clone();
             copy.push_back(item);
 
@@ -1062,12 +2543,12 @@ mod checks {
                 && copy.pop_back() == Some(item)
                 && copy == rb
         }
-        quickcheck(prop);
+        quickcheck(prop as fn(RingBuf<i32>, i32) -> bool);
     }
 
     #[test]
     fn check_front_push_pop_get() {
-        fn prop(rb: RingBuf<int>, item: int) -> bool {
+        fn prop(rb: RingBuf<i32>, item: i32) -> bool {
             let mut copy = rb.clone();
             copy.push_front(item);
 
@@ -1075,91 +2556,442 @@ mod checks {
                 && copy.pop_front() == Some(item)
                 && copy == rb
         }
-        quickcheck(prop);
+        quickcheck(prop as fn(RingBuf<i32>, i32) -> bool);
     }
 
     #[test]
     fn check_get() {
-        fn prop(rb: RingBuf<int>) -> bool {
+        fn prop(rb: RingBuf<i32>) -> bool {
             let vec = rb.clone().into_vec();
             rb.len() == vec.len()
-                && rb.iter().zip(vec.iter()).all(|(a, b)| a == b)
+                && (0..vec.len()).all(|i| rb.get(i) == vec.get(i))
+                && rb.get(vec.len()).is_none()
         }
-        quickcheck(prop);
+        quickcheck(prop as fn(RingBuf<i32>) -> bool);
     }
 
     #[test]
     fn check_cmp() {
-        fn prop(rb1: RingBuf<int>, rb2: RingBuf<int>) -> bool {
+        fn prop(rb1: RingBuf<i32>, rb2: RingBuf<i32>) -> bool {
             let vec1 = rb1.clone().into_vec();
             let vec2 = rb2.clone().into_vec();
 
             rb1.cmp(&rb2) == vec1.cmp(&vec2)
         }
-        quickcheck(prop);
+        quickcheck(prop as fn(RingBuf<i32>, RingBuf<i32>) -> bool);
     }
 
     #[test]
     fn check_extendable() {
-        fn prop(vec: Vec<int>) -> bool {
+        fn prop(vec: Vec<i32>) -> bool {
             let mut rb = RingBuf::new();
-            rb.extend(vec.clone().move_iter());
+            rb.extend(vec.clone());
             vec == rb.into_vec()
         }
-        quickcheck(prop);
+        quickcheck(prop as fn(Vec<i32>) -> bool);
     }
 
     #[test]
     fn check_iter() {
-        fn prop(rb: RingBuf<int>) -> bool {
-            rb.clone().iter().zip(rb.into_vec().iter()).all(|(a, b)| a == b)
+        fn prop(rb: RingBuf<i32>) -> bool {
+            rb.clone().iter().eq(rb.into_vec().iter())
+        }
+
+        quickcheck(prop as fn(RingBuf<i32>) -> bool);
+    }
+
+    #[test]
+    fn check_iter_rev() {
+        fn prop(rb: RingBuf<i32>) -> bool {
+            rb.iter().len() == rb.len()
+                && rb.clone().iter().rev().eq(rb.into_vec().iter().rev())
+        }
+
+        quickcheck(prop as fn(RingBuf<i32>) -> bool);
+    }
+
+    #[test]
+    fn check_iter_mut() {
+        fn prop(rb: RingBuf<i32>) -> bool {
+            rb.clone().iter_mut().eq(rb.into_vec().iter_mut())
         }
 
-        quickcheck(prop);
+        quickcheck(prop as fn(RingBuf<i32>) -> bool);
     }
 
     #[test]
-    fn check_mut_iter() {
-        fn prop(rb: RingBuf<int>) -> bool {
-            rb.clone().mut_iter().zip(rb.into_vec().mut_iter()).all(|(a, b)| a == b)
+    fn check_into_iter() {
+        fn prop(rb: RingBuf<i32>) -> bool {
+            rb.clone().into_iter().eq(rb.into_vec())
         }
 
-        quickcheck(prop);
+        quickcheck(prop as fn(RingBuf<i32>) -> bool);
     }
 
     #[test]
-    fn check_move_iter() {
-        fn prop(rb: RingBuf<int>) -> bool {
-            rb.clone().move_iter().zip(rb.into_vec().move_iter()).all(|(a, b)| a == b)
+    fn check_range() {
+        fn prop(rb: RingBuf<i32>, a: usize, b: usize) -> bool {
+            let vec = rb.clone().into_vec();
+            let (start, end) = if vec.is_empty() {
+                (0, 0)
+            } else {
+                let (a, b) = (a % (vec.len() + 1), b % (vec.len() + 1));
+                (a.min(b), a.max(b))
+            };
+            rb.range(start..end).eq(vec[start..end].iter())
         }
 
-        quickcheck(prop);
+        quickcheck(prop as fn(RingBuf<i32>, usize, usize) -> bool);
     }
 
     #[test]
     fn check_truncate() {
-        fn prop(mut rb: RingBuf<int>, len: uint) -> bool {
+        fn prop(mut rb: RingBuf<i32>, len: usize) -> bool {
             let mut vec = rb.clone().into_vec();
             vec.truncate(len);
             rb.truncate(len);
             RingBuf::from_vec(vec) == rb
         }
 
-        quickcheck(prop);
+        quickcheck(prop as fn(RingBuf<i32>, usize) -> bool);
+    }
+
+    #[test]
+    fn check_insert() {
+        fn prop(mut rb: RingBuf<i32>, index: usize, item: i32) -> bool {
+            let mut vec = rb.clone().into_vec();
+            let index = index % (vec.len() + 1);
+            vec.insert(index, item);
+            rb.insert(index, item);
+            RingBuf::from_vec(vec) == rb
+        }
+
+        quickcheck(prop as fn(RingBuf<i32>, usize, i32) -> bool);
+    }
+
+    #[test]
+    fn check_remove() {
+        fn prop(mut rb: RingBuf<i32>, index: usize) -> bool {
+            let mut vec = rb.clone().into_vec();
+            if vec.is_empty() {
+                return rb.remove(index).is_none();
+            }
+            let index = index % vec.len();
+            vec.remove(index) == rb.remove(index).unwrap()
+                && RingBuf::from_vec(vec) == rb
+        }
+
+        quickcheck(prop as fn(RingBuf<i32>, usize) -> bool);
+    }
+
+    #[test]
+    fn check_retain() {
+        fn prop(mut rb: RingBuf<i32>) -> bool {
+            let mut vec = rb.clone().into_vec();
+            vec.retain(|&x| x % 3 != 0);
+            rb.retain(|&x| x % 3 != 0);
+            RingBuf::from_vec(vec) == rb
+        }
+
+        quickcheck(prop as fn(RingBuf<i32>) -> bool);
+    }
+
+    #[test]
+    fn check_binary_search() {
+        fn prop(mut vec: Vec<i32>, lo: usize, item: i32) -> bool {
+            vec.sort();
+            vec.dedup();
+            let rb = create_ringbuf_with_offset(&vec, vec.len(), lo);
+            rb.binary_search(&item) == vec.binary_search(&item)
+                && rb.partition_point(|&x| x < item) == vec.partition_point(|&x| x < item)
+        }
+
+        quickcheck(prop as fn(Vec<i32>, usize, i32) -> bool);
+    }
+
+    #[test]
+    fn check_split_off() {
+        fn prop(mut rb: RingBuf<i32>, at: usize) -> bool {
+            let mut vec = rb.clone().into_vec();
+            let at = at % (vec.len() + 1);
+            let cap = rb.capacity();
+            let other = rb.split_off(at);
+            let other_vec = vec.split_off(at);
+            rb.capacity() == cap
+                && RingBuf::from_vec(vec) == rb
+                && RingBuf::from_vec(other_vec) == other
+        }
+
+        quickcheck(prop as fn(RingBuf<i32>, usize) -> bool);
+    }
+
+    #[test]
+    fn check_append() {
+        fn prop(mut rb1: RingBuf<i32>, mut rb2: RingBuf<i32>) -> bool {
+            let mut vec1 = rb1.clone().into_vec();
+            let mut vec2 = rb2.clone().into_vec();
+            rb1.append(&mut rb2);
+            vec1.append(&mut vec2);
+            rb2.is_empty() && RingBuf::from_vec(vec1) == rb1
+        }
+
+        quickcheck(prop as fn(RingBuf<i32>, RingBuf<i32>) -> bool);
+    }
+
+    #[test]
+    fn check_make_contiguous() {
+        fn prop(mut rb: RingBuf<i32>) -> bool {
+            let vec = rb.clone().into_vec();
+            let cap = rb.capacity();
+            rb.make_contiguous() == &vec[..]
+                && rb.as_slices().1.is_empty()
+                && rb.capacity() == cap
+        }
+
+        quickcheck(prop as fn(RingBuf<i32>) -> bool);
+    }
+
+    #[test]
+    fn check_rotate() {
+        fn prop(mut rb: RingBuf<i32>, n: usize, left: bool) -> bool {
+            let mut vec = rb.clone().into_vec();
+            let n = n % (vec.len() + 1);
+            if left {
+                vec.rotate_left(n);
+                rb.rotate_left(n);
+            } else {
+                vec.rotate_right(n);
+                rb.rotate_right(n);
+            }
+            RingBuf::from_vec(vec) == rb
+        }
+
+        quickcheck(prop as fn(RingBuf<i32>, usize, bool) -> bool);
+    }
+
+    #[test]
+    fn check_try_push() {
+        fn prop(rb: RingBuf<i32>, item: i32) -> bool {
+            let mut back = rb.clone();
+            let mut front = rb.clone();
+            let vec = rb.into_vec();
+            back.try_push_back(item).is_ok()
+                && front.try_push_front(item).is_ok()
+                && back.pop_back() == Some(item)
+                && front.pop_front() == Some(item)
+                && back.into_vec() == vec
+                && front.into_vec() == vec
+        }
+
+        quickcheck(prop as fn(RingBuf<i32>, i32) -> bool);
+    }
+
+    #[test]
+    fn check_grow_in_place() {
+        // Growing keeps the elements in order, whichever part of a wrapped
+        // ring is moved, and leaves a contiguous ring contiguous.
+        fn prop(cap: u8, lo: u8, len: u8, extra: u8) -> bool {
+            let cap = cap as usize % 16 + 1;
+            let lo = lo as usize % cap;
+            let len = len as usize % (cap + 1);
+            let mut rb = RingBuf::with_capacity(cap);
+            rb.extend(0..lo);
+            while rb.pop_front().is_some() {}
+            rb.extend(0..len);
+
+            let contiguous = rb.as_slices().1.is_empty();
+            rb.reserve_exact(cap - len + 1 + extra as usize % 16);
+            rb.capacity() > cap
+                && rb.iter().cloned().eq(0..len)
+                && (!contiguous || rb.as_slices().1.is_empty())
+        }
+
+        quickcheck(prop as fn(u8, u8, u8, u8) -> bool);
+    }
+
+    #[test]
+    fn test_try_reserve_overflow() {
+        let mut rb = RingBuf::from_vec(vec![1u64, 2, 3]);
+        assert_eq!(rb.try_reserve(usize::MAX), Err(TryReserveError::CapacityOverflow));
+        assert_eq!(rb.try_reserve_exact(usize::MAX / 4), Err(TryReserveError::CapacityOverflow));
+        assert_eq!(rb.capacity(), 3);
+        assert_eq!(rb.into_vec(), vec![1, 2, 3]);
+
+        let mut rb = RingBuf::new();
+        rb.push_back(());
+        match rb.try_reserve(usize::MAX) {
+            Err(TryReserveError::CapacityOverflow) => (),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn check_spare_capacity() {
+        fn prop(mut rb: RingBuf<i32>, extra: u8, back: usize, front: usize) -> bool {
+            rb.reserve(extra as usize % 8);
+            let mut vec = rb.clone().into_vec();
+
+            let (slice1, slice2) = rb.spare_capacity_mut();
+            let back = back % (slice1.len() + slice2.len() + 1);
+            for (i, slot) in slice1.iter_mut().chain(slice2).take(back).enumerate() {
+                slot.write(1000 + i as i32);
+            }
+            unsafe { rb.commit_back(back); }
+            vec.extend((0..back).map(|i| 1000 + i as i32));
+
+            let (slice1, slice2) = rb.spare_front_mut();
+            let front = front % (slice1.len() + slice2.len() + 1);
+            for (i, slot) in slice1.iter_mut().chain(slice2).rev().take(front).enumerate() {
+                slot.write(-1 - i as i32);
+            }
+            unsafe { rb.commit_front(front); }
+            vec.splice(0..0, (0..front).rev().map(|i| -1 - i as i32));
+
+            rb.into_vec() == vec
+        }
+
+        quickcheck(prop as fn(RingBuf<i32>, u8, usize, usize) -> bool);
+    }
+
+    #[test]
+    fn check_drain() {
+        fn prop(mut rb: RingBuf<i32>, a: usize, b: usize, taken: usize) -> bool {
+            let mut vec = rb.clone().into_vec();
+            let (a, b) = (a % (vec.len() + 1), b % (vec.len() + 1));
+            let (start, end) = (a.min(b), a.max(b));
+
+            // Only consume part of the drain, the rest is dropped with it.
+            let taken = taken % (end - start + 1);
+            let drained: Vec<i32> = rb.drain(start..end).take(taken).collect();
+            let expected: Vec<i32> = vec.drain(start..end).take(taken).collect();
+            drained == expected && RingBuf::from_vec(vec) == rb
+        }
+
+        quickcheck(prop as fn(RingBuf<i32>, usize, usize, usize) -> bool);
+    }
+
+    #[test]
+    fn check_drain_rev() {
+        fn prop(mut rb: RingBuf<i32>, a: usize, b: usize) -> bool {
+            let mut vec = rb.clone().into_vec();
+            let (a, b) = (a % (vec.len() + 1), b % (vec.len() + 1));
+            let (start, end) = (a.min(b), a.max(b));
+
+            rb.drain(start..end).rev().eq(vec.drain(start..end).rev())
+                && RingBuf::from_vec(vec) == rb
+        }
+
+        quickcheck(prop as fn(RingBuf<i32>, usize, usize) -> bool);
+    }
+
+    #[test]
+    fn test_drain_panic_safety() {
+        use std::cell::Cell;
+        use std::panic::{self, AssertUnwindSafe};
+        use std::rc::Rc;
+
+        /// Counts drops, and panics when dropped if `panics` is set.
+        struct D(Rc<Cell<usize>>, bool);
+
+        impl Drop for D {
+            fn drop(&mut self) {
+                self.0.set(self.0.get() + 1);
+                if self.1 { panic!("panic in drop"); }
+            }
+        }
+
+        let drops = Rc::new(Cell::new(0));
+        let mut rb = RingBuf::with_capacity(8);
+        for i in 0..8 {
+            rb.push_back(D(drops.clone(), i == 4));
+        }
+        // Wrap the ring around the end of the buffer.
+        for _ in 0..3 { rb.pop_front(); }
+        for _ in 0..3 { rb.push_back(D(drops.clone(), false)); }
+        drops.set(0);
+
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            rb.drain(1..6);
+        }));
+        assert!(result.is_err());
+        assert_eq!(drops.get(), 5);
+        assert_eq!(rb.len(), 3);
+
+        for d in rb.iter_mut() { d.1 = false; }
+        drop(rb);
+        assert_eq!(drops.get(), 8);
     }
 
     #[test]
     fn check_drop_items() {
         // This is testing memory safety
-        fn prop(vec: Vec<int>) -> bool {
+        fn prop(vec: Vec<i32>) -> bool {
             let len = vec.len();
-            let mut rb: RingBuf<Box<int>> = RingBuf::new();
-            for item in vec.move_iter() {
-                rb.push_back(box item);
+            let mut rb: RingBuf<Box<i32>> = RingBuf::new();
+            for item in vec.into_iter() {
+                rb.push_back(Box::new(item));
             }
             rb.len() == len
         }
 
-        quickcheck(prop);
+        quickcheck(prop as fn(Vec<i32>) -> bool);
+    }
+
+    #[test]
+    fn check_zero_sized() {
+        fn prop(pushes: u8, pops: u8) -> bool {
+            let mut rb = RingBuf::new();
+            for _ in 0..pushes { rb.push_back(()); }
+            for _ in 0..pops { rb.pop_front(); }
+            rb.len() == (pushes as usize).saturating_sub(pops as usize)
+                && rb.iter().count() == rb.len()
+        }
+
+        quickcheck(prop as fn(u8, u8) -> bool);
+    }
+
+    /// An allocator which counts the bytes it has handed out, and fails once
+    /// the count would exceed its limit.
+    #[derive(Clone)]
+    struct CountingAlloc {
+        allocated: Rc<Cell<usize>>,
+        limit: usize,
+    }
+
+    unsafe impl Allocator for CountingAlloc {
+        fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+            let allocated = self.allocated.get() + layout.size();
+            if allocated > self.limit { return Err(AllocError) }
+            self.allocated.set(allocated);
+            Global.allocate(layout)
+        }
+
+        unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+            self.allocated.set(self.allocated.get() - layout.size());
+            Global.deallocate(ptr, layout)
+        }
+    }
+
+    #[test]
+    fn test_allocator() {
+        let alloc = CountingAlloc { allocated: Rc::new(Cell::new(0)), limit: 1024 };
+        let mut rb: RingBuf<u32, _> = RingBuf::with_capacity_in(2, alloc.clone());
+        assert_eq!(alloc.allocated.get(), 8);
+
+        rb.extend([1, 2, 3]);
+        assert_eq!(alloc.allocated.get(), 4 * rb.capacity());
+        let other = rb.split_off(1);
+        let clone = other.clone();
+        assert!(clone.iter().eq(&[2, 3]));
+        assert_eq!(alloc.allocated.get(), 4 * (rb.capacity() + 2 * other.capacity()));
+
+        match rb.try_reserve_exact(1024) {
+            Err(TryReserveError::AllocError { .. }) => (),
+            other => panic!("unexpected result: {:?}", other),
+        }
+        assert!(rb.iter().eq(&[1]));
+
+        drop((rb, other, clone));
+        assert_eq!(alloc.allocated.get(), 0);
     }
 }
@@ -0,0 +1,403 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+//
+
+//! A ring buffer which addresses its elements by sequence number.
+
+use core::fmt;
+use core::iter::FromIterator;
+use core::ops::{Bound, Deref, RangeBounds};
+
+use super::{IntoIter, Iter, IterMut, RingBuf, SeqError};
+
+/// A queue which gives every element pushed onto it the next of a
+/// monotonically increasing series of `u64` sequence numbers.
+///
+/// Elements are pushed onto the back and popped (evicted) from the front,
+/// like a log, and can be looked up by sequence number in constant time
+/// with `get_seq` and `range_seq`. A sequence number always refers to the
+/// same element, and lookups report whether a missing element has already
+/// been evicted or is yet to be pushed. All of the read-only methods of
+/// `RingBuf`, which index from the front, are available through `Deref`.
+///
+/// # Example
+///
+/// ```rust
+/// # use ringbuf::{SeqError, SeqRingBuf};
+/// let mut log = SeqRingBuf::new();
+/// assert_eq!(log.push_back("a"), 0);
+/// assert_eq!(log.push_back("b"), 1);
+/// assert_eq!(log.push_back("c"), 2);
+///
+/// log.pop_front();
+/// assert_eq!(log.get_seq(0), Err(SeqError::Evicted));
+/// assert_eq!(log.get_seq(1), Ok(&"b"));
+/// assert_eq!(log.get_seq(3), Err(SeqError::Pending));
+/// assert!(log.range_seq(1..).unwrap().eq(&["b", "c"]));
+/// ```
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SeqRingBuf<T> {
+
+    /// The elements, in sequence order.
+    ringbuf: RingBuf<T>,
+
+    /// The sequence number of the front element, or of the next element to
+    /// be pushed if the ring is empty.
+    first: u64,
+}
+
+impl<T> SeqRingBuf<T> {
+
+    /// Constructs a new, empty `SeqRingBuf` whose first element will have
+    /// sequence number 0.
+    ///
+    /// The ring buffer will not allocate until elements are pushed onto it.
+    pub const fn new() -> SeqRingBuf<T> {
+        SeqRingBuf::starting_at(0)
+    }
+
+    /// Constructs a new, empty `SeqRingBuf` whose first element will have
+    /// sequence number `seq`, for example to resume a log.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use ringbuf::SeqRingBuf;
+    /// let mut log = SeqRingBuf::starting_at(100);
+    /// assert_eq!(log.push_back('a'), 100);
+    /// ```
+    pub const fn starting_at(seq: u64) -> SeqRingBuf<T> {
+        SeqRingBuf { ringbuf: RingBuf::new(), first: seq }
+    }
+
+    /// Constructs a new, empty `SeqRingBuf` with the specified capacity,
+    /// whose first element will have sequence number 0.
+    pub fn with_capacity(capacity: usize) -> SeqRingBuf<T> {
+        SeqRingBuf { ringbuf: RingBuf::with_capacity(capacity), first: 0 }
+    }
+
+    /// Constructs a new `SeqRingBuf` from the elements of a `RingBuf`, the
+    /// first of which gets sequence number `seq`. No copying will be done.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the sequence numbers of the elements overflow a `u64`.
+    pub fn from_ringbuf(ringbuf: RingBuf<T>, seq: u64) -> SeqRingBuf<T> {
+        assert!(seq.checked_add(ringbuf.len() as u64).is_some(), "sequence number overflow");
+        SeqRingBuf { ringbuf, first: seq }
+    }
+
+    /// Converts the `SeqRingBuf` into a `RingBuf` holding the same elements,
+    /// discarding their sequence numbers. No copying will be done.
+    pub fn into_ringbuf(self) -> RingBuf<T> {
+        self.ringbuf
+    }
+
+    /// Returns the sequence number of the front element, or of the next
+    /// element to be pushed if the ring is empty. Every lower sequence
+    /// number has been evicted.
+    #[inline]
+    pub fn first_seq(&self) -> u64 {
+        self.first
+    }
+
+    /// Returns the sequence number the next pushed element will get. Every
+    /// sequence number from it on is pending.
+    #[inline]
+    pub fn next_seq(&self) -> u64 {
+        self.first + self.ringbuf.len() as u64
+    }
+
+    /// Returns a reference to the element with sequence number `seq`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use ringbuf::{SeqError, SeqRingBuf};
+    /// let mut log = SeqRingBuf::starting_at(10);
+    /// log.push_back('a');
+    /// assert_eq!(log.get_seq(10), Ok(&'a'));
+    /// assert_eq!(log.get_seq(9), Err(SeqError::Evicted));
+    /// assert_eq!(log.get_seq(11), Err(SeqError::Pending));
+    /// ```
+    #[inline]
+    pub fn get_seq(&self, seq: u64) -> Result<&T, SeqError> {
+        let index = self.index_of(seq)?;
+        Ok(&self.ringbuf[index])
+    }
+
+    /// Returns a mutable reference to the element with sequence number `seq`.
+    #[inline]
+    pub fn get_seq_mut(&mut self, seq: u64) -> Result<&mut T, SeqError> {
+        let index = self.index_of(seq)?;
+        Ok(&mut self.ringbuf[index])
+    }
+
+    /// Returns an iterator over the elements with sequence numbers in the
+    /// given range. An unbounded start or end is taken to be the first or
+    /// the next sequence number.
+    ///
+    /// Returns `SeqError::Evicted` if any part of the range has been evicted,
+    /// and otherwise `SeqError::Pending` if any part has not been pushed yet.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the start of the range is greater than the end.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use ringbuf::{SeqError, SeqRingBuf};
+    /// let mut log: SeqRingBuf<u32> = (0..5).collect();
+    /// log.evict_before(2);
+    /// assert!(log.range_seq(2..4).unwrap().eq(&[2, 3]));
+    /// assert!(log.range_seq(..).unwrap().eq(&[2, 3, 4]));
+    /// assert!(log.range_seq(5..).unwrap().eq(&[]));
+    /// assert_eq!(log.range_seq(1..4).err(), Some(SeqError::Evicted));
+    /// assert_eq!(log.range_seq(3..=5).err(), Some(SeqError::Pending));
+    /// ```
+    pub fn range_seq<R>(&self, range: R) -> Result<Iter<'_, T>, SeqError>
+            where R: RangeBounds<u64> {
+        let start = match range.start_bound() {
+            Bound::Included(&start) => start,
+            Bound::Excluded(&start) => start.checked_add(1).ok_or(SeqError::Pending)?,
+            Bound::Unbounded => self.first,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(&end) => end.checked_add(1).ok_or(SeqError::Pending)?,
+            Bound::Excluded(&end) => end,
+            Bound::Unbounded => self.next_seq(),
+        };
+        if start < self.first || end < self.first { return Err(SeqError::Evicted) }
+        if start > self.next_seq() || end > self.next_seq() { return Err(SeqError::Pending) }
+        assert!(start <= end, "range start is greater than range end");
+        Ok(self.ringbuf.range((start - self.first) as usize..(end - self.first) as usize))
+    }
+
+    /// Appends an element to the ring and returns its sequence number.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the sequence number overflows a `u64`.
+    pub fn push_back(&mut self, value: T) -> u64 {
+        let seq = self.next_seq();
+        assert!(seq < u64::MAX, "sequence number overflow");
+        self.ringbuf.push_back(value);
+        seq
+    }
+
+    /// Removes the front element and returns it, or `None` if the ring is
+    /// empty. Its sequence number, `first_seq` before the call, is evicted.
+    #[inline]
+    pub fn pop_front(&mut self) -> Option<T> {
+        let value = self.ringbuf.pop_front()?;
+        self.first += 1;
+        Some(value)
+    }
+
+    /// Evicts every element with a sequence number below `seq`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use ringbuf::SeqRingBuf;
+    /// let mut log: SeqRingBuf<char> = "abcd".chars().collect();
+    /// log.evict_before(2);
+    /// assert_eq!(log.first_seq(), 2);
+    /// assert_eq!(log.front(), Some(&'c'));
+    /// ```
+    pub fn evict_before(&mut self, seq: u64) {
+        while self.first < seq && self.pop_front().is_some() {}
+    }
+
+    /// Evicts every element. The next pushed element keeps the next sequence
+    /// number.
+    pub fn clear(&mut self) {
+        self.first = self.next_seq();
+        self.ringbuf.clear();
+    }
+
+    /// Return a mutable reference to the first element.
+    #[inline]
+    pub fn front_mut(&mut self) -> Option<&mut T> {
+        self.ringbuf.front_mut()
+    }
+
+    /// Return a mutable reference to the last element.
+    #[inline]
+    pub fn back_mut(&mut self) -> Option<&mut T> {
+        self.ringbuf.back_mut()
+    }
+
+    /// Returns an iterator over mutable references to the elements of the
+    /// ring in order.
+    #[inline]
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        self.ringbuf.iter_mut()
+    }
+
+    /// Reserves capacity for at least `additional` more elements.
+    #[inline]
+    pub fn reserve(&mut self, additional: usize) {
+        self.ringbuf.reserve(additional)
+    }
+
+    /// Returns the index from the front of the element with sequence number
+    /// `seq`.
+    #[inline]
+    fn index_of(&self, seq: u64) -> Result<usize, SeqError> {
+        if seq < self.first { return Err(SeqError::Evicted) }
+        if seq >= self.next_seq() { return Err(SeqError::Pending) }
+        Ok((seq - self.first) as usize)
+    }
+}
+
+impl<T> Default for SeqRingBuf<T> {
+    #[inline]
+    fn default() -> SeqRingBuf<T> { SeqRingBuf::new() }
+}
+
+impl<T> Deref for SeqRingBuf<T> {
+    type Target = RingBuf<T>;
+
+    #[inline]
+    fn deref(&self) -> &RingBuf<T> {
+        &self.ringbuf
+    }
+}
+
+impl<T> FromIterator<T> for SeqRingBuf<T> {
+    /// Collects the elements, numbering them from 0.
+    fn from_iter<I: IntoIterator<Item = T>>(iterator: I) -> SeqRingBuf<T> {
+        SeqRingBuf::from_ringbuf(iterator.into_iter().collect(), 0)
+    }
+}
+
+impl<T> Extend<T> for SeqRingBuf<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iterator: I) {
+        for element in iterator {
+            self.push_back(element);
+        }
+    }
+}
+
+impl<T> IntoIterator for SeqRingBuf<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    #[inline]
+    fn into_iter(self) -> IntoIter<T> {
+        self.ringbuf.into_iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a SeqRingBuf<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    #[inline]
+    fn into_iter(self) -> Iter<'a, T> {
+        self.ringbuf.iter()
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for SeqRingBuf<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_map().entries((self.first..).zip(self.ringbuf.iter())).finish()
+    }
+}
+
+#[cfg(test)]
+mod checks {
+    use std::collections::VecDeque;
+
+    use quickcheck::quickcheck;
+
+    use super::SeqRingBuf;
+    use crate::SeqError;
+
+    #[test]
+    fn check_model() {
+        // The model is a `VecDeque` and the sequence number of its front
+        // element. Each op is applied to both, then every lookup is checked
+        // over a window of sequence numbers around the ring.
+        fn prop(ops: Vec<(u8, u8)>, first: u16) -> bool {
+            let mut ring = SeqRingBuf::starting_at(first as u64);
+            let mut model = VecDeque::new();
+            let mut model_first = first as u64;
+
+            for (op, n) in ops {
+                match op % 4 {
+                    0 | 1 => {
+                        if ring.push_back(n) != model_first + model.len() as u64 { return false }
+                        model.push_back(n);
+                    }
+                    2 => {
+                        let popped = model.pop_front();
+                        if ring.pop_front() != popped { return false }
+                        if popped.is_some() { model_first += 1; }
+                    }
+                    _ => {
+                        let seq = model_first + n as u64 % 4;
+                        ring.evict_before(seq);
+                        while model_first < seq && model.pop_front().is_some() {
+                            model_first += 1;
+                        }
+                    }
+                }
+
+                let next = model_first + model.len() as u64;
+                if ring.first_seq() != model_first || ring.next_seq() != next { return false }
+                for seq in model_first.saturating_sub(2)..next + 2 {
+                    let expected = if seq < model_first {
+                        Err(SeqError::Evicted)
+                    } else if seq >= next {
+                        Err(SeqError::Pending)
+                    } else {
+                        Ok(&model[(seq - model_first) as usize])
+                    };
+                    if ring.get_seq(seq) != expected { return false }
+                    if let Ok(iter) = ring.range_seq(seq..) {
+                        if seq < model_first || !iter.eq(model.range((seq - model_first) as usize..)) {
+                            return false;
+                        }
+                    } else if seq >= model_first && seq <= next {
+                        return false;
+                    }
+                }
+            }
+            true
+        }
+
+        quickcheck(prop as fn(Vec<(u8, u8)>, u16) -> bool);
+    }
+
+    #[test]
+    fn test_range_errors() {
+        let mut log: SeqRingBuf<i32> = (0..4).collect();
+        log.pop_front();
+        assert_eq!(log.range_seq(0..3).err(), Some(SeqError::Evicted));
+        assert_eq!(log.range_seq(..0).err(), Some(SeqError::Evicted));
+        assert!(log.range_seq(..3).unwrap().eq(&[1, 2]));
+        assert_eq!(log.range_seq(5..).err(), Some(SeqError::Pending));
+        assert_eq!(log.range_seq(..=u64::MAX).err(), Some(SeqError::Pending));
+        assert!(log.range_seq(1..1).unwrap().eq(&[]));
+        assert!(log.range_seq(4..4).unwrap().eq(&[]));
+
+        log.clear();
+        assert_eq!(log.first_seq(), 4);
+        assert_eq!(log.get_seq(3), Err(SeqError::Evicted));
+        assert_eq!(log.push_back(4), 4);
+    }
+
+    #[test]
+    #[should_panic(expected = "range start is greater than range end")]
+    fn test_range_reversed() {
+        let log: SeqRingBuf<i32> = (0..4).collect();
+        #[allow(clippy::reversed_empty_ranges)]
+        let _ = log.range_seq(3..1);
+    }
+}
@@ -0,0 +1,487 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+//
+
+//! A ring buffer which stores a few elements inline before spilling to the
+//! heap.
+
+use core::cmp;
+use core::iter::FusedIterator;
+use core::mem;
+
+use super::array::{self, ArrayRingBuf};
+use super::{Iter, IterMut, RingBuf};
+
+/// Expands to `$body` for whichever variant of the `$kind` enum `$storage`
+/// holds, with the variant's value bound to `$ring`.
+macro_rules! dispatch {
+    ($kind:ident, $storage:expr, ref $ring:ident => $body:expr) => {
+        match $storage {
+            $kind::Inline(ref $ring) => $body,
+            $kind::Heap(ref $ring) => $body,
+        }
+    };
+    ($kind:ident, $storage:expr, ref mut $ring:ident => $body:expr) => {
+        match $storage {
+            $kind::Inline(ref mut $ring) => $body,
+            $kind::Heap(ref mut $ring) => $body,
+        }
+    };
+}
+
+#[derive(Clone)]
+enum Storage<T, const N: usize> {
+    Inline(ArrayRingBuf<T, N>),
+    Heap(RingBuf<T>),
+}
+
+/// A double-ended queue which holds up to `N` elements inline, and moves
+/// them to a heap-allocated `RingBuf` when it needs to hold more.
+///
+/// Queues which usually stay short never allocate. Once spilled, the queue
+/// grows like a `RingBuf`, and `shrink_to_fit` moves the elements back inline
+/// if they fit again. `as_slices` has the same semantics as it does for
+/// `RingBuf`.
+///
+/// # Example
+///
+/// ```rust
+/// # use ringbuf::SmallRingBuf;
+/// let mut ringbuf: SmallRingBuf<i32, 2> = SmallRingBuf::new();
+/// ringbuf.push_back(1);
+/// ringbuf.push_back(2);
+/// assert!(!ringbuf.spilled());
+///
+/// ringbuf.push_front(0);
+/// assert!(ringbuf.spilled());
+/// assert!(ringbuf.iter().eq(&[0, 1, 2]));
+/// ```
+#[derive(Clone)]
+pub struct SmallRingBuf<T, const N: usize> {
+    storage: Storage<T, N>,
+}
+
+impl<T, const N: usize> SmallRingBuf<T, N> {
+
+    /// Creates an empty `SmallRingBuf`, which does not allocate.
+    #[inline]
+    pub const fn new() -> SmallRingBuf<T, N> {
+        SmallRingBuf { storage: Storage::Inline(ArrayRingBuf::new()) }
+    }
+
+    /// Creates an empty `SmallRingBuf` which can hold at least `capacity`
+    /// elements without reallocating. It only allocates if `capacity` is
+    /// greater than `N`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use ringbuf::SmallRingBuf;
+    /// let ringbuf: SmallRingBuf<i32, 4> = SmallRingBuf::with_capacity(3);
+    /// assert!(!ringbuf.spilled());
+    /// let ringbuf: SmallRingBuf<i32, 4> = SmallRingBuf::with_capacity(5);
+    /// assert!(ringbuf.spilled());
+    /// ```
+    pub fn with_capacity(capacity: usize) -> SmallRingBuf<T, N> {
+        if capacity <= N {
+            SmallRingBuf::new()
+        } else {
+            SmallRingBuf { storage: Storage::Heap(RingBuf::with_capacity(capacity)) }
+        }
+    }
+
+    /// Returns `true` if the elements have been moved to the heap.
+    #[inline]
+    pub fn spilled(&self) -> bool {
+        matches!(self.storage, Storage::Heap(_))
+    }
+
+    /// Returns the number of elements the `SmallRingBuf` can hold without
+    /// reallocating.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        dispatch!(Storage, self.storage, ref ring => ring.capacity())
+    }
+
+    /// Returns the number of elements in the `SmallRingBuf`.
+    #[inline]
+    pub fn len(&self) -> usize {
+        dispatch!(Storage, self.storage, ref ring => ring.len())
+    }
+
+    /// Returns `true` if the `SmallRingBuf` contains no elements.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns a reference to the value at index `index`, or `None` if the
+    /// index is out of bounds.
+    #[inline]
+    pub fn get(&self, index: usize) -> Option<&T> {
+        dispatch!(Storage, self.storage, ref ring => ring.get(index))
+    }
+
+    /// Returns a mutable reference to the value at index `index`, or `None`
+    /// if the index is out of bounds.
+    #[inline]
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        dispatch!(Storage, self.storage, ref mut ring => ring.get_mut(index))
+    }
+
+    /// Swaps elements at indices `i` and `j`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if either index is out of bounds.
+    pub fn swap(&mut self, i: usize, j: usize) {
+        dispatch!(Storage, self.storage, ref mut ring => ring.swap(i, j))
+    }
+
+    /// Shortens the `SmallRingBuf`, dropping excess elements from the back.
+    pub fn truncate(&mut self, len: usize) {
+        dispatch!(Storage, self.storage, ref mut ring => ring.truncate(len))
+    }
+
+    /// Returns a pair of slices which contain the contents of the
+    /// `SmallRingBuf`, in order.
+    #[inline]
+    pub fn as_slices(&self) -> (&[T], &[T]) {
+        dispatch!(Storage, self.storage, ref ring => ring.as_slices())
+    }
+
+    /// Returns a pair of mutable slices which contain the contents of the
+    /// `SmallRingBuf`, in order.
+    #[inline]
+    pub fn as_mut_slices(&mut self) -> (&mut [T], &mut [T]) {
+        dispatch!(Storage, self.storage, ref mut ring => ring.as_mut_slices())
+    }
+
+    /// Returns a front-to-back iterator.
+    pub fn iter(&self) -> Iter<'_, T> {
+        dispatch!(Storage, self.storage, ref ring => ring.iter())
+    }
+
+    /// Returns a front-to-back iterator which returns mutable references.
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        dispatch!(Storage, self.storage, ref mut ring => ring.iter_mut())
+    }
+
+    /// Reserves capacity for at least `additional` more elements, moving the
+    /// elements to the heap if they will not fit inline.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the new capacity overflows a `usize`.
+    pub fn reserve(&mut self, additional: usize) {
+        let required = self.len().checked_add(additional).expect("capacity overflow");
+        match self.storage {
+            Storage::Inline(_) if required > N => { self.spill(required); }
+            Storage::Inline(_) => (),
+            Storage::Heap(ref mut ringbuf) => ringbuf.reserve(additional),
+        }
+    }
+
+    /// Shrinks the capacity as much as possible. If the elements fit inline
+    /// they are moved back off the heap, and the buffer is freed.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use ringbuf::SmallRingBuf;
+    /// let mut ringbuf: SmallRingBuf<i32, 2> = (0..4).collect();
+    /// assert!(ringbuf.spilled());
+    /// ringbuf.truncate(2);
+    /// ringbuf.shrink_to_fit();
+    /// assert!(!ringbuf.spilled());
+    /// assert_eq!(ringbuf.as_slices(), (&[0, 1][..], &[][..]));
+    /// ```
+    pub fn shrink_to_fit(&mut self) {
+        if let Storage::Heap(ref mut ringbuf) = self.storage {
+            if ringbuf.len() > N { return ringbuf.shrink_to_fit() }
+            let ringbuf = mem::take(ringbuf);
+            self.storage = Storage::Inline(ringbuf.into_iter().collect());
+        }
+    }
+
+    /// Clears the `SmallRingBuf`, removing all values. The capacity is
+    /// unchanged.
+    pub fn clear(&mut self) {
+        dispatch!(Storage, self.storage, ref mut ring => ring.clear())
+    }
+
+    /// Returns `true` if the `SmallRingBuf` contains an element equal to the
+    /// given value.
+    pub fn contains(&self, x: &T) -> bool where T: PartialEq {
+        dispatch!(Storage, self.storage, ref ring => ring.contains(x))
+    }
+
+    /// Provides a reference to the front element, or `None` if the
+    /// `SmallRingBuf` is empty.
+    #[inline]
+    pub fn front(&self) -> Option<&T> {
+        dispatch!(Storage, self.storage, ref ring => ring.front())
+    }
+
+    /// Provides a mutable reference to the front element, or `None` if the
+    /// `SmallRingBuf` is empty.
+    #[inline]
+    pub fn front_mut(&mut self) -> Option<&mut T> {
+        dispatch!(Storage, self.storage, ref mut ring => ring.front_mut())
+    }
+
+    /// Provides a reference to the back element, or `None` if the
+    /// `SmallRingBuf` is empty.
+    #[inline]
+    pub fn back(&self) -> Option<&T> {
+        dispatch!(Storage, self.storage, ref ring => ring.back())
+    }
+
+    /// Provides a mutable reference to the back element, or `None` if the
+    /// `SmallRingBuf` is empty.
+    #[inline]
+    pub fn back_mut(&mut self) -> Option<&mut T> {
+        dispatch!(Storage, self.storage, ref mut ring => ring.back_mut())
+    }
+
+    /// Prepends an element to the `SmallRingBuf`, moving the elements to the
+    /// heap if they no longer fit inline.
+    pub fn push_front(&mut self, value: T) {
+        let value = match self.storage {
+            Storage::Inline(ref mut array) => match array.try_push_front(value) {
+                Ok(()) => return,
+                Err(error) => error.into_inner(),
+            },
+            Storage::Heap(ref mut ringbuf) => return ringbuf.push_front(value),
+        };
+        self.spill(cmp::max(N, 1) * 2).push_front(value);
+    }
+
+    /// Removes the first element and returns it, or `None` if the
+    /// `SmallRingBuf` is empty.
+    pub fn pop_front(&mut self) -> Option<T> {
+        dispatch!(Storage, self.storage, ref mut ring => ring.pop_front())
+    }
+
+    /// Appends an element to the back of the `SmallRingBuf`, moving the
+    /// elements to the heap if they no longer fit inline.
+    pub fn push_back(&mut self, value: T) {
+        let value = match self.storage {
+            Storage::Inline(ref mut array) => match array.try_push_back(value) {
+                Ok(()) => return,
+                Err(error) => error.into_inner(),
+            },
+            Storage::Heap(ref mut ringbuf) => return ringbuf.push_back(value),
+        };
+        self.spill(cmp::max(N, 1) * 2).push_back(value);
+    }
+
+    /// Removes the last element and returns it, or `None` if the
+    /// `SmallRingBuf` is empty.
+    pub fn pop_back(&mut self) -> Option<T> {
+        dispatch!(Storage, self.storage, ref mut ring => ring.pop_back())
+    }
+
+    /// Inserts an element at `index` within the `SmallRingBuf`, moving the
+    /// elements to the heap if they no longer fit inline.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is greater than the length.
+    pub fn insert(&mut self, index: usize, value: T) {
+        match self.storage {
+            Storage::Inline(ref array) if array.is_full() => {
+                assert!(index <= N, "index out of bounds");
+                self.spill(cmp::max(N, 1) * 2).insert(index, value);
+            }
+            Storage::Inline(ref mut array) => array.insert(index, value),
+            Storage::Heap(ref mut ringbuf) => ringbuf.insert(index, value),
+        }
+    }
+
+    /// Removes and returns the element at `index`, or `None` if `index` is
+    /// out of bounds.
+    pub fn remove(&mut self, index: usize) -> Option<T> {
+        dispatch!(Storage, self.storage, ref mut ring => ring.remove(index))
+    }
+
+    /// Retains only the elements specified by the predicate.
+    pub fn retain<F>(&mut self, f: F) where F: FnMut(&T) -> bool {
+        dispatch!(Storage, self.storage, ref mut ring => ring.retain(f))
+    }
+
+    /// Retains only the elements specified by the predicate, passing a
+    /// mutable reference to it.
+    pub fn retain_mut<F>(&mut self, f: F) where F: FnMut(&mut T) -> bool {
+        dispatch!(Storage, self.storage, ref mut ring => ring.retain_mut(f))
+    }
+
+    /// Rearranges the elements so that they do not wrap around the end of
+    /// the buffer, and returns them as a single mutable slice.
+    pub fn make_contiguous(&mut self) -> &mut [T] {
+        dispatch!(Storage, self.storage, ref mut ring => ring.make_contiguous())
+    }
+
+    /// Converts the `SmallRingBuf` into a `RingBuf`, which only allocates if
+    /// the elements are inline.
+    pub fn into_ringbuf(self) -> RingBuf<T> {
+        match self.storage {
+            Storage::Inline(array) => RingBuf::from_iter(array),
+            Storage::Heap(ringbuf) => ringbuf,
+        }
+    }
+
+    /// Moves the inline elements into a new heap buffer with room for
+    /// `capacity` elements.
+    fn spill(&mut self, capacity: usize) -> &mut RingBuf<T> {
+        if let Storage::Inline(ref mut array) = self.storage {
+            let mut ringbuf = RingBuf::with_capacity(capacity);
+            ringbuf.extend(mem::take(array));
+            self.storage = Storage::Heap(ringbuf);
+        }
+        match self.storage {
+            Storage::Heap(ref mut ringbuf) => ringbuf,
+            Storage::Inline(_) => unreachable!(),
+        }
+    }
+}
+
+deque_impls!(SmallRingBuf<T, const N: usize>);
+
+impl<T, const N: usize> From<RingBuf<T>> for SmallRingBuf<T, N> {
+    /// Wraps the `RingBuf` without moving its elements.
+    fn from(ringbuf: RingBuf<T>) -> SmallRingBuf<T, N> {
+        SmallRingBuf { storage: Storage::Heap(ringbuf) }
+    }
+}
+
+impl<T, const N: usize> Extend<T> for SmallRingBuf<T, N> {
+    fn extend<I: IntoIterator<Item=T>>(&mut self, iterable: I) {
+        let iterator = iterable.into_iter();
+        let (lower, _) = iterator.size_hint();
+        self.reserve(lower);
+        for value in iterator {
+            self.push_back(value);
+        }
+    }
+}
+
+impl<T, const N: usize> IntoIterator for SmallRingBuf<T, N> {
+    type Item = T;
+    type IntoIter = IntoIter<T, N>;
+
+    fn into_iter(self) -> IntoIter<T, N> {
+        match self.storage {
+            Storage::Inline(array) => IntoIter { inner: Inner::Inline(array.into_iter()) },
+            Storage::Heap(ringbuf) => IntoIter { inner: Inner::Heap(ringbuf.into_iter()) },
+        }
+    }
+}
+
+/// An iterator that moves out of a `SmallRingBuf`.
+#[derive(Clone, Debug)]
+pub struct IntoIter<T, const N: usize> {
+    inner: Inner<T, N>,
+}
+
+#[derive(Clone, Debug)]
+enum Inner<T, const N: usize> {
+    Inline(array::IntoIter<T, N>),
+    Heap(super::IntoIter<T>),
+}
+
+impl<T, const N: usize> Iterator for IntoIter<T, N> {
+    type Item = T;
+
+    #[inline]
+    fn next(&mut self) -> Option<T> {
+        dispatch!(Inner, self.inner, ref mut iter => iter.next())
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        dispatch!(Inner, self.inner, ref iter => iter.size_hint())
+    }
+}
+
+impl<T, const N: usize> DoubleEndedIterator for IntoIter<T, N> {
+    #[inline]
+    fn next_back(&mut self) -> Option<T> {
+        dispatch!(Inner, self.inner, ref mut iter => iter.next_back())
+    }
+}
+
+impl<T, const N: usize> ExactSizeIterator for IntoIter<T, N> {}
+
+impl<T, const N: usize> FusedIterator for IntoIter<T, N> {}
+
+#[cfg(test)]
+mod checks {
+    use std::collections::VecDeque;
+    use std::rc::Rc;
+
+    use super::SmallRingBuf;
+    use crate::model::{self, Model};
+
+    impl Model for SmallRingBuf<i32, 4> {
+        fn apply(&mut self, model: &mut VecDeque<i32>, op: u8, value: i32, index: usize) -> bool {
+            match op % 3 {
+                0 => {
+                    self.insert(index, value);
+                    model.insert(index, value);
+                    true
+                }
+                1 => self.remove(index) == model.remove(index),
+                _ => {
+                    self.shrink_to_fit();
+                    self.spilled() == (model.len() > 4)
+                }
+            }
+        }
+
+        fn check(&self, _: &VecDeque<i32>) -> bool {
+            self.len() <= self.capacity()
+        }
+    }
+
+    #[test]
+    fn check_model() {
+        model::check_model::<SmallRingBuf<i32, 4>>();
+    }
+
+    #[test]
+    fn test_spill() {
+        let mut ringbuf: SmallRingBuf<i32, 3> = SmallRingBuf::new();
+        ringbuf.extend([1, 2, 3]);
+        assert!(!ringbuf.spilled());
+        assert_eq!(ringbuf.capacity(), 3);
+
+        ringbuf.insert(1, 9);
+        assert!(ringbuf.spilled());
+        assert!(ringbuf.iter().eq(&[1, 9, 2, 3]));
+
+        ringbuf.clear();
+        ringbuf.reserve(2);
+        assert!(ringbuf.spilled());
+        ringbuf.shrink_to_fit();
+        assert!(!ringbuf.spilled());
+
+        let mut ringbuf: SmallRingBuf<i32, 0> = SmallRingBuf::new();
+        ringbuf.push_front(1);
+        assert!(ringbuf.spilled());
+        assert_eq!(ringbuf.into_ringbuf().into_vec(), vec![1]);
+    }
+
+    #[test]
+    fn test_drop_items() {
+        model::check_drop_items::<SmallRingBuf<Rc<()>, 2>>(3);
+    }
+
+    #[test]
+    fn test_zero_sized() {
+        model::check_zero_sized::<SmallRingBuf<(), 2>>(5);
+    }
+}
@@ -0,0 +1,644 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+//
+
+//! A wait-free single-producer, single-consumer queue which shares the
+//! buffer of a `BoundedRingBuf` between two threads.
+
+use core::cmp;
+use core::fmt;
+use core::marker::PhantomData;
+use core::mem;
+use core::mem::MaybeUninit;
+use core::ops::Deref;
+use core::ptr;
+use core::slice;
+use core::sync::atomic::{AtomicUsize, Ordering};
+use alloc::sync::Arc;
+
+use super::{BoundedRingBuf, Global, GrowthPolicy, RingBuf, TryPushError};
+
+/// Pads and aligns a value to the size of a cache line, so that the head and
+/// tail indices written by different threads do not share a line.
+///
+/// Modern x86-64 and aarch64 CPUs prefetch cache lines in pairs, so the
+/// value is aligned to 128 bytes.
+#[repr(align(128))]
+pub(crate) struct CachePadded<T>(pub(crate) T);
+
+impl<T> Deref for CachePadded<T> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+/// The state shared by the producer and consumer.
+///
+/// `head` and `tail` are indices in `[0, 2 * cap)`, so that a full queue
+/// (`tail - head == cap`) can be told apart from an empty one
+/// (`tail == head`). The slot of an index is the index modulo `cap`.
+struct Shared<T> {
+
+    /// The index of the next element to pop. Written only by the consumer.
+    head: CachePadded<AtomicUsize>,
+
+    /// The index of the next slot to push into. Written only by the producer.
+    tail: CachePadded<AtomicUsize>,
+
+    /// Pointer to the start of the buffer.
+    ptr: *mut T,
+
+    /// The number of slots in the queue.
+    cap: usize,
+
+    /// The capacity of the allocation backing the buffer.
+    buf_cap: usize,
+}
+
+unsafe impl<T: Send> Send for Shared<T> {}
+unsafe impl<T: Send> Sync for Shared<T> {}
+
+impl<T> Shared<T> {
+
+    /// Returns the number of elements between `head` and `tail`.
+    #[inline]
+    fn len(&self, head: usize, tail: usize) -> usize {
+        if tail >= head { tail - head } else { 2 * self.cap - (head - tail) }
+    }
+
+    /// Returns the index `n` places after `index`.
+    #[inline]
+    fn advance(&self, index: usize, n: usize) -> usize {
+        // The order of these operations preserves numerical stability
+        if index >= 2 * self.cap - n {
+            index - (2 * self.cap - n)
+        } else {
+            index + n
+        }
+    }
+
+    /// Returns the buffer offset of the slot of `index`.
+    #[inline]
+    fn slot(&self, index: usize) -> usize {
+        if index >= self.cap { index - self.cap } else { index }
+    }
+
+    /// Calculates the start offset and length of the two contiguous runs of
+    /// `len` slots starting at `index`.
+    #[inline]
+    fn runs(&self, index: usize, len: usize) -> (usize, usize, usize) {
+        let offset = self.slot(index);
+        let len1 = cmp::min(len, self.cap - offset);
+        (offset, len1, len - len1)
+    }
+}
+
+impl<T> Drop for Shared<T> {
+    fn drop(&mut self) {
+        // Hand the remaining elements back to a ring buffer, which drops them
+        // and frees the buffer.
+        let head = *self.head.0.get_mut();
+        let tail = *self.tail.0.get_mut();
+        drop(RingBuf {
+            lo: self.slot(head),
+            len: self.len(head, tail),
+            cap: self.buf_cap,
+            ptr: self.ptr,
+            alloc: Global,
+            growth: GrowthPolicy::Double,
+            shrink_divisor: None,
+            init: 0,
+            marker: PhantomData,
+        });
+    }
+}
+
+impl<T> BoundedRingBuf<T> {
+
+    /// Splits the ring into a `Producer` and a `Consumer`, which can be sent
+    /// to different threads to use the ring as a wait-free single-producer,
+    /// single-consumer queue.
+    ///
+    /// The elements in the ring stay in the queue, and the buffer is reused
+    /// without copying.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the capacity is greater than `usize::MAX / 2`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use ringbuf::BoundedRingBuf;
+    /// use std::thread;
+    ///
+    /// let (mut producer, mut consumer) = BoundedRingBuf::with_capacity(16).split();
+    /// let handle = thread::spawn(move || {
+    ///     for i in 0..100 {
+    ///         while producer.try_push(i).is_err() {}
+    ///     }
+    /// });
+    ///
+    /// let mut received = Vec::new();
+    /// while received.len() < 100 {
+    ///     received.extend(consumer.try_pop());
+    /// }
+    /// handle.join().unwrap();
+    /// assert_eq!(received, (0..100).collect::<Vec<_>>());
+    /// ```
+    pub fn split(self) -> (Producer<T>, Consumer<T>) {
+        let cap = self.capacity();
+        assert!(cap <= usize::MAX / 2, "capacity overflow");
+
+        let mut ringbuf = self.into_ringbuf();
+        if mem::size_of::<T>() != 0 && ringbuf.cap != cap {
+            // The slots wrap at `cap`, so the buffer must hold exactly that
+            // many.
+            let mut exact = RingBuf::with_capacity(cap);
+            exact.append(&mut ringbuf);
+            ringbuf = exact;
+        }
+        ringbuf.reset();
+        let ringbuf = core::mem::ManuallyDrop::new(ringbuf);
+
+        let shared = Arc::new(Shared {
+            head: CachePadded(AtomicUsize::new(0)),
+            tail: CachePadded(AtomicUsize::new(ringbuf.len)),
+            ptr: ringbuf.ptr,
+            cap,
+            buf_cap: ringbuf.cap,
+        });
+        let producer = Producer { shared: shared.clone(), head: 0, tail: ringbuf.len };
+        let consumer = Consumer {
+            shared,
+            head: 0,
+            tail: ringbuf.len,
+            marker: PhantomData,
+        };
+        (producer, consumer)
+    }
+}
+
+/// The pushing half of a single-producer, single-consumer queue.
+///
+/// Created by `BoundedRingBuf::split`.
+pub struct Producer<T> {
+    shared: Arc<Shared<T>>,
+
+    /// The last observed value of the consumer's head index.
+    head: usize,
+
+    /// The producer's tail index.
+    tail: usize,
+}
+
+impl<T> Producer<T> {
+
+    /// Returns the number of elements the queue can hold.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.shared.cap
+    }
+
+    /// Returns the number of elements in the queue.
+    ///
+    /// The consumer may concurrently pop elements, so this is an upper bound.
+    #[inline]
+    pub fn len(&self) -> usize {
+        let head = self.shared.head.load(Ordering::Acquire);
+        self.shared.len(head, self.tail)
+    }
+
+    /// Returns `true` if the queue is empty.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns `true` if the queue is full.
+    #[inline]
+    pub fn is_full(&self) -> bool {
+        self.len() == self.capacity()
+    }
+
+    /// Returns the number of free slots, refreshing the cached head index
+    /// from the consumer only if the cached value shows fewer than `wanted`.
+    #[inline]
+    fn free(&mut self, wanted: usize) -> usize {
+        let mut free = self.shared.cap - self.shared.len(self.head, self.tail);
+        if free < wanted {
+            self.head = self.shared.head.load(Ordering::Acquire);
+            free = self.shared.cap - self.shared.len(self.head, self.tail);
+        }
+        free
+    }
+
+    /// Push an element onto the back of the queue, or return it in an error
+    /// if the queue is full.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use ringbuf::{BoundedRingBuf, TryPushError};
+    /// let (mut producer, mut consumer) = BoundedRingBuf::with_capacity(1).split();
+    /// assert!(producer.try_push(1).is_ok());
+    /// assert_eq!(producer.try_push(2), Err(TryPushError::Full(2)));
+    /// assert_eq!(consumer.try_pop(), Some(1));
+    /// ```
+    pub fn try_push(&mut self, value: T) -> Result<(), TryPushError<T>> {
+        if self.free(1) == 0 { return Err(TryPushError::Full(value)) }
+        unsafe {
+            let offset = self.shared.slot(self.tail);
+            ptr::write(self.shared.ptr.add(offset), value);
+            self.advance(1);
+        }
+        Ok(())
+    }
+
+    /// Copy as many elements from `values` as fit into the back of the
+    /// queue, and return the number copied.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use ringbuf::BoundedRingBuf;
+    /// let (mut producer, mut consumer) = BoundedRingBuf::with_capacity(4).split();
+    /// assert_eq!(producer.push_slice(&[1, 2, 3, 4, 5]), 4);
+    /// let mut out = [0; 8];
+    /// assert_eq!(consumer.pop_slice(&mut out), 4);
+    /// assert_eq!(&out[..4], &[1, 2, 3, 4]);
+    /// ```
+    pub fn push_slice(&mut self, values: &[T]) -> usize where T: Copy {
+        let (slice1, slice2) = self.vacant_slices();
+        let len1 = cmp::min(values.len(), slice1.len());
+        let len2 = cmp::min(values.len() - len1, slice2.len());
+        unsafe {
+            ptr::copy_nonoverlapping(values.as_ptr(), slice1.as_mut_ptr() as *mut T, len1);
+            ptr::copy_nonoverlapping(values.as_ptr().add(len1),
+                                     slice2.as_mut_ptr() as *mut T,
+                                     len2);
+            self.advance(len1 + len2);
+        }
+        len1 + len2
+    }
+
+    /// Work with the free space of the queue as a pair of slices of
+    /// uninitialized slots, in the order they will be pushed.
+    ///
+    /// Either or both slices may be empty. After writing elements into the
+    /// start of the slots, call `advance` to push them.
+    pub fn vacant_slices(&mut self) -> (&mut [MaybeUninit<T>], &mut [MaybeUninit<T>]) {
+        let free = self.free(self.shared.cap);
+        let (offset, len1, len2) = self.shared.runs(self.tail, free);
+        unsafe {
+            let ptr = self.shared.ptr as *mut MaybeUninit<T>;
+            (slice::from_raw_parts_mut(ptr.add(offset), len1),
+             slice::from_raw_parts_mut(ptr, len2))
+        }
+    }
+
+    /// Push the first `n` slots returned by `vacant_slices` onto the queue.
+    ///
+    /// # Safety
+    ///
+    /// The first `n` vacant slots must have been initialized, and `n` must
+    /// not be greater than the number of vacant slots.
+    pub unsafe fn advance(&mut self, n: usize) {
+        self.tail = self.shared.advance(self.tail, n);
+        self.shared.tail.store(self.tail, Ordering::Release);
+    }
+}
+
+impl<T> fmt::Debug for Producer<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Producer")
+         .field("len", &self.len())
+         .field("capacity", &self.capacity())
+         .finish()
+    }
+}
+
+/// The popping half of a single-producer, single-consumer queue.
+///
+/// Created by `BoundedRingBuf::split`.
+pub struct Consumer<T> {
+    shared: Arc<Shared<T>>,
+
+    /// The consumer's head index.
+    head: usize,
+
+    /// The last observed value of the producer's tail index.
+    tail: usize,
+
+    /// The consumer hands out references to the elements.
+    marker: PhantomData<T>,
+}
+
+impl<T> Consumer<T> {
+
+    /// Returns the number of elements the queue can hold.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.shared.cap
+    }
+
+    /// Returns the number of elements in the queue.
+    ///
+    /// The producer may concurrently push elements, so this is a lower bound.
+    #[inline]
+    pub fn len(&self) -> usize {
+        let tail = self.shared.tail.load(Ordering::Acquire);
+        self.shared.len(self.head, tail)
+    }
+
+    /// Returns `true` if the queue is empty.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the number of available elements, refreshing the cached tail
+    /// index from the producer only if the cached value shows fewer than
+    /// `wanted`.
+    #[inline]
+    fn available(&mut self, wanted: usize) -> usize {
+        let mut len = self.shared.len(self.head, self.tail);
+        if len < wanted {
+            self.tail = self.shared.tail.load(Ordering::Acquire);
+            len = self.shared.len(self.head, self.tail);
+        }
+        len
+    }
+
+    /// Remove the first element of the queue and return it, or `None` if
+    /// the queue is empty.
+    pub fn try_pop(&mut self) -> Option<T> {
+        if self.available(1) == 0 { return None }
+        unsafe {
+            let offset = self.shared.slot(self.head);
+            let value = ptr::read(self.shared.ptr.add(offset));
+            self.release(1);
+            Some(value)
+        }
+    }
+
+    /// Copy as many elements from the front of the queue as fit into `out`,
+    /// remove them from the queue, and return the number copied.
+    pub fn pop_slice(&mut self, out: &mut [T]) -> usize where T: Copy {
+        let (slice1, slice2) = self.as_slices();
+        let len1 = cmp::min(out.len(), slice1.len());
+        let len2 = cmp::min(out.len() - len1, slice2.len());
+        out[..len1].copy_from_slice(&slice1[..len1]);
+        out[len1..len1 + len2].copy_from_slice(&slice2[..len2]);
+        unsafe { self.release(len1 + len2); }
+        len1 + len2
+    }
+
+    /// Work with the elements of the queue as a pair of slices, in order.
+    ///
+    /// Either or both slices may be empty. The producer may concurrently push
+    /// more elements, which will not be included.
+    pub fn as_slices(&mut self) -> (&[T], &[T]) {
+        let (slice1, slice2) = self.as_mut_slices();
+        (slice1, slice2)
+    }
+
+    /// Work with the elements of the queue as a pair of mutable slices, in
+    /// order.
+    ///
+    /// Either or both slices may be empty.
+    pub fn as_mut_slices(&mut self) -> (&mut [T], &mut [T]) {
+        let len = self.available(self.shared.cap);
+        let (offset, len1, len2) = self.shared.runs(self.head, len);
+        unsafe {
+            (slice::from_raw_parts_mut(self.shared.ptr.add(offset), len1),
+             slice::from_raw_parts_mut(self.shared.ptr, len2))
+        }
+    }
+
+    /// Remove and drop up to `n` elements from the front of the queue, and
+    /// return the number removed.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use ringbuf::BoundedRingBuf;
+    /// let (mut producer, mut consumer) = BoundedRingBuf::with_capacity(4).split();
+    /// producer.push_slice(&[1, 2, 3]);
+    /// assert_eq!(consumer.as_slices().0.first(), Some(&1));
+    /// assert_eq!(consumer.skip(2), 2);
+    /// assert_eq!(consumer.try_pop(), Some(3));
+    /// ```
+    pub fn skip(&mut self, n: usize) -> usize {
+        let (slice1, slice2) = self.as_mut_slices();
+        let len1 = cmp::min(n, slice1.len());
+        let len2 = cmp::min(n - len1, slice2.len());
+        let (run1, run2) = (&mut slice1[..len1] as *mut [T], &mut slice2[..len2] as *mut [T]);
+        // The slots must not be released until the elements have been
+        // dropped, or the producer could write into them concurrently.
+        let guard = ReleaseOnDrop { consumer: self, n: len1 + len2 };
+        unsafe {
+            ptr::drop_in_place(run1);
+            ptr::drop_in_place(run2);
+        }
+        drop(guard);
+        len1 + len2
+    }
+
+    /// Hand the first `n` slots back to the producer.
+    #[inline]
+    unsafe fn release(&mut self, n: usize) {
+        self.head = self.shared.advance(self.head, n);
+        self.shared.head.store(self.head, Ordering::Release);
+    }
+}
+
+/// Releases slots when dropped, including while unwinding from a panicking
+/// destructor, so that the remaining elements are leaked instead of being
+/// dropped twice.
+struct ReleaseOnDrop<'a, T> {
+    consumer: &'a mut Consumer<T>,
+    n: usize,
+}
+
+impl<'a, T> Drop for ReleaseOnDrop<'a, T> {
+    fn drop(&mut self) {
+        unsafe { self.consumer.release(self.n) }
+    }
+}
+
+impl<T> fmt::Debug for Consumer<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Consumer")
+         .field("len", &self.len())
+         .field("capacity", &self.capacity())
+         .finish()
+    }
+}
+
+#[cfg(test)]
+mod checks {
+    use std::collections::VecDeque;
+    use std::sync::Arc;
+    use std::thread;
+
+    use quickcheck::quickcheck;
+
+    use crate::{BoundedRingBuf, RingBuf};
+
+    #[test]
+    fn check_sequential_model() {
+        // Each op pushes `Some` values and pops on `None`.
+        fn prop(initial: RingBuf<i32>, ops: Vec<Option<i32>>, extra: u8) -> bool {
+            let mut model: VecDeque<i32> = initial.iter().cloned().collect();
+            let capacity = initial.len() + extra as usize % 8;
+            let mut ring = BoundedRingBuf::with_capacity(capacity);
+            ring.extend(initial);
+            let (mut producer, mut consumer) = ring.split();
+
+            for op in ops {
+                match op {
+                    Some(value) => {
+                        let pushed = producer.try_push(value).is_ok();
+                        if pushed != (model.len() < capacity) { return false }
+                        if pushed { model.push_back(value); }
+                    }
+                    None => {
+                        if consumer.try_pop() != model.pop_front() { return false }
+                    }
+                }
+                if producer.len() != model.len() || consumer.len() != model.len() {
+                    return false;
+                }
+            }
+            let (slice1, slice2) = consumer.as_slices();
+            slice1.iter().chain(slice2).eq(model.iter())
+        }
+
+        quickcheck(prop as fn(RingBuf<i32>, Vec<Option<i32>>, u8) -> bool);
+    }
+
+    #[test]
+    fn check_slices() {
+        fn prop(pushes: Vec<Vec<u8>>, capacity: u8) -> bool {
+            let capacity = capacity as usize % 16;
+            let (mut producer, mut consumer) = BoundedRingBuf::with_capacity(capacity).split();
+            let mut model = VecDeque::new();
+
+            for values in pushes {
+                let pushed = producer.push_slice(&values);
+                if pushed != std::cmp::min(values.len(), capacity - model.len()) {
+                    return false;
+                }
+                model.extend(&values[..pushed]);
+
+                let mut out = vec![0; values.len() / 2];
+                let popped = consumer.pop_slice(&mut out);
+                let expected: Vec<u8> = model.drain(..popped).collect();
+                if out[..popped] != expected[..] { return false }
+            }
+            true
+        }
+
+        quickcheck(prop as fn(Vec<Vec<u8>>, u8) -> bool);
+    }
+
+    #[test]
+    fn check_drop_items() {
+        // This is testing memory safety
+        fn prop(items: Vec<i32>, pops: u8) -> bool {
+            let (mut producer, mut consumer) = BoundedRingBuf::with_capacity(items.len()).split();
+            for item in items {
+                assert!(producer.try_push(Box::new(item)).is_ok());
+            }
+            consumer.skip(pops as usize);
+            drop(producer);
+            true
+        }
+
+        quickcheck(prop as fn(Vec<i32>, u8) -> bool);
+    }
+
+    #[test]
+    fn test_threaded_skip() {
+        // The producer spins on a full queue while the consumer drops
+        // elements with `skip`, so slots are reused as soon as they are
+        // released.
+        const COUNT: usize = 100_000;
+
+        let item = Arc::new(());
+        let (mut producer, mut consumer) = BoundedRingBuf::with_capacity(4).split();
+        let handle = {
+            let item = item.clone();
+            thread::spawn(move || {
+                for _ in 0..COUNT {
+                    let mut value = item.clone();
+                    while let Err(error) = producer.try_push(value) {
+                        value = error.into_inner();
+                        thread::yield_now();
+                    }
+                }
+            })
+        };
+
+        let mut skipped = 0;
+        while skipped < COUNT {
+            match consumer.skip(3) {
+                0 => thread::yield_now(),
+                n => skipped += n,
+            }
+        }
+        handle.join().unwrap();
+        assert!(consumer.is_empty());
+        assert_eq!(Arc::strong_count(&item), 1);
+    }
+
+    #[test]
+    fn test_threaded_stress() {
+        const COUNT: u64 = 200_000;
+
+        let (mut producer, mut consumer) = BoundedRingBuf::with_capacity(127).split();
+        let handle = thread::spawn(move || {
+            let mut next = 0;
+            let mut batch = Vec::new();
+            while next < COUNT {
+                // Alternate between single pushes and batches.
+                let pushed = if next % 3 == 0 {
+                    producer.try_push(next).is_ok() as u64
+                } else {
+                    batch.clear();
+                    batch.extend(next..std::cmp::min(next + 50, COUNT));
+                    producer.push_slice(&batch) as u64
+                };
+                if pushed == 0 { thread::yield_now(); }
+                next += pushed;
+            }
+        });
+
+        let mut expected = 0;
+        let mut out = [0; 64];
+        while expected < COUNT {
+            let popped = if expected % 2 == 0 {
+                match consumer.try_pop() {
+                    Some(value) => { out[0] = value; 1 }
+                    None => 0,
+                }
+            } else {
+                consumer.pop_slice(&mut out)
+            };
+            if popped == 0 { thread::yield_now(); }
+            for &value in &out[..popped] {
+                assert_eq!(value, expected);
+                expected += 1;
+            }
+        }
+        handle.join().unwrap();
+        assert!(consumer.is_empty());
+    }
+}